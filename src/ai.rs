@@ -3,11 +3,22 @@
 use crate::{
     game_outcome::GameOutcome,
     gameboard::{GameBoard, BoardSpaceLocation, BoardSpace},
-    active_player::ActivePlayer
+    active_player::ActivePlayer,
+    variants::{Variant, Move},
+    multiplayer::MultiplayerGame
 };
 use rand::Rng;
+
+//declare search module which contains
+//the time-limited search used to play variants
+pub mod search;
+
+//declare paranoid module which contains
+//the time-limited search used to play games with more than two players
+pub mod paranoid;
+
 /// Represents an AI player
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 pub struct AiPlayer{
     difficulty: f64
 }
@@ -79,7 +90,7 @@ impl AiPlayer{
     pub fn mistake_chance(&self) -> f64
     {
         // return the mistake chance with bounds checking to ensure value is within valid range
-        (1.0 - self.difficulty).min(1.0).max(0.0)
+        (1.0 - self.difficulty).clamp(0.0, 1.0)
     }
 
     /// Plays a turn on the specified game board
@@ -94,6 +105,116 @@ impl AiPlayer{
     /// If a move cannot be played (for example, because the game is finished), this method
     /// will return `Err(AiError)` with an appropriate [AiError] describing the issue.
     pub fn do_turn(&self, board: &GameBoard, player: &ActivePlayer) -> Result<GameBoard, AiError>
+    {
+        let new_location = self.choose_move(board, player)?;
+
+        // Clone the input board; this gets a new, mutable board to play move on
+        let mut new_board = board.clone();
+
+        // play next move and return modified board
+        *new_board.space_mut(new_location) = player.get_board_space();
+        Ok(new_board)
+    }
+
+    /// Chooses a move to play on the specified game board, without playing it
+    /// 
+    /// Which turn to choose for (player X or player O) is determined by `player`
+    /// 
+    /// If a move can be chosen successfully, this method will return `Ok(location)`
+    /// where `location` is the [BoardSpaceLocation] the AI would claim.
+    /// 
+    /// If a move cannot be chosen (for example, because the game is finished), this method
+    /// will return `Err(AiError)` with an appropriate [AiError] describing the issue.
+    pub fn choose_move(&self, board: &GameBoard, player: &ActivePlayer) -> Result<BoardSpaceLocation, AiError>
+    {
+        let possible_moves = Self::evaluate_moves(board, player)?;
+        Ok(self.pick_move(possible_moves))
+    }
+
+    /// Chooses a move for the active player of the specified [Variant], without playing it
+    /// 
+    /// Moves are evaluated using [search::evaluate_moves], and the same mistake chance
+    /// applies as for [AiPlayer::choose_move].
+    /// 
+    /// If a move cannot be chosen (for example, because the game is finished), this method
+    /// will return `Err(AiError)` with an appropriate [AiError] describing the issue.
+    pub fn choose_variant_move(&self, variant: &dyn Variant) -> Result<Move, AiError>
+    {
+        let possible_moves = search::evaluate_moves(variant, search::TIME_LIMIT)?;
+        Ok(self.pick_move(possible_moves))
+    }
+
+    /// Decides whether the active player of the specified [Variant] should swap sides with their
+    /// opponent (under the swap rule), rather than reply to their opponent's first move
+    /// 
+    /// Sides are swapped if the best move found by [search::evaluate_moves] still leaves the
+    /// active player behind. With the same mistake chance as for [AiPlayer::choose_move], the
+    /// opposite decision is made instead.
+    /// 
+    /// If the position cannot be evaluated (for example, because the game is finished), this
+    /// method will return `Err(AiError)` with an appropriate [AiError] describing the issue.
+    pub fn choose_swap(&self, variant: &dyn Variant) -> Result<bool, AiError>
+    {
+        let possible_moves = search::evaluate_moves(variant, search::TIME_LIMIT)?;
+        let best_score = possible_moves.last().unwrap().1;
+
+        Ok((best_score < 0.0) != self.do_mistake())
+    }
+
+    /// Chooses a cell to claim for the active player of the specified [MultiplayerGame],
+    /// without claiming it
+    /// 
+    /// Moves are evaluated using [paranoid::evaluate_moves], and the same mistake chance
+    /// applies as for [AiPlayer::choose_move].
+    /// 
+    /// If a move cannot be chosen (for example, because the game is finished), this method
+    /// will return `Err(AiError)` with an appropriate [AiError] describing the issue.
+    pub fn choose_multiplayer_move(&self, game: &MultiplayerGame) -> Result<usize, AiError>
+    {
+        let possible_moves = paranoid::evaluate_moves(game, search::TIME_LIMIT)?;
+        Ok(self.pick_move(possible_moves))
+    }
+
+    /// Returns true, with a probability of the [mistake chance](AiPlayer::mistake_chance),
+    /// if the AI should make a mistake this turn
+    fn do_mistake(&self) -> bool
+    {
+        // generate a number from 0 to (not including) 1
+        // if the mistake chance is greater than this value, do mistake; otherwise play optimally
+        // 1.0 mistake chance is always greater than generated value
+        // 0.0 mistake chance is always less than or equal to (thus not greater than) generated value
+        self.mistake_chance() > rand::thread_rng().gen_range(0.0..1.0)
+    }
+
+    /// Picks a move from the given moves and their scores, sorted from worst to best
+    /// 
+    /// The worst move is picked if the AI [makes a mistake](AiPlayer::do_mistake);
+    /// otherwise the best move is picked.
+    /// 
+    ///# Panics
+    /// 
+    /// This method panics if `possible_moves` is empty
+    fn pick_move<T>(&self, mut possible_moves: Vec<(T, f64)>) -> T
+    {
+        let index = if self.do_mistake() {
+            // play worst move
+            0
+        } else {
+            // play optimally if not making a mistake
+            possible_moves.len() - 1
+        };
+        possible_moves.swap_remove(index).0
+    }
+
+    /// Evaluates every possible move on the specified game board for the specified player
+    /// 
+    /// Returns the location of each possible move along with its win score, sorted from 
+    /// lowest score (worst move) to highest score (best move). Win scores are within the
+    /// range `[-1.0, 1.0]`; positive scores favour `player`, negative scores favour their opponent.
+    /// 
+    /// If no moves can be evaluated (for example, because the game is finished), this method
+    /// will return `Err(AiError)` with an appropriate [AiError] describing the issue.
+    pub fn evaluate_moves(board: &GameBoard, player: &ActivePlayer) -> Result<Vec<(BoardSpaceLocation, f64)>, AiError>
     {
 
         // return early if game is already finished
@@ -127,28 +248,9 @@ impl AiPlayer{
             }
         });
 
-        // generate a number from 0 to (not including) 1
-        // if the mistake chance is greater than this value, do mistake; otherwise play optimally
-        // 1.0 mistake chance is always greater than generated value
-        // 0.0 mistake chance is always less than or equal to (thus not greater than) generated value
-        let do_mistake = self.mistake_chance() > rand::thread_rng().gen_range(0.0..1.0);
-        
-        // determine next move 
-        let next_move = if do_mistake {
-            // play worst move
-            possible_moves.first().unwrap()
-        } else {
-            // play optimally if do_mistake is false
-            possible_moves.last().unwrap()
-        };
-
-        // Clone the input board; this gets a new, mutable board to play move on
-        let mut new_board = board.clone();
-
-        // play next move and return modified board
-        let new_location = *next_move.new_location();
-        *new_board.space_mut(new_location) = player.get_board_space();
-        Ok(new_board)
+        Ok(possible_moves.into_iter().map(|possible_move|{
+            (*possible_move.new_location(), possible_move.win_score())
+        }).collect())
     }
 }
 
@@ -238,7 +340,7 @@ impl PossibleMove{
     /// 
     /// Represented as a float from -1 to 1, where -1 is least preferable, 1 is most preferable
     fn calculate_win_score(
-        sub_moves: &Vec<PossibleMove>,
+        sub_moves: &[PossibleMove],
         board: &GameBoard,
         winning_player: &ActivePlayer
     ) -> f64
@@ -0,0 +1,147 @@
+//! Time-limited paranoid search for games with more than two players
+//!
+//! In a paranoid search, the searching player assumes every other player is working together
+//! against them. That turns the game back into a two sided one (the searching player against
+//! everyone else), so the same alpha-beta pruning and iterative deepening as [search](super::search)
+//! can be used, with positions at the depth limit scored by [MultiplayerGame::heuristic].
+
+use std::time::{Duration, Instant};
+
+use rand::seq::SliceRandom;
+
+use crate::multiplayer::{MultiplayerGame, MultiplayerOutcome, Seat};
+
+use super::{
+    AiError,
+    search::{HEURISTIC_WEIGHT, PLY_PENALTY}
+};
+
+/// Evaluates every legal move in the given game for its active player
+///
+/// Returns each cell that can be claimed along with its score, sorted from lowest score
+/// (worst move) to highest score (best move). Scores are within the range `[-1.0, 1.0]`;
+/// positive scores favour the active player, negative scores favour any of their opponents.
+/// Moves with equal scores are in random order.
+///
+/// The search always completes at least one move deep, even if that takes longer than
+/// `time_limit`.
+///
+/// If no moves can be evaluated (for example, because the game is finished), this function
+/// will return `Err(AiError)` with an appropriate [AiError] describing the issue.
+pub fn evaluate_moves(game: &MultiplayerGame, time_limit: Duration) -> Result<Vec<(usize, f64)>, AiError>
+{
+    if game.outcome().game_finished() {
+        return Err(AiError::GameFinished);
+    }
+
+    let mut moves = game.legal_moves();
+    if moves.is_empty() {
+        return Err(AiError::NoMovesFound);
+    }
+    moves.shuffle(&mut rand::thread_rng());
+
+    let mut search = ParanoidSearch{
+        player: game.active_seat(),
+        deadline: Instant::now() + time_limit,
+        can_abort: false,
+        depth_limited: false
+    };
+
+    let mut evaluated = Vec::new();
+    for depth in 1.. {
+        search.depth_limited = false;
+        match search.evaluate_root(game, &moves, depth) {
+            Some(scores) => evaluated = scores,
+            // ran out of time; keep the scores from the previous depth
+            None => break
+        }
+        // later depths may be abandoned, as there is already a result to fall back on
+        search.can_abort = true;
+
+        // stop once every line has been searched to the end of the game
+        if !search.depth_limited || Instant::now() >= search.deadline {
+            break;
+        }
+    }
+
+    // stable sort keeps equal moves in their shuffled order
+    evaluated.sort_by(|(_, score_a), (_, score_b)|{
+        score_a.partial_cmp(score_b).unwrap_or(std::cmp::Ordering::Equal)
+    });
+    Ok(evaluated)
+}
+
+/// The state of a paranoid search in progress
+struct ParanoidSearch {
+    /// The player the search is choosing a move for
+    player: Seat,
+    deadline: Instant,
+    /// true if the search should be abandoned once the deadline has passed
+    can_abort: bool,
+    /// true if any position was scored by the heuristic rather than the game's outcome
+    depth_limited: bool
+}
+
+impl ParanoidSearch {
+
+    /// Scores each of the given moves, searching `depth` moves deep
+    ///
+    /// Returns `None` if the search was abandoned
+    fn evaluate_root(&mut self, game: &MultiplayerGame, moves: &[usize], depth: u32) -> Option<Vec<(usize, f64)>>
+    {
+        moves.iter().map(|cell|{
+            let mut child = game.clone();
+            child.play_move(*cell);
+            // each move gets a full window so that every score is exact, not just the best one
+            let score = self.paranoid(&child, depth - 1, 1, -f64::INFINITY, f64::INFINITY)?;
+            Some((*cell, score))
+        }).collect()
+    }
+
+    /// Returns the score of the given position for the searching player, searching `depth`
+    /// moves deep
+    ///
+    /// The searching player picks the highest scoring move, and every other player the lowest.
+    /// `ply` is the number of moves played since the start of the search.
+    /// Returns `None` if the search was abandoned.
+    fn paranoid(&mut self, game: &MultiplayerGame, depth: u32, ply: u32, mut alpha: f64, mut beta: f64) -> Option<f64>
+    {
+        match game.outcome() {
+            MultiplayerOutcome::Win(winner) => {
+                let score = 1.0 - ply as f64 * PLY_PENALTY;
+                return Some(if winner == self.player {score} else {-score});
+            },
+            MultiplayerOutcome::Draw => return Some(0.0),
+            MultiplayerOutcome::Incomplete => {}
+        }
+
+        if depth == 0 {
+            self.depth_limited = true;
+            return Some(game.heuristic(self.player) * HEURISTIC_WEIGHT);
+        }
+
+        if self.can_abort && Instant::now() >= self.deadline {
+            return None;
+        }
+
+        let maximising = game.active_seat() == self.player;
+        let mut best = if maximising {-f64::INFINITY} else {f64::INFINITY};
+        for cell in game.legal_moves() {
+            let mut child = game.clone();
+            child.play_move(cell);
+            let score = self.paranoid(&child, depth - 1, ply + 1, alpha, beta)?;
+
+            if maximising {
+                best = best.max(score);
+                alpha = alpha.max(score);
+            } else {
+                best = best.min(score);
+                beta = beta.min(score);
+            }
+            if alpha >= beta {
+                break;
+            }
+        }
+        Some(best)
+    }
+}
@@ -0,0 +1,151 @@
+//! Time-limited game tree search for [Variant]s
+//!
+//! Variants have far too many positions to search to the end of the game the way
+//! [AiPlayer](super::AiPlayer) does for the classic board. Instead, moves are searched with
+//! alpha-beta pruning to increasing depths until the time limit is reached, and positions at the
+//! depth limit are scored with [Variant::heuristic].
+
+use std::time::{Duration, Instant};
+
+use rand::seq::SliceRandom;
+
+use crate::variants::{Variant, Move, Outcome};
+
+use super::AiError;
+
+/// How long a search may take before returning the result of the deepest completed depth
+pub const TIME_LIMIT: Duration = Duration::from_millis(750);
+
+/// Heuristic scores are scaled by this factor so that they never outweigh a forced win or loss
+pub(super) const HEURISTIC_WEIGHT: f64 = 0.9;
+
+/// Win scores are reduced by this amount per move, so that quicker wins are preferred
+pub(super) const PLY_PENALTY: f64 = 0.001;
+
+/// Evaluates every legal move in the given variant for its active player
+///
+/// Returns each move along with its score, sorted from lowest score (worst move) to highest
+/// score (best move). Scores are within the range `[-1.0, 1.0]`; positive scores favour the
+/// active player, negative scores favour their opponent. Moves with equal scores are in random
+/// order.
+///
+/// The search always completes at least one move deep, even if that takes longer than
+/// `time_limit`.
+///
+/// If no moves can be evaluated (for example, because the game is finished), this function
+/// will return `Err(AiError)` with an appropriate [AiError] describing the issue.
+pub fn evaluate_moves(variant: &dyn Variant, time_limit: Duration) -> Result<Vec<(Move, f64)>, AiError>
+{
+    if variant.outcome().game_finished() {
+        return Err(AiError::GameFinished);
+    }
+
+    let mut moves = variant.legal_moves();
+    if moves.is_empty() {
+        return Err(AiError::NoMovesFound);
+    }
+    moves.shuffle(&mut rand::thread_rng());
+
+    let mut search = Search{
+        deadline: Instant::now() + time_limit,
+        can_abort: false,
+        depth_limited: false
+    };
+
+    let mut evaluated = Vec::new();
+    for depth in 1.. {
+        search.depth_limited = false;
+        match search.evaluate_root(variant, &moves, depth) {
+            Some(scores) => evaluated = scores,
+            // ran out of time; keep the scores from the previous depth
+            None => break
+        }
+        // later depths may be abandoned, as there is already a result to fall back on
+        search.can_abort = true;
+
+        // stop once every line has been searched to the end of the game
+        if !search.depth_limited || Instant::now() >= search.deadline {
+            break;
+        }
+    }
+
+    // stable sort keeps equal moves in their shuffled order
+    evaluated.sort_by(|(_, score_a), (_, score_b)|{
+        score_a.partial_cmp(score_b).unwrap_or(std::cmp::Ordering::Equal)
+    });
+    Ok(evaluated)
+}
+
+/// The state of a search in progress
+struct Search {
+    deadline: Instant,
+    /// true if the search should be abandoned once the deadline has passed
+    can_abort: bool,
+    /// true if any position was scored by the heuristic rather than the game's outcome
+    depth_limited: bool
+}
+
+impl Search {
+
+    /// Scores each of the given moves, searching `depth` moves deep
+    ///
+    /// Returns `None` if the search was abandoned
+    fn evaluate_root(&mut self, variant: &dyn Variant, moves: &[Move], depth: u32) -> Option<Vec<(Move, f64)>>
+    {
+        let player = variant.active_player();
+        moves.iter().map(|game_move|{
+            let mut child = variant.clone_variant();
+            child.play_move(game_move);
+            // each move gets a full window so that every score is exact, not just the best one
+            let score = self.negamax(child.as_ref(), depth - 1, 1, -f64::INFINITY, f64::INFINITY)?;
+            let score = if child.active_player() == player {score} else {-score};
+            Some((game_move.clone(), score))
+        }).collect()
+    }
+
+    /// Returns the score of the given position for its active player, searching `depth` moves deep
+    ///
+    /// `ply` is the number of moves played since the start of the search.
+    /// Returns `None` if the search was abandoned.
+    fn negamax(&mut self, variant: &dyn Variant, depth: u32, ply: u32, mut alpha: f64, beta: f64) -> Option<f64>
+    {
+        let player = variant.active_player();
+        match variant.outcome() {
+            Outcome::Win(winner) => {
+                let score = 1.0 - ply as f64 * PLY_PENALTY;
+                return Some(if winner == player {score} else {-score});
+            },
+            Outcome::Draw => return Some(0.0),
+            Outcome::Incomplete => {}
+        }
+
+        if depth == 0 {
+            self.depth_limited = true;
+            return Some(variant.heuristic(&player) * HEURISTIC_WEIGHT);
+        }
+
+        if self.can_abort && Instant::now() >= self.deadline {
+            return None;
+        }
+
+        let mut best = -f64::INFINITY;
+        for game_move in variant.legal_moves() {
+            let mut child = variant.clone_variant();
+            child.play_move(&game_move);
+
+            // some variants let a player move more than once in a row
+            let score = if child.active_player() == player {
+                self.negamax(child.as_ref(), depth - 1, ply + 1, alpha, beta)?
+            } else {
+                -self.negamax(child.as_ref(), depth - 1, ply + 1, -beta, -alpha)?
+            };
+
+            best = best.max(score);
+            alpha = alpha.max(score);
+            if alpha >= beta {
+                break;
+            }
+        }
+        Some(best)
+    }
+}
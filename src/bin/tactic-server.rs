@@ -0,0 +1,73 @@
+use std::{net::TcpListener, path::PathBuf};
+
+use tactic::server::{self, records::Records, DEFAULT_PORT};
+
+const USAGE: &str = "\
+Usage: tactic-server [OPTION]...
+
+Runs a lobby server that pairs up players and keeps their win/draw/loss records.
+Connect with any line based TCP client, e.g. `nc localhost 4041`.
+
+Options:
+  --port PORT         listen on PORT (default 4041)
+  --records FILE      load records from and save records to FILE
+  -h, --help          print this message";
+
+/// Settings for the server, as determined by command line arguments
+struct ServerOptions {
+    port: u16,
+    records_path: Option<PathBuf>
+}
+
+/// Parses the command line arguments into [ServerOptions]
+///
+/// Returns `Ok(None)` if help was requested, or `Err` with a message to print
+/// if the arguments are invalid
+fn parse_args() -> Result<Option<ServerOptions>, String>
+{
+    let mut options = ServerOptions{port: DEFAULT_PORT, records_path: None};
+    let mut args = std::env::args().skip(1);
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--port" => {
+                let port = args.next().ok_or(format!("--port requires a port\n\n{}", USAGE))?;
+                options.port = port.parse()
+                    .map_err(|_|{format!("Invalid port '{}'\n\n{}", port, USAGE)})?;
+            },
+            "--records" => {
+                let path = args.next().ok_or(format!("--records requires a file\n\n{}", USAGE))?;
+                options.records_path = Some(PathBuf::from(path));
+            },
+            "-h" | "--help" => return Ok(None),
+            other => return Err(format!("Unknown option '{}'\n\n{}", other, USAGE))
+        }
+    }
+
+    Ok(Some(options))
+}
+
+fn main() -> std::io::Result<()>
+{
+    let options = match parse_args() {
+        Ok(Some(options)) => options,
+        Ok(None) => {
+            println!("{}", USAGE);
+            return Ok(());
+        },
+        Err(message) => {
+            eprintln!("{}", message);
+            std::process::exit(2);
+        }
+    };
+
+    let records = match options.records_path {
+        Some(path) => Records::load(path)?,
+        None => Records::new()
+    };
+
+    let listener = TcpListener::bind(("0.0.0.0", options.port))?;
+    println!("tactic-server listening on port {}", options.port);
+
+    server::run(listener, records)
+}
@@ -1,6 +1,9 @@
 //! Utilities to determine the winner of a game (if any)
 
-use crate::gameboard::{GameBoard, BoardSpace, BoardSpaceLocation};
+use crate::{
+    gameboard::{GameBoard, BoardSpace, BoardSpaceLocation},
+    active_player::ActivePlayer
+};
 
 mod win_position_constants;
 
@@ -29,19 +32,25 @@ impl GameOutcome {
     /// 
     /// If a game has multiple valid win positions,
     /// only one win position (and therefore one winner) is selected.
-    /// The prioritiy of win positions is defined by the order that [WinPosition] variants
-    /// are defined in; the earlier variants are higher priority than the later variants.
+    /// Only the win positions allowed by the board's [Rules](crate::rules::Rules) are checked,
+    /// and the first one found wins; they are checked in the order returned by
+    /// [Rules::win_positions](crate::rules::Rules::win_positions).
     /// 
-    /// More specifically, the priority is defined by the order that variants are returned by
-    /// the [WinPosition::all] function, but this order and the variant definition order 
-    /// should be identical.
+    /// That is, the rows, columns and diagonals returned by [WinPosition::all] come first, in
+    /// the order that [WinPosition] variants are defined in. If the board is toroidal, the
+    /// wrapped diagonals returned by [WinPosition::all_wrapped] are checked next, and any
+    /// [patterns](WinPosition::Pattern) are checked last.
+    ///
+    /// Only the board is analyzed, so `Draw` is only returned once the board is full. In
+    /// variants where pieces leave the board (such as [Rolling](crate::variants::rolling::Rolling)),
+    /// the board never fills up, and the variant decides draws from the game's history instead.
     pub fn analyze_game(board: &GameBoard) -> GameOutcome
     {
-        for win_position in WinPosition::all(){
+        for win_position in board.rules().win_positions(){
 
             //get iter over the BoardSpace in each position
             let mut board_space_values = 
-                win_position.as_board_spaces().iter().map(|board_space|{
+                win_position.spaces().iter().map(|board_space|{
                     board.space(*board_space)
                 });
             
@@ -53,7 +62,7 @@ impl GameOutcome {
 
             if is_winner{
                 match possible_winner {
-                    BoardSpace::Empty => {/* do nothing */},
+                    BoardSpace::Empty | BoardSpace::Blocked => {/* do nothing */},
                     BoardSpace::X => {return GameOutcome::PlayerX(win_position);},
                     BoardSpace::O => {return GameOutcome::PlayerO(win_position);}
                 }
@@ -85,14 +94,40 @@ impl GameOutcome {
     {
         !matches!(self, Self::Draw | Self::Incomplete)
     }
+
+    /// Returns this outcome with any win credited to `mover`, whichever symbol the
+    /// winning line is made of
+    /// 
+    /// [analyze_game](GameOutcome::analyze_game) credits a line to the player whose symbol it
+    /// is made of, but in games where either player may place either symbol (such as
+    /// [Wild](crate::variants::wild::Wild)), the player who completed the line wins instead.
+    pub fn credited_to(self, mover: &ActivePlayer) -> GameOutcome
+    {
+        match (self, mover) {
+            (Self::PlayerX(win_position) | Self::PlayerO(win_position), ActivePlayer::PlayerX) => {
+                Self::PlayerX(win_position)
+            },
+            (Self::PlayerX(win_position) | Self::PlayerO(win_position), ActivePlayer::PlayerO) => {
+                Self::PlayerO(win_position)
+            },
+            (outcome, _) => outcome
+        }
+    }
 }
 
-/// The row, column, or diagonal that a game was won with
+/// The row, column, diagonal or pattern that a game was won with
+/// 
+/// The four wrapped diagonals only win on a toroidal board (rows and columns
+/// are the same whether or not they wrap around), and [patterns](WinPosition::Pattern)
+/// only win if the board's [Rules](crate::rules::Rules) include them.
+/// 
+/// A wrapped diagonal is named after its top and bottom spaces, so its name doesn't give
+/// the direction it runs in; the docs of each one list all three of its spaces.
 /// 
 ///# Notes
 /// 
-/// The [GameOutcome::analyze_game] function will check each win position in the order
-/// that `WinPosition` variants are defined.
+/// The [GameOutcome::analyze_game] function will check each win position allowed by the
+/// board's rules in the order that `WinPosition` variants are defined.
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum WinPosition {
     TopRow,
@@ -102,11 +137,42 @@ pub enum WinPosition {
     MiddleColumn,
     RightColumn,
     TopLeftToBottomRight,
-    BottomLeftToTopRight
+    BottomLeftToTopRight,
+    /// TopMiddle, MiddleRight and BottomLeft: down and to the right from TopMiddle,
+    /// wrapping around the right edge after MiddleRight
+    TopMiddleToBottomLeft,
+    /// TopRight, MiddleLeft and BottomMiddle: down and to the right from TopRight,
+    /// wrapping around the right edge to MiddleLeft
+    TopRightToBottomMiddle,
+    /// TopLeft, MiddleRight and BottomMiddle: down and to the left from TopLeft,
+    /// wrapping around the left edge to MiddleRight
+    TopLeftToBottomMiddle,
+    /// TopMiddle, MiddleLeft and BottomRight: down and to the left from TopMiddle,
+    /// wrapping around the left edge after MiddleLeft
+    TopMiddleToBottomRight,
+    /// A user-provided pattern of spaces (see [Rules](crate::rules::Rules))
+    Pattern(WinPattern)
 }
 
 impl WinPosition{
+    /// Returns a slice of [BoardSpaceLocation] with each space contained in this `WinPosition`,
+    /// including [patterns](WinPosition::Pattern)
+    pub fn spaces(&self) -> &[BoardSpaceLocation]
+    {
+        match self {
+            Self::Pattern(win_pattern) => win_pattern.spaces(),
+            line => line.as_board_spaces()
+        }
+    }
+
     /// Returns an array of [BoardSpaceLocation] with each space contained in this `WinPosition`
+    /// 
+    /// Use [WinPosition::spaces] for win positions that may be [patterns](WinPosition::Pattern).
+    /// 
+    ///# Panics
+    /// 
+    /// This function panics if this `WinPosition` is a [pattern](WinPosition::Pattern), which
+    /// may not have three spaces
     pub const fn as_board_spaces(&self) -> &'static [BoardSpaceLocation; 3]
     {
         match self {
@@ -117,11 +183,24 @@ impl WinPosition{
             Self::MiddleColumn => &win_position_constants::MIDDLE_COLUMN,
             Self::RightColumn => &win_position_constants::RIGHT_COLUMN,
             Self::TopLeftToBottomRight => &win_position_constants::TOP_LEFT_TO_BOTTOM_RIGHT,
-            Self::BottomLeftToTopRight => &win_position_constants::BOTTOM_LEFT_TO_TOP_RIGHT
+            Self::BottomLeftToTopRight => &win_position_constants::BOTTOM_LEFT_TO_TOP_RIGHT,
+            Self::TopMiddleToBottomLeft => &win_position_constants::TOP_MIDDLE_TO_BOTTOM_LEFT,
+            Self::TopRightToBottomMiddle => &win_position_constants::TOP_RIGHT_TO_BOTTOM_MIDDLE,
+            Self::TopLeftToBottomMiddle => &win_position_constants::TOP_LEFT_TO_BOTTOM_MIDDLE,
+            Self::TopMiddleToBottomRight => &win_position_constants::TOP_MIDDLE_TO_BOTTOM_RIGHT,
+            Self::Pattern(_) => panic!("a pattern is not a line of three spaces")
         }
     }
 
-    /// Returns an iterator over all variants of `WinPosition`
+    /// Returns true if this `WinPosition` wraps around the edges of the board
+    pub fn is_wrapped(&self) -> bool
+    {
+        Self::all_wrapped().any(|win_position|{win_position == *self})
+    }
+
+    /// Returns an iterator over the variants of `WinPosition` that win on any board
+    /// 
+    /// These are the rows, columns and diagonals that don't wrap around the edges of the board.
     pub fn all() -> impl Iterator<Item = Self>
     {
         const VARIANTS: [WinPosition; 8] = [
@@ -137,4 +216,102 @@ impl WinPosition{
 
         VARIANTS.into_iter()
     }
-}
\ No newline at end of file
+
+    /// Returns an iterator over the variants of `WinPosition` that wrap around the edges of
+    /// the board, which only win on a toroidal board
+    pub fn all_wrapped() -> impl Iterator<Item = Self>
+    {
+        const VARIANTS: [WinPosition; 4] = [
+            WinPosition::TopMiddleToBottomLeft,
+            WinPosition::TopRightToBottomMiddle,
+            WinPosition::TopLeftToBottomMiddle,
+            WinPosition::TopMiddleToBottomRight
+        ];
+
+        VARIANTS.into_iter()
+    }
+}
+
+/// A user-provided pattern of spaces that won a game
+/// 
+/// Holds the index of the pattern in [Rules::patterns](crate::rules::Rules::patterns),
+/// which can be used to look up its name, along with a copy of its spaces.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct WinPattern {
+    index: usize,
+    spaces: [BoardSpaceLocation; 9],
+    len: usize
+}
+
+impl WinPattern {
+
+    /// Returns a new `WinPattern` for the pattern at `index` with the given spaces
+    /// 
+    ///# Panics
+    /// 
+    /// This function panics if `spaces` is empty or has more than 9 spaces
+    pub fn new(index: usize, spaces: &[BoardSpaceLocation]) -> Self
+    {
+        assert!((1..=9).contains(&spaces.len()), "A pattern must have between 1 and 9 spaces");
+
+        let mut pattern_spaces = [BoardSpaceLocation::TopLeft; 9];
+        pattern_spaces[..spaces.len()].copy_from_slice(spaces);
+        Self{index, spaces: pattern_spaces, len: spaces.len()}
+    }
+
+    /// Returns the index of this pattern in [Rules::patterns](crate::rules::Rules::patterns)
+    pub fn index(&self) -> usize
+    {
+        self.index
+    }
+
+    /// Returns the spaces in this pattern
+    pub fn spaces(&self) -> &[BoardSpaceLocation]
+    {
+        &self.spaces[..self.len]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Creates a board from rows of spaces (`X`, `O`, `#` for blocked or `-` for empty)
+    fn board(rows: [&str; 3]) -> GameBoard
+    {
+        let mut board = GameBoard::new();
+        for (y, row) in rows.iter().enumerate() {
+            for (x, space) in row.chars().enumerate() {
+                *board.space_by_coordinates_mut((x as u8, y as u8)) = match space {
+                    'X' => BoardSpace::X,
+                    'O' => BoardSpace::O,
+                    '#' => BoardSpace::Blocked,
+                    _ => BoardSpace::Empty
+                };
+            }
+        }
+        board
+    }
+
+    #[test]
+    fn blocked_spaces_never_complete_a_line()
+    {
+        assert_eq!(GameOutcome::analyze_game(&board(["###", "---", "---"])), GameOutcome::Incomplete);
+        assert_eq!(GameOutcome::analyze_game(&board(["XX#", "-#-", "#OO"])), GameOutcome::Incomplete);
+    }
+
+    #[test]
+    fn lines_through_blocked_spaces_are_ignored()
+    {
+        assert_eq!(GameOutcome::analyze_game(&board(["#--", "XXX", "-O#"])), GameOutcome::PlayerX(WinPosition::MiddleRow));
+        assert_eq!(GameOutcome::analyze_game(&board(["O#X", "O#-", "OX-"])), GameOutcome::PlayerO(WinPosition::LeftColumn));
+    }
+
+    #[test]
+    fn blocked_spaces_count_as_filled_for_draws()
+    {
+        assert_eq!(GameOutcome::analyze_game(&board(["XO#", "OXX", "#XO"])), GameOutcome::Draw);
+        assert_eq!(GameOutcome::analyze_game(&board(["XO#", "OXX", "#X-"])), GameOutcome::Incomplete);
+        assert_eq!(GameOutcome::analyze_game(&board(["###", "###", "###"])), GameOutcome::Draw);
+    }
+}
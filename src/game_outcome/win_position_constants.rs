@@ -41,4 +41,24 @@ pub const BOTTOM_LEFT_TO_TOP_RIGHT: [BoardSpaceLocation; 3] = [
     BoardSpaceLocation::BottomLeft,
     BoardSpaceLocation::MiddleMiddle,
     BoardSpaceLocation::TopRight
+];
+pub const TOP_MIDDLE_TO_BOTTOM_LEFT: [BoardSpaceLocation; 3] = [
+    BoardSpaceLocation::TopMiddle,
+    BoardSpaceLocation::MiddleRight,
+    BoardSpaceLocation::BottomLeft
+];
+pub const TOP_RIGHT_TO_BOTTOM_MIDDLE: [BoardSpaceLocation; 3] = [
+    BoardSpaceLocation::TopRight,
+    BoardSpaceLocation::MiddleLeft,
+    BoardSpaceLocation::BottomMiddle
+];
+pub const TOP_LEFT_TO_BOTTOM_MIDDLE: [BoardSpaceLocation; 3] = [
+    BoardSpaceLocation::TopLeft,
+    BoardSpaceLocation::MiddleRight,
+    BoardSpaceLocation::BottomMiddle
+];
+pub const TOP_MIDDLE_TO_BOTTOM_RIGHT: [BoardSpaceLocation; 3] = [
+    BoardSpaceLocation::TopMiddle,
+    BoardSpaceLocation::MiddleLeft,
+    BoardSpaceLocation::BottomRight
 ];
\ No newline at end of file
@@ -1,18 +1,21 @@
 //! Representation of a tic-tac-toe game board
 
-use std::fmt::{Display, Write};
-use crate::game_outcome::GameOutcome;
+use std::{fmt::{Display, Write}, sync::Arc};
+use crate::{game_outcome::GameOutcome, rules::Rules};
 
 /// The state of a single space on a game board
 /// 
-/// A BoardSpace represents the three states a space on the tic-tac-toe
-/// game board can be in: occupied by an X, occupied by an O, or not occupied at all
+/// A BoardSpace represents the states a space on the tic-tac-toe
+/// game board can be in: occupied by an X, occupied by an O, not occupied at all,
+/// or blocked so that neither player can claim it
 #[derive(Debug, Default, PartialEq, Eq, Clone)]
 pub enum BoardSpace {
     #[default]
     Empty,
     X,
-    O
+    O,
+    /// A space that can't be claimed by either player, and so never completes a line
+    Blocked
 }
 impl BoardSpace {
     /// Returns the character used to represent this variant of `BoardSpace`
@@ -26,7 +29,8 @@ impl BoardSpace {
         match self {
             Self::Empty => ' ',
             Self::X => 'X',
-            Self::O => 'O'
+            Self::O => 'O',
+            Self::Blocked => '#'
         }
     }
 }
@@ -97,6 +101,22 @@ impl BoardSpaceLocation {
         panic!("Coordinates ({},{}) don't correspond to any BoardSpaceLocation", x, y);
     }
 
+    /// Returns the name of this `BoardSpaceLocation`, such as "top left" or "centre"
+    pub const fn name(&self) -> &'static str
+    {
+        match self {
+            Self::TopLeft => "top left",
+            Self::TopMiddle => "top",
+            Self::TopRight => "top right",
+            Self::MiddleLeft => "left",
+            Self::MiddleMiddle => "centre",
+            Self::MiddleRight => "right",
+            Self::BottomLeft => "bottom left",
+            Self::BottomMiddle => "bottom",
+            Self::BottomRight => "bottom right"
+        }
+    }
+
     /// Returns an iterator over all variants of `BoardSpaceLocation`
     pub fn all() -> impl Iterator<Item = Self>
     {
@@ -122,9 +142,12 @@ impl BoardSpaceLocation {
 /// That is, represents a square divided into 9 equally sized square spaces.
 /// The state of each space is represented as a [BoardSpace].
 ///
-#[derive(Default, Clone)]
+/// Each board has [Rules] deciding which sets of spaces win, which are
+/// shared between copies of the board.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
 pub struct GameBoard {
-    board_state: [[BoardSpace; 3]; 3]
+    board_state: [[BoardSpace; 3]; 3],
+    rules: Arc<Rules>
 }
 
 impl GameBoard {
@@ -137,6 +160,19 @@ impl GameBoard {
         GameBoard::default()
     }
 
+    /// Returns a new `GameBoard` instance played with the given [Rules],
+    /// with all spaces initialized to [BoardSpace::Empty]
+    pub fn with_rules(rules: Arc<Rules>) -> Self
+    {
+        Self{rules, ..Self::default()}
+    }
+
+    /// Returns the [Rules] this board is played with
+    pub fn rules(&self) -> &Rules
+    {
+        &self.rules
+    }
+
     /// Returns a reference to one of the board spaces
     pub fn space(&self, space_location: BoardSpaceLocation) -> &BoardSpace
     {
@@ -0,0 +1,561 @@
+//! An HTTP server exposing the game engine as a JSON API
+//!
+//! Only available with the `http` cargo feature. Games are kept in memory and nothing is
+//! persisted. A game is deleted once it hasn't been used for [GAME_EXPIRY], and at most
+//! [MAX_GAMES] games are kept at once; `POST /games` returns `503` when that many are in use.
+//!
+//! # Endpoints
+//!
+//! | Request                      | Body                                      | Response                          |
+//! |------------------------------|-------------------------------------------|-----------------------------------|
+//! | `POST /games`                | `{"mode"?}`                               | `201` with the new game           |
+//! | `GET /games/{id}`            |                                           | `200` with the game               |
+//! | `DELETE /games/{id}`         |                                           | `204`                             |
+//! | `POST /games/{id}/moves`     | `{"x", "y"}`                              | `200` with the game               |
+//! | `POST /games/{id}/ai-move`   | `{"difficulty"?}`                         | `200` with the game and `"move"`  |
+//! | `POST /evaluate`             | `{"board", "player"?, "mode"?}`           | `200` with the evaluation         |
+//!
+//! Bodies marked `?` are optional. `mode` is `"classic"` (the default) or `"reverse"`, and
+//! `difficulty` is from `0.0` to `1.0` (the default), as in the setup menu.
+//! Coordinates are from `0` to `2`, where `(0,0)` is the top left.
+//!
+//! A game is returned as:
+//!
+//! ```json
+//! {
+//!     "id": 0,
+//!     "mode": "classic",
+//!     "board": ["X--", "-O-", "--X"],
+//!     "active_player": "O",
+//!     "outcome": {"result": "incomplete", "win_position": null},
+//!     "winner": null
+//! }
+//! ```
+//!
+//! `board` is one string per row, from top to bottom, with `X`, `O` or `-` for empty.
+//! `outcome` mirrors [GameOutcome]: `result` is `"player_x"` or `"player_o"` if that player
+//! completed a line (along with the line, e.g. `"top_row"`), `"draw"` or `"incomplete"`.
+//! `winner` takes the game mode into account, so in reverse mode the player who completed a
+//! line loses.
+//!
+//! An evaluation contains the `outcome` and `winner` of the given board, the `player` to move,
+//! and every possible `move` as `{"x", "y", "score"}`, best first. A score is a relative
+//! preference from `-1.0` to `1.0`, where positive scores favour `player`; it is only `1.0` or
+//! `-1.0` for a move that wins or loses at once. Outcomes further ahead are averaged and halved
+//! for each move until they happen, so scores are most useful compared with each other.
+//!
+//! Errors are returned as `{"error": message}` with an appropriate status code.
+
+use std::{
+    collections::HashMap,
+    io,
+    net::{TcpListener, TcpStream},
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+    thread,
+    time::{Duration, Instant}
+};
+
+use serde_json::{json, Value};
+
+use crate::{
+    gameboard::{GameBoard, BoardSpace, BoardSpaceLocation},
+    game_outcome::{GameOutcome, WinPosition},
+    game_settings::GameMode,
+    active_player::ActivePlayer,
+    ai::{AiPlayer, AiError}
+};
+
+mod http;
+use http::{Request, Response};
+
+/// The port the API listens on if no port is specified
+pub const DEFAULT_PORT: u16 = 4042;
+
+/// How long a client may take to send its request, or to read the response
+const IO_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How long a game is kept after it was last created, fetched or played in
+pub const GAME_EXPIRY: Duration = Duration::from_secs(60 * 60);
+
+/// The largest number of games kept at once
+pub const MAX_GAMES: usize = 10_000;
+
+/// Accepts requests on the given listener forever, handling each connection on its own thread
+pub fn run(listener: TcpListener) -> io::Result<()>
+{
+    let games = Arc::new(Mutex::new(Games::default()));
+
+    for stream in listener.incoming() {
+        // a failed connection only affects that client
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(_) => continue
+        };
+        let games = Arc::clone(&games);
+        thread::spawn(move ||{
+            // errors mean the client disconnected before reading the response
+            let _ = handle_connection(&games, stream);
+        });
+    }
+
+    Ok(())
+}
+
+/// Reads a single request from the connection and writes the response
+fn handle_connection(games: &Mutex<Games>, stream: TcpStream) -> io::Result<()>
+{
+    stream.set_read_timeout(Some(IO_TIMEOUT))?;
+    stream.set_write_timeout(Some(IO_TIMEOUT))?;
+    let response = match Request::read(&stream) {
+        Ok(request) => route(games, &request).unwrap_or_else(|error|{error}),
+        Err(error) => error
+    };
+    response.write(&stream)
+}
+
+/// Dispatches a request to the handler for its method and path
+///
+/// Errors are returned as `Err` so handlers can use `?`
+fn route(games: &Mutex<Games>, request: &Request) -> Result<Response, Response>
+{
+    let segments: Vec<&str> = request.path.split('/').filter(|segment|{!segment.is_empty()}).collect();
+
+    match (request.method.as_str(), segments.as_slice()) {
+        ("POST", ["games"]) => {
+            let mode = parse_mode(&request.json()?)?;
+            let mut games = lock(games);
+            let id = games.create(mode)?;
+            Ok(Response::json(201, games.get_mut(id)?.to_json(id)))
+        },
+        ("GET", ["games", id]) => {
+            let id = parse_id(id)?;
+            Ok(Response::json(200, lock(games).get_mut(id)?.to_json(id)))
+        },
+        ("DELETE", ["games", id]) => {
+            let id = parse_id(id)?;
+            match lock(games).games.remove(&id) {
+                Some(_) => Ok(Response::empty(204)),
+                None => Err(game_not_found(id))
+            }
+        },
+        ("POST", ["games", id, "moves"]) => {
+            let id = parse_id(id)?;
+            let body = request.json()?;
+            let location = BoardSpaceLocation::from_coordinates((
+                parse_coordinate(&body, "x")?,
+                parse_coordinate(&body, "y")?
+            ));
+            let mut games = lock(games);
+            let game = games.get_mut(id)?;
+            game.play_move(location)?;
+            Ok(Response::json(200, game.to_json(id)))
+        },
+        ("POST", ["games", id, "ai-move"]) => {
+            let id = parse_id(id)?;
+            let difficulty = parse_difficulty(&request.json()?)?;
+            // the move is chosen without holding the lock, so other requests aren't held up
+            let (board, active_player, mode) = {
+                let mut games = lock(games);
+                let game = games.get_mut(id)?;
+                (game.board.clone(), game.active_player.clone(), game.mode)
+            };
+            let ai_player = match mode {
+                GameMode::Reverse => AiPlayer::new(difficulty).reverse_difficulty(),
+                _ => AiPlayer::new(difficulty)
+            };
+            let location = ai_player.choose_move(&board, &active_player)
+                .map_err(|error|{ai_error(&error)})?;
+
+            let mut games = lock(games);
+            let game = games.get_mut(id)?;
+            if game.board != board || game.active_player != active_player {
+                return Err(Response::error(409, "the game changed while the move was being chosen"));
+            }
+            game.play_move(location)?;
+
+            let mut body = game.to_json(id);
+            body["move"] = location_to_json(location);
+            Ok(Response::json(200, body))
+        },
+        ("POST", ["evaluate"]) => evaluate(&request.json()?),
+        (_, ["games"] | ["games", _] | ["games", _, "moves" | "ai-move"] | ["evaluate"]) => {
+            Err(Response::error(405, &format!("{} is not allowed on {}", request.method, request.path)))
+        },
+        _ => Err(Response::error(404, &format!("no such endpoint {}", request.path)))
+    }
+}
+
+/// Handles `POST /evaluate`
+fn evaluate(body: &Value) -> Result<Response, Response>
+{
+    let board = parse_board(body)?;
+    let mode = parse_mode(body)?;
+    let player = match body.get("player") {
+        None | Some(Value::Null) => next_player(&board),
+        Some(player) => parse_player(player)?
+    };
+
+    let outcome = board.game_outcome();
+    // finished games have no moves to evaluate
+    let moves = AiPlayer::evaluate_moves(&board, &player).unwrap_or_default();
+
+    // evaluate_moves rates moves for classic games, worst first;
+    // in reverse mode the best classic move is the worst reverse move
+    let moves: Vec<(BoardSpaceLocation, f64)> = match mode {
+        GameMode::Reverse => moves.into_iter().map(|(location, score)|{(location, -score)}).collect(),
+        _ => moves.into_iter().rev().collect()
+    };
+    let moves: Vec<Value> = moves.into_iter().map(|(location, score)|{
+        let mut value = location_to_json(location);
+        value["score"] = json!(score);
+        value
+    }).collect();
+
+    Ok(Response::json(200, json!({
+        "mode": mode.name(),
+        "player": player_name(&player),
+        "winner": winner_to_json(&outcome, mode),
+        "outcome": outcome_to_json(&outcome),
+        "moves": moves
+    })))
+}
+
+/// Every game created through the API that hasn't expired
+#[derive(Default)]
+struct Games {
+    games: HashMap<u64, ApiGame>,
+    next_id: u64
+}
+
+impl Games {
+
+    /// Creates a new game and returns its id
+    ///
+    /// Expired games are deleted first. Returns a `503` response if [MAX_GAMES] games
+    /// are still in use.
+    fn create(&mut self, mode: GameMode) -> Result<u64, Response>
+    {
+        let now = Instant::now();
+        self.games.retain(|_, game|{now.duration_since(game.last_used) < GAME_EXPIRY});
+        if self.games.len() >= MAX_GAMES {
+            return Err(Response::error(503, "too many games; try again later"));
+        }
+
+        let id = self.next_id;
+        self.next_id += 1;
+        self.games.insert(id, ApiGame{
+            board: GameBoard::new(),
+            active_player: ActivePlayer::PlayerX,
+            mode,
+            last_used: now
+        });
+        Ok(id)
+    }
+
+    /// Returns the game with the given id, or a `404` response if there is no such game
+    /// or it has expired
+    ///
+    /// The game won't expire until [GAME_EXPIRY] after this call.
+    fn get_mut(&mut self, id: u64) -> Result<&mut ApiGame, Response>
+    {
+        let now = Instant::now();
+        match self.games.get_mut(&id) {
+            Some(game) if now.duration_since(game.last_used) < GAME_EXPIRY => {
+                game.last_used = now;
+                Ok(game)
+            },
+            _ => Err(game_not_found(id))
+        }
+    }
+}
+
+/// A game created through the API
+struct ApiGame {
+    board: GameBoard,
+    active_player: ActivePlayer,
+    mode: GameMode,
+    /// when the game was last created, fetched or played in
+    last_used: Instant
+}
+
+impl ApiGame {
+
+    /// Claims the given space for the active player
+    ///
+    /// Returns a `409` response if the game is finished or the space is taken
+    fn play_move(&mut self, location: BoardSpaceLocation) -> Result<(), Response>
+    {
+        if self.board.game_outcome().game_finished() {
+            return Err(Response::error(409, "the game is finished"));
+        }
+        if self.board.space(location) != &BoardSpace::Empty {
+            return Err(Response::error(409, "that space is taken"));
+        }
+
+        *self.board.space_mut(location) = self.active_player.get_board_space();
+        self.active_player.switch();
+        Ok(())
+    }
+
+    /// Returns the JSON representation of this game, as described in the [module docs](self)
+    fn to_json(&self, id: u64) -> Value
+    {
+        let outcome = self.board.game_outcome();
+        json!({
+            "id": id,
+            "mode": self.mode.name(),
+            "board": board_to_json(&self.board),
+            "active_player": player_name(&self.active_player),
+            "outcome": outcome_to_json(&outcome),
+            "winner": winner_to_json(&outcome, self.mode)
+        })
+    }
+}
+
+/// Locks the games, recovering them if another request's thread panicked while holding the lock
+fn lock(games: &Mutex<Games>) -> MutexGuard<'_, Games>
+{
+    games.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+fn game_not_found(id: u64) -> Response
+{
+    Response::error(404, &format!("no game with id {}", id))
+}
+
+fn ai_error(error: &AiError) -> Response
+{
+    match error {
+        AiError::GameFinished => Response::error(409, "the game is finished"),
+        AiError::NoMovesFound => Response::error(409, "there are no moves to play")
+    }
+}
+
+/// Returns the player whose turn it is on the given board, assuming X moved first
+fn next_player(board: &GameBoard) -> ActivePlayer
+{
+    let count = |piece: BoardSpace|{board.all_spaces().filter(|(_, space)|{**space == piece}).count()};
+    if count(BoardSpace::X) > count(BoardSpace::O) {
+        ActivePlayer::PlayerO
+    } else {
+        ActivePlayer::PlayerX
+    }
+}
+
+fn parse_id(id: &str) -> Result<u64, Response>
+{
+    id.parse().map_err(|_|{Response::error(404, &format!("invalid game id '{}'", id))})
+}
+
+/// Parses the optional `mode` field of a request body
+fn parse_mode(body: &Value) -> Result<GameMode, Response>
+{
+    let mode = match body.get("mode") {
+        None | Some(Value::Null) => return Ok(GameMode::Classic),
+        Some(Value::String(mode)) => mode.parse::<GameMode>().ok(),
+        Some(_) => None
+    };
+    // only modes played on a single board are supported
+    mode.filter(GameMode::is_classic)
+        .ok_or_else(||{Response::error(400, "mode must be \"classic\" or \"reverse\"")})
+}
+
+/// Parses the optional `difficulty` field of a request body
+fn parse_difficulty(body: &Value) -> Result<f64, Response>
+{
+    match body.get("difficulty") {
+        None | Some(Value::Null) => Ok(1.0),
+        Some(difficulty) => difficulty.as_f64()
+            .filter(|difficulty|{(0.0..=1.0).contains(difficulty)})
+            .ok_or_else(||{Response::error(400, "difficulty must be a number from 0.0 to 1.0")})
+    }
+}
+
+/// Parses a required coordinate field of a request body
+fn parse_coordinate(body: &Value, field: &str) -> Result<u8, Response>
+{
+    body.get(field)
+        .and_then(Value::as_u64)
+        .filter(|coordinate|{*coordinate <= 2})
+        .map(|coordinate|{coordinate as u8})
+        .ok_or_else(||{Response::error(400, &format!("{} must be a number from 0 to 2", field))})
+}
+
+fn parse_player(player: &Value) -> Result<ActivePlayer, Response>
+{
+    match player.as_str() {
+        Some("X" | "x") => Ok(ActivePlayer::PlayerX),
+        Some("O" | "o") => Ok(ActivePlayer::PlayerO),
+        _ => Err(Response::error(400, "player must be \"X\" or \"O\""))
+    }
+}
+
+/// Parses the required `board` field of a request body, in the format described in the [module docs](self)
+fn parse_board(body: &Value) -> Result<GameBoard, Response>
+{
+    let invalid = ||{Response::error(400, "board must be 3 strings of 3 characters: X, O or -")};
+
+    let rows = body.get("board").and_then(Value::as_array).ok_or_else(invalid)?;
+    if rows.len() != 3 {
+        return Err(invalid());
+    }
+
+    let mut board = GameBoard::new();
+    for (y, row) in rows.iter().enumerate() {
+        let row: Vec<char> = row.as_str().ok_or_else(invalid)?.chars().collect();
+        if row.len() != 3 {
+            return Err(invalid());
+        }
+        for (x, space) in row.into_iter().enumerate() {
+            *board.space_by_coordinates_mut((x as u8, y as u8)) = match space {
+                'X' | 'x' => BoardSpace::X,
+                'O' | 'o' => BoardSpace::O,
+                '-' | ' ' => BoardSpace::Empty,
+                _ => return Err(invalid())
+            };
+        }
+    }
+    Ok(board)
+}
+
+fn board_to_json(board: &GameBoard) -> Value
+{
+    (0..3).map(|y|{
+        (0..3).map(|x|{
+            match board.space_by_coordinates((x, y)) {
+                BoardSpace::Empty => '-',
+                space => space.get_char()
+            }
+        }).collect::<String>()
+    }).collect()
+}
+
+fn location_to_json(location: BoardSpaceLocation) -> Value
+{
+    let (x, y) = location.as_coordinates();
+    json!({"x": x, "y": y})
+}
+
+fn outcome_to_json(outcome: &GameOutcome) -> Value
+{
+    let (result, win_position) = match outcome {
+        GameOutcome::PlayerX(win_position) => ("player_x", Some(win_position_name(win_position))),
+        GameOutcome::PlayerO(win_position) => ("player_o", Some(win_position_name(win_position))),
+        GameOutcome::Draw => ("draw", None),
+        GameOutcome::Incomplete => ("incomplete", None)
+    };
+    json!({"result": result, "win_position": win_position})
+}
+
+/// Returns the winning player's name, taking into account that
+/// completing a line loses in reverse mode
+fn winner_to_json(outcome: &GameOutcome, mode: GameMode) -> Value
+{
+    let line_completed_by = match outcome {
+        GameOutcome::PlayerX(_) => ActivePlayer::PlayerX,
+        GameOutcome::PlayerO(_) => ActivePlayer::PlayerO,
+        GameOutcome::Draw | GameOutcome::Incomplete => return Value::Null
+    };
+    match mode {
+        GameMode::Reverse => json!(player_name(&line_completed_by.opposite())),
+        _ => json!(player_name(&line_completed_by))
+    }
+}
+
+fn player_name(player: &ActivePlayer) -> String
+{
+    player.get_char().to_string()
+}
+
+fn win_position_name(win_position: &WinPosition) -> &'static str
+{
+    match win_position {
+        WinPosition::TopRow => "top_row",
+        WinPosition::MiddleRow => "middle_row",
+        WinPosition::BottomRow => "bottom_row",
+        WinPosition::LeftColumn => "left_column",
+        WinPosition::MiddleColumn => "middle_column",
+        WinPosition::RightColumn => "right_column",
+        WinPosition::TopLeftToBottomRight => "top_left_to_bottom_right",
+        WinPosition::BottomLeftToTopRight => "bottom_left_to_top_right",
+        WinPosition::TopMiddleToBottomLeft => "top_middle_to_bottom_left",
+        WinPosition::TopRightToBottomMiddle => "top_right_to_bottom_middle",
+        WinPosition::TopLeftToBottomMiddle => "top_left_to_bottom_middle",
+        WinPosition::TopMiddleToBottomRight => "top_middle_to_bottom_right",
+        WinPosition::Pattern(_) => "pattern"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns the status of the error response from `result`, or `None` if it is `Ok`
+    fn error_status<T>(result: Result<T, Response>) -> Option<u16>
+    {
+        result.err().map(|response|{response.status})
+    }
+
+    #[test]
+    fn mode_defaults_to_classic()
+    {
+        assert_eq!(parse_mode(&json!({})).ok(), Some(GameMode::Classic));
+        assert_eq!(parse_mode(&json!({"mode": null})).ok(), Some(GameMode::Classic));
+        assert_eq!(parse_mode(&json!({"mode": "reverse"})).ok(), Some(GameMode::Reverse));
+    }
+
+    #[test]
+    fn unsupported_modes_are_rejected()
+    {
+        assert_eq!(error_status(parse_mode(&json!({"mode": "ultimate"}))), Some(400));
+        assert_eq!(error_status(parse_mode(&json!({"mode": "chess"}))), Some(400));
+        assert_eq!(error_status(parse_mode(&json!({"mode": 1}))), Some(400));
+    }
+
+    #[test]
+    fn difficulty_must_be_between_zero_and_one()
+    {
+        assert_eq!(parse_difficulty(&json!({})).ok(), Some(1.0));
+        assert_eq!(parse_difficulty(&json!({"difficulty": 0.25})).ok(), Some(0.25));
+        assert_eq!(parse_difficulty(&json!({"difficulty": 0})).ok(), Some(0.0));
+        assert_eq!(error_status(parse_difficulty(&json!({"difficulty": 1.5}))), Some(400));
+        assert_eq!(error_status(parse_difficulty(&json!({"difficulty": "hard"}))), Some(400));
+    }
+
+    #[test]
+    fn coordinates_must_be_on_the_board()
+    {
+        assert_eq!(parse_coordinate(&json!({"x": 2}), "x").ok(), Some(2));
+        assert_eq!(error_status(parse_coordinate(&json!({"x": 3}), "x")), Some(400));
+        assert_eq!(error_status(parse_coordinate(&json!({"x": -1}), "x")), Some(400));
+        assert_eq!(error_status(parse_coordinate(&json!({"y": 1}), "x")), Some(400));
+    }
+
+    #[test]
+    fn players_are_parsed_in_either_case()
+    {
+        assert_eq!(parse_player(&json!("x")).ok(), Some(ActivePlayer::PlayerX));
+        assert_eq!(parse_player(&json!("O")).ok(), Some(ActivePlayer::PlayerO));
+        assert_eq!(error_status(parse_player(&json!("Z"))), Some(400));
+        assert_eq!(error_status(parse_player(&json!(1))), Some(400));
+    }
+
+    #[test]
+    fn boards_round_trip()
+    {
+        let body = json!({"board": ["X-O", "-X-", "O--"]});
+        let board = parse_board(&body).ok().expect("board should be valid");
+        assert_eq!(*board.space_by_coordinates((0, 0)), BoardSpace::X);
+        assert_eq!(*board.space_by_coordinates((2, 0)), BoardSpace::O);
+        assert_eq!(board_to_json(&board), body["board"]);
+        assert_eq!(next_player(&board), ActivePlayer::PlayerX);
+    }
+
+    #[test]
+    fn invalid_boards_are_rejected()
+    {
+        for board in [json!(null), json!("X-O-X-O--"), json!(["X-O", "-X-"]), json!(["X-O", "-X-", "O-"]), json!(["X-O", "-X-", "O-#"])] {
+            assert_eq!(error_status(parse_board(&json!({"board": board}))), Some(400));
+        }
+    }
+}
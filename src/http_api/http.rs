@@ -0,0 +1,219 @@
+//! Just enough HTTP/1.1 to serve the JSON API
+//!
+//! Each connection carries a single request; responses always close the connection.
+
+use std::io::{self, BufRead, BufReader, Read, Write};
+
+use serde_json::Value;
+
+/// The largest request body that will be read
+const MAX_BODY_LENGTH: usize = 64 * 1024;
+
+/// The largest number of headers that will be read
+const MAX_HEADERS: usize = 100;
+
+/// An HTTP request
+pub struct Request {
+    pub method: String,
+    /// The path of the request, without any query string
+    pub path: String,
+    pub body: Vec<u8>
+}
+
+impl Request {
+
+    /// Reads a request from the given stream
+    ///
+    /// Returns `Err` with a [Response] to send back if the request is malformed
+    pub fn read(stream: impl Read) -> Result<Self, Response>
+    {
+        let mut reader = BufReader::new(stream);
+        let bad_request = |message: &str|{Response::error(400, message)};
+
+        let request_line = read_line(&mut reader).ok_or_else(||{bad_request("missing request line")})?;
+        let mut parts = request_line.split_whitespace();
+        let (method, target) = match (parts.next(), parts.next(), parts.next()) {
+            (Some(method), Some(target), Some(version)) if version.starts_with("HTTP/1.") =>
+                (method.to_owned(), target),
+            _ => return Err(bad_request("malformed request line"))
+        };
+        let path = target.split('?').next().unwrap_or_default().to_owned();
+
+        let mut content_length = 0;
+        for _ in 0..=MAX_HEADERS {
+            let line = read_line(&mut reader).ok_or_else(||{bad_request("unexpected end of headers")})?;
+            if line.is_empty() {
+                let mut body = vec![0; content_length];
+                reader.read_exact(&mut body).map_err(|_|{bad_request("body shorter than Content-Length")})?;
+                return Ok(Self{method, path, body});
+            }
+
+            let (name, value) = line.split_once(':').ok_or_else(||{bad_request("malformed header")})?;
+            if name.trim().eq_ignore_ascii_case("Content-Length") {
+                content_length = value.trim().parse().map_err(|_|{bad_request("invalid Content-Length")})?;
+                if content_length > MAX_BODY_LENGTH {
+                    return Err(Response::error(413, "request body too large"));
+                }
+            }
+        }
+        Err(Response::error(431, "too many headers"))
+    }
+
+    /// Parses the body as a JSON object
+    ///
+    /// An empty body is treated as an empty object
+    pub fn json(&self) -> Result<Value, Response>
+    {
+        if self.body.iter().all(u8::is_ascii_whitespace) {
+            return Ok(Value::Object(Default::default()));
+        }
+        match serde_json::from_slice(&self.body) {
+            Ok(value @ Value::Object(_)) => Ok(value),
+            Ok(_) => Err(Response::error(400, "request body must be a JSON object")),
+            Err(error) => Err(Response::error(400, &format!("invalid JSON: {}", error)))
+        }
+    }
+}
+
+/// Reads a line, without its line ending
+///
+/// Returns `None` if the stream ends or the line is not valid UTF-8
+fn read_line(reader: &mut impl BufRead) -> Option<String>
+{
+    let mut line = String::new();
+    match reader.read_line(&mut line) {
+        Ok(0) | Err(_) => None,
+        Ok(_) => Some(line.trim_end_matches(['\r', '\n']).to_owned())
+    }
+}
+
+/// An HTTP response with an optional JSON body
+pub struct Response {
+    pub status: u16,
+    pub body: Option<Value>
+}
+
+impl Response {
+
+    /// A response with the given status and JSON body
+    pub fn json(status: u16, body: Value) -> Self
+    {
+        Self{status, body: Some(body)}
+    }
+
+    /// A response with the given status and no body
+    pub fn empty(status: u16) -> Self
+    {
+        Self{status, body: None}
+    }
+
+    /// An error response, with a body of the form `{"error": message}`
+    pub fn error(status: u16, message: &str) -> Self
+    {
+        Self::json(status, serde_json::json!({"error": message}))
+    }
+
+    /// Writes this response to the given stream
+    pub fn write(&self, mut stream: impl Write) -> io::Result<()>
+    {
+        let body = match &self.body {
+            Some(body) => body.to_string(),
+            None => String::new()
+        };
+
+        write!(stream, "HTTP/1.1 {} {}\r\n", self.status, reason_phrase(self.status))?;
+        if self.body.is_some() {
+            write!(stream, "Content-Type: application/json\r\n")?;
+        }
+        write!(stream, "Content-Length: {}\r\nConnection: close\r\n\r\n{}", body.len(), body)?;
+        stream.flush()
+    }
+}
+
+/// Returns the standard reason phrase for the status codes used by the API
+fn reason_phrase(status: u16) -> &'static str
+{
+    match status {
+        200 => "OK",
+        201 => "Created",
+        204 => "No Content",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        409 => "Conflict",
+        413 => "Payload Too Large",
+        431 => "Request Header Fields Too Large",
+        _ => "Internal Server Error"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns the status of the error response from `result`, or `None` if it is `Ok`
+    fn error_status<T>(result: Result<T, Response>) -> Option<u16>
+    {
+        result.err().map(|response|{response.status})
+    }
+
+    #[test]
+    fn reads_get_request()
+    {
+        let request = Request::read("GET /games/3?verbose=1 HTTP/1.1\r\nHost: localhost\r\n\r\n".as_bytes())
+            .ok().expect("request should be valid");
+        assert_eq!(request.method, "GET");
+        assert_eq!(request.path, "/games/3");
+        assert!(request.body.is_empty());
+    }
+
+    #[test]
+    fn reads_post_request_with_body()
+    {
+        let input = "POST /games HTTP/1.0\ncontent-length: 18\n\n{\"mode\":\"classic\"}";
+        let request = Request::read(input.as_bytes()).ok().expect("request should be valid");
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.path, "/games");
+        assert_eq!(request.body, b"{\"mode\":\"classic\"}");
+    }
+
+    #[test]
+    fn malformed_requests_are_rejected()
+    {
+        assert_eq!(error_status(Request::read("".as_bytes())), Some(400));
+        assert_eq!(error_status(Request::read("GET /games\r\n\r\n".as_bytes())), Some(400));
+        assert_eq!(error_status(Request::read("GET /games HTTP/2\r\n\r\n".as_bytes())), Some(400));
+        assert_eq!(error_status(Request::read("GET /games HTTP/1.1\r\nHost: localhost\r\n".as_bytes())), Some(400));
+        assert_eq!(error_status(Request::read("GET /games HTTP/1.1\r\nno colon\r\n\r\n".as_bytes())), Some(400));
+        assert_eq!(error_status(Request::read("POST /games HTTP/1.1\r\nContent-Length: ten\r\n\r\n".as_bytes())), Some(400));
+        assert_eq!(error_status(Request::read("POST /games HTTP/1.1\r\nContent-Length: 10\r\n\r\n{}".as_bytes())), Some(400));
+    }
+
+    #[test]
+    fn large_bodies_are_rejected()
+    {
+        let input = format!("POST /games HTTP/1.1\r\nContent-Length: {}\r\n\r\n", MAX_BODY_LENGTH + 1);
+        assert_eq!(error_status(Request::read(input.as_bytes())), Some(413));
+    }
+
+    #[test]
+    fn too_many_headers_are_rejected()
+    {
+        let input = format!("GET /games HTTP/1.1\r\n{}\r\n", "X-Header: value\r\n".repeat(MAX_HEADERS + 1));
+        assert_eq!(error_status(Request::read(input.as_bytes())), Some(431));
+
+        let input = format!("GET /games HTTP/1.1\r\n{}\r\n", "X-Header: value\r\n".repeat(MAX_HEADERS));
+        assert_eq!(error_status(Request::read(input.as_bytes())), None);
+    }
+
+    #[test]
+    fn body_is_parsed_as_json_object()
+    {
+        let request = |body: &str|{Request{method: "POST".to_owned(), path: "/games".to_owned(), body: body.as_bytes().to_vec()}};
+
+        assert_eq!(request("{\"x\": 1}").json().ok(), Some(serde_json::json!({"x": 1})));
+        assert_eq!(request(" \n").json().ok(), Some(serde_json::json!({})));
+        assert_eq!(error_status(request("[1, 2]").json()), Some(400));
+        assert_eq!(error_status(request("{\"x\": ").json()), Some(400));
+    }
+}
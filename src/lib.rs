@@ -3,18 +3,27 @@ pub mod gameboard;
 pub mod game_outcome;
 pub mod ui;
 pub mod ai;
+pub mod network;
+pub mod server;
+pub mod variants;
+pub mod rules;
+pub mod multiplayer;
+#[cfg(feature = "http")]
+pub mod http_api;
 
 /// The PlayerType enum
 pub mod player_type {
 
     use crate::ai::AiPlayer;
 
-    /// Represents the type of a player (either human or AI)
+    /// Represents the type of a player (human, AI, or a human playing 
+    /// from another `tactic` instance over the network)
     #[derive(Default, Debug, PartialEq)]
     pub enum PlayerType {
         #[default]
         Human,
-        AI(AiPlayer)
+        AI(AiPlayer),
+        Remote
     }
 }
 
@@ -24,7 +33,7 @@ pub mod active_player{
 
 
     /// Represents which player (X or O) is currently active
-    #[derive(PartialEq, Eq, Clone)]
+    #[derive(Debug, PartialEq, Eq, Clone)]
     pub enum ActivePlayer {
         PlayerX,
         PlayerO
@@ -68,16 +77,168 @@ pub mod active_player{
 
 
 /// Enums to represent different game settings
-mod game_settings{
+pub mod game_settings{
     /// Determines the game mode to be played
-    #[derive(Default)]
+    #[derive(Default, Debug, PartialEq, Eq, Clone, Copy)]
     pub enum GameMode{
         #[default]
         Classic,
-        Reverse
+        Reverse,
+        /// A 3x3 grid of boards, where each move decides which board the opponent plays in
+        Ultimate,
+        /// A 4x4x4 cube, where lines of four can run through all four layers
+        Qubic,
+        /// Each player may only have three pieces; placing a fourth removes their oldest piece
+        Rolling,
+        /// Three Men's Morris: each player places three pieces, then moves them
+        Morris,
+        /// Either player may place either symbol, and whoever completes a line wins
+        Wild,
+        /// Both players place X's on several boards, and whoever completes the last line loses
+        Notakto,
+        /// Pieces fall to the bottom of their column, as in Connect Four
+        Gravity,
+        /// Each move places a mark in two squares at once; cycles of these marks collapse
+        /// into ordinary marks, which win as in the classic game
+        Quantum,
+        /// One player places odd numbers and the other even numbers; a line adding up to 15 wins
+        Numerical,
+        /// One player (Order) tries to make five in a row of either symbol on a 6x6 board,
+        /// and the other (Chaos) tries to fill the board without one
+        OrderChaos,
+        /// Three or four players take turns on a larger board, and the first to complete a line wins
+        Multiplayer
     }
+
+    impl GameMode {
+        /// Every game mode, in the order they are listed in the setup menu
+        pub const ALL: [GameMode; 13] = [
+            GameMode::Classic,
+            GameMode::Reverse,
+            GameMode::Ultimate,
+            GameMode::Qubic,
+            GameMode::Rolling,
+            GameMode::Morris,
+            GameMode::Wild,
+            GameMode::Notakto,
+            GameMode::Gravity,
+            GameMode::Quantum,
+            GameMode::Numerical,
+            GameMode::OrderChaos,
+            GameMode::Multiplayer
+        ];
+
+        /// Returns the name of this mode used by the network protocol and the HTTP API
+        pub fn name(&self) -> &'static str
+        {
+            match self {
+                GameMode::Classic => "classic",
+                GameMode::Reverse => "reverse",
+                GameMode::Ultimate => "ultimate",
+                GameMode::Qubic => "qubic",
+                GameMode::Rolling => "rolling",
+                GameMode::Morris => "morris",
+                GameMode::Wild => "wild",
+                GameMode::Notakto => "notakto",
+                GameMode::Gravity => "gravity",
+                GameMode::Quantum => "quantum",
+                GameMode::Numerical => "numerical",
+                GameMode::OrderChaos => "order_chaos",
+                GameMode::Multiplayer => "multiplayer"
+            }
+        }
+
+        /// Returns true if this mode is played on a single [GameBoard](crate::gameboard::GameBoard),
+        /// rather than on a [Variant](crate::variants::Variant)
+        /// 
+        /// Only these modes can be played over the network
+        pub fn is_classic(&self) -> bool
+        {
+            matches!(self, GameMode::Classic | GameMode::Reverse)
+        }
+    }
+
+    impl std::str::FromStr for GameMode {
+        type Err = String;
+
+        /// Parses a mode from its [name](GameMode::name)
+        fn from_str(name: &str) -> Result<Self, Self::Err> {
+            GameMode::ALL.into_iter()
+                .find(|game_mode|{game_mode.name() == name})
+                .ok_or_else(||{format!("unknown game mode '{}'", name)})
+        }
+    }
+
+    /// Which player plays Order in [GameMode::OrderChaos]
+    #[derive(Default, Debug, PartialEq, Eq, Clone, Copy)]
+    pub enum OrderRole {
+        /// Whoever would move first plays Order, so the roles change hands with the first move
+        #[default]
+        FirstPlayer,
+        PlayerX,
+        PlayerO
+    }
+
+    /// Settings that only apply to some game modes
+    #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+    pub struct VariantOptions {
+        /// In [GameMode::Morris], whether pieces may move diagonally
+        pub diagonal_moves: bool,
+        /// In [GameMode::Notakto], the number of boards
+        pub board_count: usize,
+        /// In [GameMode::Gravity] and [GameMode::Multiplayer], the number of columns of the board
+        pub board_columns: usize,
+        /// In [GameMode::Gravity] and [GameMode::Multiplayer], the number of rows of the board
+        pub board_rows: usize,
+        /// In [GameMode::Gravity] and [GameMode::Multiplayer], the number of pieces in a row
+        /// needed to win
+        pub line_length: usize,
+        /// In [GameMode::Multiplayer], the number of players
+        pub players: usize,
+        /// In [GameMode::OrderChaos], which player plays Order
+        pub order_role: OrderRole,
+        /// In two player games played on a [Variant](crate::variants::Variant), whether the
+        /// second player may swap sides with the first player instead of replying to their
+        /// first move (the pie rule)
+        pub swap_rule: bool,
+        /// In [GameMode::Classic] and [GameMode::Reverse], whether lines wrap around the
+        /// edges of the board (see [Rules::toroidal](crate::rules::Rules::toroidal))
+        pub toroidal: bool,
+        /// In [GameMode::Classic] and [GameMode::Reverse], the number of
+        /// [blocked](crate::gameboard::BoardSpace::Blocked) spaces each game starts with
+        pub blocked_spaces: usize,
+        /// In [GameMode::Classic] and [GameMode::Reverse], the number of X's each game starts with
+        pub handicap_x: usize,
+        /// In [GameMode::Classic] and [GameMode::Reverse], the number of O's each game starts with
+        pub handicap_o: usize,
+        /// Whether the blocked spaces and handicap pieces are placed by the user
+        /// before the first game, rather than at random before every game
+        pub choose_starting_position: bool
+    }
+
+    impl Default for VariantOptions {
+        fn default() -> Self
+        {
+            Self{
+                diagonal_moves: false,
+                board_count: 3,
+                board_columns: 7,
+                board_rows: 6,
+                line_length: 4,
+                players: 3,
+                order_role: OrderRole::FirstPlayer,
+                swap_rule: false,
+                toroidal: false,
+                blocked_spaces: 0,
+                handicap_x: 0,
+                handicap_o: 0,
+                choose_starting_position: false
+            }
+        }
+    }
+
     /// Determines how many games will be played before auto-exiting
-    #[derive(Default, PartialEq, Eq)]
+    #[derive(Default, Debug, PartialEq, Eq, Clone, Copy)]
     pub enum GameAutoquitMode {
         /// No limit
         #[default]
@@ -1,52 +1,233 @@
+use std::net::TcpListener;
+
 use tactic::{
-    ui::UI, 
-    game_outcome::GameOutcome, 
-    active_player::ActivePlayer
+    ui::{UI, DEFAULT_SERVE_PORT, key_bindings::KeyBindings, theme::Theme, config}, 
+    network::DEFAULT_PORT,
+    rules::Rules
 };
 
+/// The line of [USAGE] describing `--http`, which is only listed when the `http` feature is enabled
+#[cfg(feature = "http")]
+macro_rules! http_usage {
+    () => {"  --http [PORT]       serve the HTTP/JSON API on PORT (default 4042) instead of playing\n"}
+}
+
+#[cfg(not(feature = "http"))]
+macro_rules! http_usage {
+    () => {""}
+}
+
+const USAGE: &str = concat!("\
+Usage: tactic [--keys FILE] [--theme THEME] [OPTION]
+
+Options:
+  --host [PORT]       host a game that another player can join over the network
+  --join ADDRESS      join a game hosted at ADDRESS (host or host:port)
+  --spectate ADDRESS  watch a game hosted at ADDRESS (host or host:port)
+  --serve [PORT]      let others play against the AI by connecting with telnet or nc
+                      to PORT (default 4000)
+  --rules FILE        play local games with the winning lines and patterns in FILE
+                      (can't be combined with the options above)
+  --keys FILE         use the key bindings in FILE instead of those in the configuration
+                      directory (can be given with any other option)
+  --theme THEME       draw boards in THEME: classic, high-contrast, colour-blind or
+                      monochrome, instead of the theme in the configuration directory
+                      (can be given with any other option)
+", http_usage!(),
+"  -h, --help          print this message");
+
+/// How the game was launched, as determined by command line arguments
+enum LaunchMode {
+    Help,
+    /// A local game, with the rules loaded from a rules file if one was given
+    Local(Option<Rules>),
+    Host(u16),
+    Join(String),
+    Spectate(String),
+    Serve(u16),
+    #[cfg(feature = "http")]
+    Http(u16)
+}
+
+/// Settings chosen on the command line that apply however the game was launched
+struct Preferences {
+    /// The key bindings loaded from a key bindings file, or from the configuration directory
+    /// if no file was given
+    key_bindings: Option<KeyBindings>,
+    /// The theme chosen, or the theme in the configuration directory if none was chosen
+    theme: Option<Theme>
+}
+
+/// Parses the command line arguments into a [LaunchMode] and [Preferences]
+/// 
+/// Returns `Err` with a message to print if the arguments are invalid
+fn parse_args() -> Result<(LaunchMode, Preferences), String>
+{
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    let key_bindings = match take_option_value(&mut args, "--keys", "a file")? {
+        Some(path) => Some(KeyBindings::from_file(&path)
+            .map_err(|error|{format!("Invalid key bindings file '{}': {}", path, error)})?),
+        None => config::load_key_bindings().map_err(|error|{
+            let path = config::key_bindings_path().unwrap_or_default();
+            format!("Invalid key bindings file '{}': {}", path.display(), error)
+        })?
+    };
+    let theme = match take_option_value(&mut args, "--theme", "a theme")? {
+        Some(name) => Some(Theme::from_name(&name)
+            .ok_or_else(||{format!("Unknown theme '{}'\n\n{}", name, USAGE)})?),
+        None => config::load_theme().map_err(|error|{
+            let path = config::theme_path().unwrap_or_default();
+            format!("Invalid theme file '{}': {}", path.display(), error)
+        })?
+    };
+    let preferences = Preferences{key_bindings, theme};
+
+    let rules = match take_option_value(&mut args, "--rules", "a file")? {
+        // the network protocol and the servers only support the standard rules
+        Some(_) if !args.is_empty() => {
+            return Err(format!("--rules can only be used for local games\n\n{}", USAGE));
+        },
+        Some(path) => Some(Rules::from_file(&path)
+            .map_err(|error|{format!("Invalid rules file '{}': {}", path, error)})?),
+        None => None
+    };
+
+    let mut args = args.into_iter();
+    let launch_mode = match args.next().as_deref() {
+        None => LaunchMode::Local(rules),
+        Some("--host") => match args.next() {
+            None => LaunchMode::Host(DEFAULT_PORT),
+            Some(port) => LaunchMode::Host(port.parse()
+                .map_err(|_|{format!("Invalid port '{}'\n\n{}", port, USAGE)})?)
+        },
+        Some("--join") => LaunchMode::Join(parse_address(args.next(), "--join")?),
+        Some("--spectate") => LaunchMode::Spectate(parse_address(args.next(), "--spectate")?),
+        Some("--serve") => match args.next() {
+            None => LaunchMode::Serve(DEFAULT_SERVE_PORT),
+            Some(port) => LaunchMode::Serve(port.parse()
+                .map_err(|_|{format!("Invalid port '{}'\n\n{}", port, USAGE)})?)
+        },
+        #[cfg(feature = "http")]
+        Some("--http") => match args.next() {
+            None => LaunchMode::Http(tactic::http_api::DEFAULT_PORT),
+            Some(port) => LaunchMode::Http(port.parse()
+                .map_err(|_|{format!("Invalid port '{}'\n\n{}", port, USAGE)})?)
+        },
+        Some("-h" | "--help") => LaunchMode::Help,
+        Some(other) => return Err(format!("Unknown option '{}'\n\n{}", other, USAGE))
+    };
+
+    if args.next().is_some() {
+        return Err(format!("Too many arguments\n\n{}", USAGE));
+    }
+    Ok((launch_mode, preferences))
+}
+
+/// Removes the given option and the value after it from `args`, wherever they are,
+/// and returns the value
+/// 
+/// Returns `Err` with a message to print if the option has no value, naming the `expected` value
+fn take_option_value(args: &mut Vec<String>, option: &str, expected: &str) -> Result<Option<String>, String>
+{
+    match args.iter().position(|arg|{arg == option}) {
+        None => Ok(None),
+        Some(index) if index + 1 >= args.len() => Err(format!("{} requires {}\n\n{}", option, expected, USAGE)),
+        Some(index) => {
+            let value = args.remove(index + 1);
+            args.remove(index);
+            Ok(Some(value))
+        }
+    }
+}
+
+/// Returns the given address, adding the default port if none was given
+/// 
+/// Returns `Err` with a message to print if no address was given
+fn parse_address(address: Option<String>, option: &str) -> Result<String, String>
+{
+    match address {
+        Some(address) if address.contains(':') => Ok(address),
+        Some(address) => Ok(format!("{}:{}", address, DEFAULT_PORT)),
+        None => Err(format!("{} requires an address\n\n{}", option, USAGE))
+    }
+}
+
 fn main() -> crossterm::Result<()>
 {
-    let mut ui = UI::new()?;
+    let (launch_mode, preferences) = match parse_args() {
+        Ok(parsed) => parsed,
+        Err(message) => {
+            eprintln!("{}", message);
+            std::process::exit(2);
+        }
+    };
 
-    ui.setup_menu()?;
+    if let LaunchMode::Help = launch_mode {
+        println!("{}", USAGE);
+        return Ok(());
+    }
 
-    loop {
-        let game_outcome = ui.game_loop()?;
-        if game_outcome == GameOutcome::Incomplete || 
-            ui.autoquit_satisfied() || 
-            !ui.play_again_menu()? {
-            break;
-        } else {
-            match game_outcome {
-                GameOutcome::PlayerX(_) => {*ui.active_player_mut() = ActivePlayer::PlayerO},
-                GameOutcome::PlayerO(_) => {*ui.active_player_mut() = ActivePlayer::PlayerX},
-                _ => {
-                    // do nothing if neither player won
-                    // the active player will flip-flop naturally
-                }
+    if let LaunchMode::Serve(port) = launch_mode {
+        let listener = TcpListener::bind(("0.0.0.0", port))?;
+        println!("tactic serving games on port {}", port);
+        return UI::serve(listener);
+    }
+
+    #[cfg(feature = "http")]
+    if let LaunchMode::Http(port) = launch_mode {
+        let listener = TcpListener::bind(("0.0.0.0", port))?;
+        println!("tactic HTTP API listening on port {}", port);
+        return tactic::http_api::run(listener);
+    }
+
+    let mut ui = UI::new()?;
+    if let Some(key_bindings) = preferences.key_bindings {
+        ui.set_key_bindings(key_bindings);
+    }
+    if let Some(theme) = preferences.theme {
+        ui.set_theme(theme);
+    }
+    let initial_theme = ui.theme();
+
+    match launch_mode {
+        LaunchMode::Help | LaunchMode::Serve(_) => unreachable!(),
+        #[cfg(feature = "http")]
+        LaunchMode::Http(_) => unreachable!(),
+        LaunchMode::Local(rules) => {
+            if let Some(rules) = rules {
+                ui.set_rules(rules);
             }
-        }
+            ui.setup_menu()?
+        },
+        LaunchMode::Host(port) => ui.host_game(port)?,
+        LaunchMode::Join(address) => ui.join_game(&address)?,
+        LaunchMode::Spectate(address) => ui.spectate(&address)?
+    }
+
+    // remember a theme chosen in the setup menu for next time
+    let theme_saved = if ui.theme() != initial_theme {
+        config::save_theme(ui.theme())
+    } else {
+        Ok(())
     };
 
-    let player_x_score = ui.player_x_score();
-    let player_o_score = ui.player_o_score();
+    ui.play_games()?;
+
+    let scores: Vec<_> = ui.seats().map(|seat|{(seat, ui.score(seat))}).collect();
     let number_of_draws = ui.number_of_draws();
     let number_of_games = ui.number_of_games();
-    let final_game_board = ui.take_game_board();
+    let final_board = ui.final_board();
+    drop(ui);
 
-    println!("Final board: {}", final_game_board);
-    println!("X score:     {}\t({:.2}%)", player_x_score, 
-        if number_of_games != 0 {
-            ((player_x_score as f64)/(number_of_games as f64))*100.0
-        } else {
-            0.0
-        });
-    println!("O score:     {}\t({:.2}%)", player_o_score, 
-        if number_of_games != 0 {
-            ((player_o_score as f64)/(number_of_games as f64))*100.0
-        } else {
-            0.0
-        });
+    println!("Final board: {}", final_board);
+    for (seat, score) in scores {
+        println!("{} score:     {}\t({:.2}%)", seat.get_char(), score, 
+            if number_of_games != 0 {
+                (score/(number_of_games as f64))*100.0
+            } else {
+                0.0
+            });
+    }
     println!("Draws:       {}\t({:.2}%)", number_of_draws,
         if number_of_games != 0 {
             ((number_of_draws as f64)/(number_of_games as f64))*100.0
@@ -54,6 +235,10 @@ fn main() -> crossterm::Result<()>
             0.0    
         });
     println!("Total Games: {}", number_of_games);
+
+    if let Err(error) = theme_saved {
+        eprintln!("Couldn't save the theme: {}", error);
+    }
     
     Ok(())
 }
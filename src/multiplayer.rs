@@ -0,0 +1,381 @@
+//! Games for three or four players
+//!
+//! Each player has a [Seat], which decides their symbol and when they play. Players take turns
+//! in seat order, each claiming one empty cell of a board larger than the classic board, and the
+//! first player to complete a line of their own symbol wins.
+
+use std::sync::Arc;
+
+use crate::{
+    active_player::ActivePlayer,
+    variants::{BoardView, CellView, CellStyle, winning_lines}
+};
+
+/// One of the players of a game, in turn order
+///
+/// The first two seats are the X and O players of a two player game.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Seat {
+    X,
+    O,
+    Triangle,
+    Square
+}
+
+impl Seat {
+
+    /// The number of seats; the most players a game can have
+    pub const COUNT: usize = 4;
+
+    /// Returns an iterator over every `Seat`, in turn order
+    pub fn all() -> impl Iterator<Item = Self>
+    {
+        const SEATS: [Seat; Seat::COUNT] = [Seat::X, Seat::O, Seat::Triangle, Seat::Square];
+
+        SEATS.into_iter()
+    }
+
+    /// Returns an iterator over the seats of a game with the given number of players, in turn order
+    pub fn first(players: usize) -> impl Iterator<Item = Self>
+    {
+        Self::all().take(players)
+    }
+
+    /// Returns the position of this seat in the turn order, starting at 0
+    pub fn index(&self) -> usize
+    {
+        match self {
+            Self::X => 0,
+            Self::O => 1,
+            Self::Triangle => 2,
+            Self::Square => 3
+        }
+    }
+
+    /// Returns the character representing this seat's pieces
+    pub fn get_char(&self) -> char
+    {
+        match self {
+            Self::X => 'X',
+            Self::O => 'O',
+            Self::Triangle => '△',
+            Self::Square => '□'
+        }
+    }
+
+    /// Returns the seat that plays after this one in a game with the given number of players
+    pub fn next(&self, players: usize) -> Self
+    {
+        Self::all().nth((self.index() + 1) % players.max(1)).unwrap_or(Self::X)
+    }
+}
+
+impl From<ActivePlayer> for Seat {
+    fn from(player: ActivePlayer) -> Self
+    {
+        match player {
+            ActivePlayer::PlayerX => Self::X,
+            ActivePlayer::PlayerO => Self::O
+        }
+    }
+}
+
+/// The outcome of a [MultiplayerGame], if any
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MultiplayerOutcome {
+    /// The game was won by the player in the given seat
+    Win(Seat),
+    /// The board is full, and nobody completed a line
+    Draw,
+    /// Nobody has won, and there are still empty cells
+    Incomplete
+}
+
+impl MultiplayerOutcome {
+    /// Returns `true` if the game is finished
+    pub fn game_finished(&self) -> bool
+    {
+        !matches!(self, Self::Incomplete)
+    }
+}
+
+/// A game for three or four players
+///
+/// Cells are numbered row by row from the top left, so the cell in column `x` of row `y`
+/// has index `y * columns + x`.
+#[derive(Debug, Clone)]
+pub struct MultiplayerGame {
+    columns: usize,
+    rows: usize,
+    players: usize,
+    cells: Vec<Option<Seat>>,
+    active_seat: Seat,
+    /// The cell of the last piece played, if any
+    last_move: Option<usize>,
+    /// Every line of cells that wins the game, shared between copies of the game
+    lines: Arc<Vec<Vec<usize>>>
+}
+
+impl MultiplayerGame {
+
+    /// The fewest players a game can have
+    pub const MIN_PLAYERS: usize = 3;
+    /// The most players a game can have
+    pub const MAX_PLAYERS: usize = Seat::COUNT;
+    /// The smallest number of columns a board can have
+    pub const MIN_COLUMNS: usize = 3;
+    /// The largest number of columns a board can have
+    pub const MAX_COLUMNS: usize = 10;
+    /// The smallest number of rows a board can have
+    pub const MIN_ROWS: usize = 3;
+    /// The largest number of rows a board can have
+    pub const MAX_ROWS: usize = 8;
+    /// The shortest winning line
+    pub const MIN_LINE_LENGTH: usize = 3;
+    /// The longest winning line
+    pub const MAX_LINE_LENGTH: usize = 6;
+
+    /// Creates a new game for the given number of `players` on a board with the given number of
+    /// `columns` and `rows`, where `line_length` pieces in a row are needed to win, with
+    /// `first_seat` to move first
+    ///
+    /// Each setting is clamped to the range allowed by the constants above, and `line_length`
+    /// is also limited to the larger of `columns` and `rows`. If `first_seat` isn't one of the
+    /// players' seats, X moves first.
+    pub fn new(players: usize, first_seat: Seat, columns: usize, rows: usize, line_length: usize) -> Self
+    {
+        let players = players.clamp(Self::MIN_PLAYERS, Self::MAX_PLAYERS);
+        let columns = columns.clamp(Self::MIN_COLUMNS, Self::MAX_COLUMNS);
+        let rows = rows.clamp(Self::MIN_ROWS, Self::MAX_ROWS);
+        let line_length = line_length.clamp(Self::MIN_LINE_LENGTH, Self::MAX_LINE_LENGTH).min(columns.max(rows));
+
+        Self{
+            columns,
+            rows,
+            players,
+            cells: vec![None; columns * rows],
+            active_seat: if first_seat.index() < players {first_seat} else {Seat::X},
+            last_move: None,
+            lines: Arc::new(winning_lines(columns, rows, line_length))
+        }
+    }
+
+    /// Returns the number of players
+    pub fn players(&self) -> usize
+    {
+        self.players
+    }
+
+    /// Returns the number of columns of the board
+    pub fn columns(&self) -> usize
+    {
+        self.columns
+    }
+
+    /// Returns the number of rows of the board
+    pub fn rows(&self) -> usize
+    {
+        self.rows
+    }
+
+    /// Returns the seat of the player whose turn it is
+    pub fn active_seat(&self) -> Seat
+    {
+        self.active_seat
+    }
+
+    /// Returns every cell the active player may claim
+    ///
+    /// Returns an empty list if the game is finished
+    pub fn legal_moves(&self) -> Vec<usize>
+    {
+        if self.outcome().game_finished() {
+            return Vec::new();
+        }
+        (0..self.cells.len()).filter(|cell|{self.cells[*cell].is_none()}).collect()
+    }
+
+    /// Claims the given cell for the active player and passes the turn to the next player
+    ///
+    /// The cell must be one of those returned by [MultiplayerGame::legal_moves]
+    pub fn play_move(&mut self, cell: usize)
+    {
+        self.cells[cell] = Some(self.active_seat);
+        self.last_move = Some(cell);
+        self.active_seat = self.active_seat.next(self.players);
+    }
+
+    /// Returns the outcome of the game so far
+    pub fn outcome(&self) -> MultiplayerOutcome
+    {
+        if let (Some(_), Some(last_move)) = (self.winning_line(), self.last_move) {
+            // only the player who moved last can have completed a line
+            MultiplayerOutcome::Win(self.cells[last_move].unwrap_or(Seat::X))
+        } else if self.cells.iter().all(Option::is_some) {
+            MultiplayerOutcome::Draw
+        } else {
+            MultiplayerOutcome::Incomplete
+        }
+    }
+
+    /// Returns the line completed by the last piece played, if any
+    fn winning_line(&self) -> Option<&Vec<usize>>
+    {
+        let last_move = self.last_move?;
+        let seat = self.cells[last_move];
+        self.lines.iter()
+            .filter(|line|{line.contains(&last_move)})
+            .find(|line|{line.iter().all(|cell|{self.cells[*cell] == seat})})
+    }
+
+    /// Estimates how likely the player in `seat` is to win from the current (unfinished) position
+    ///
+    /// Lines that only hold one player's pieces count for that player, and against everyone else,
+    /// so a player is weighed against all of their opponents together.
+    ///
+    /// Returns a value from `-1.0` (`seat` is sure to lose) to `1.0` (`seat` is sure to win)
+    pub fn heuristic(&self, seat: Seat) -> f64
+    {
+        let line_length = self.lines.first().map_or(0, Vec::len);
+
+        let score: f64 = self.lines.iter().map(|line|{
+            let mut owner = None;
+            let mut pieces = 0;
+            for cell in line {
+                match (self.cells[*cell], owner) {
+                    (None, _) => {},
+                    (Some(piece), None) => {
+                        owner = Some(piece);
+                        pieces += 1;
+                    },
+                    (Some(piece), Some(line_owner)) if piece == line_owner => pieces += 1,
+                    // a line with more than one player's pieces can't be completed
+                    _ => return 0.0
+                }
+            }
+
+            let Some(owner) = owner else {
+                return 0.0;
+            };
+            let mut value = (pieces * pieces) as f64;
+            if pieces + 1 == line_length {
+                // a line that only needs one more piece is a threat
+                value *= 2.0;
+            }
+            if owner == seat {value} else {-value}
+        }).sum();
+
+        (score / (4 * line_length * line_length) as f64).tanh()
+    }
+
+    /// Describes how to draw the board
+    ///
+    /// Pieces are styled by [seat](CellStyle::Seat), except those in a completed line.
+    pub fn boards(&self) -> Vec<BoardView>
+    {
+        let winning_line = self.winning_line();
+
+        let cells = self.cells.iter().enumerate().map(|(cell, seat)|{
+            CellView{
+                cell,
+                symbol: seat.map_or(' ', |seat|{seat.get_char()}),
+                subscript: None,
+                style: match seat {
+                    _ if winning_line.is_some_and(|line|{line.contains(&cell)}) => CellStyle::Winning,
+                    Some(seat) => CellStyle::Seat(*seat),
+                    None => CellStyle::Normal
+                }
+            }
+        }).collect();
+
+        vec![BoardView{
+            position: (0, 0),
+            columns: self.columns as u16,
+            cells,
+            style: CellStyle::Normal,
+            label: None
+        }]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Plays the given cells in order
+    fn play(game: &mut MultiplayerGame, cells: &[usize])
+    {
+        for cell in cells {
+            assert!(game.legal_moves().contains(cell), "{} should be legal", cell);
+            game.play_move(*cell);
+        }
+    }
+
+    #[test]
+    fn settings_are_clamped()
+    {
+        let game = MultiplayerGame::new(1, Seat::X, 1, 1, 1);
+        assert_eq!((game.players(), game.columns(), game.rows()), (3, 3, 3));
+        assert_eq!(game.lines.len(), 8);
+
+        // 5 lines along each of the 8 rows, 3 along each of the 10 columns, and 15 in each
+        // diagonal direction
+        let game = MultiplayerGame::new(9, Seat::X, 20, 20, 9);
+        assert_eq!((game.players(), game.columns(), game.rows()), (4, 10, 8));
+        assert_eq!(game.lines.len(), 8 * 5 + 10 * 3 + 2 * 5 * 3);
+        assert!(game.lines.iter().all(|line|{line.len() == 6}));
+    }
+
+    #[test]
+    fn line_length_is_limited_to_the_longer_side()
+    {
+        // only the columns are long enough for lines of 4
+        let game = MultiplayerGame::new(3, Seat::X, 3, 4, 6);
+        assert_eq!(game.lines.as_slice(), [vec![0, 3, 6, 9], vec![1, 4, 7, 10], vec![2, 5, 8, 11]]);
+    }
+
+    #[test]
+    fn players_move_in_seat_order()
+    {
+        let mut game = MultiplayerGame::new(3, Seat::X, 5, 5, 4);
+        let mut seats = Vec::new();
+        for cell in 0..4 {
+            seats.push(game.active_seat());
+            game.play_move(cell * 5);
+        }
+        assert_eq!(seats, [Seat::X, Seat::O, Seat::Triangle, Seat::X]);
+
+        let game = MultiplayerGame::new(4, Seat::Square, 5, 5, 4);
+        assert_eq!(game.active_seat(), Seat::Square);
+        assert_eq!(Seat::Square.next(4), Seat::X);
+
+        // the square seat isn't in a three player game
+        let game = MultiplayerGame::new(3, Seat::Square, 5, 5, 4);
+        assert_eq!(game.active_seat(), Seat::X);
+    }
+
+    #[test]
+    fn player_who_completes_a_line_wins()
+    {
+        let mut game = MultiplayerGame::new(3, Seat::X, 4, 4, 3);
+        // △ fills the third column while X and O play elsewhere
+        play(&mut game, &[0, 1, 2, 7, 13, 6, 15, 3]);
+        assert_eq!(game.outcome(), MultiplayerOutcome::Incomplete);
+        play(&mut game, &[10]);
+        assert_eq!(game.outcome(), MultiplayerOutcome::Win(Seat::Triangle));
+        assert!(game.legal_moves().is_empty());
+    }
+
+    #[test]
+    fn full_board_without_a_line_is_a_draw()
+    {
+        // X O △
+        // O △ X
+        // X O △
+        let mut game = MultiplayerGame::new(3, Seat::X, 3, 3, 3);
+        play(&mut game, &[0, 1, 2, 5, 3, 4, 6, 7]);
+        assert_eq!(game.outcome(), MultiplayerOutcome::Incomplete);
+        play(&mut game, &[8]);
+        assert_eq!(game.outcome(), MultiplayerOutcome::Draw);
+    }
+}
@@ -0,0 +1,245 @@
+//! Utilities for playing games over a TCP connection
+//!
+//! One `tactic` instance hosts a game by listening on a TCP port, and another
+//! instance joins it by connecting to that port. Both sides then exchange
+//! [Message]s as described in the [protocol] module.
+
+use std::{
+    io::{self, BufRead, BufReader, Write},
+    net::{TcpStream, ToSocketAddrs},
+    sync::mpsc::{self, Receiver, RecvTimeoutError, TryRecvError},
+    fmt::Display,
+    thread,
+    time::Duration
+};
+
+pub mod protocol;
+use protocol::{Message, NetworkSettings, ProtocolError, Role, PROTOCOL_VERSION};
+
+pub mod spectators;
+
+/// The port used when hosting or joining a game if no port is specified
+pub const DEFAULT_PORT: u16 = 4040;
+
+/// Reasons why a network operation may fail
+#[derive(Debug)]
+pub enum NetworkError {
+    /// An underlying I/O operation failed
+    Io(io::Error),
+    /// The other side sent a message that could not be parsed
+    Protocol(ProtocolError),
+    /// The other side sent a valid message at an unexpected time
+    UnexpectedMessage(Message),
+    /// The other side uses a different protocol version
+    VersionMismatch(u32),
+    /// The other side refused the connection
+    Rejected(String),
+    /// The connection was closed
+    Disconnected
+}
+
+impl Display for NetworkError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "{}", error),
+            Self::Protocol(error) => write!(f, "protocol error: {}", error),
+            Self::UnexpectedMessage(message) => write!(f, "unexpected message '{}'", message),
+            Self::VersionMismatch(version) => write!(f,
+                "protocol version mismatch (ours is {}, theirs is {})", PROTOCOL_VERSION, version),
+            Self::Rejected(reason) => write!(f, "connection rejected: {}", reason),
+            Self::Disconnected => write!(f, "connection closed")
+        }
+    }
+}
+
+impl std::error::Error for NetworkError {}
+
+impl From<io::Error> for NetworkError {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+impl From<NetworkError> for io::Error {
+    fn from(error: NetworkError) -> Self {
+        match error {
+            NetworkError::Io(error) => error,
+            other => io::Error::other(other)
+        }
+    }
+}
+
+/// A connection to another `tactic` instance
+///
+/// Incoming messages are read on a background thread, so checking for a message
+/// with [Connection::try_recv] never blocks.
+pub struct Connection {
+    stream: TcpStream,
+    incoming: Receiver<Result<Message, NetworkError>>
+}
+
+impl Connection {
+
+    /// Wraps the given [TcpStream], spawning a thread to read incoming messages
+    pub fn new(stream: TcpStream) -> io::Result<Self>
+    {
+        let reader = BufReader::new(stream.try_clone()?);
+        let (sender, incoming) = mpsc::channel();
+
+        thread::spawn(move ||{
+            for line in reader.lines() {
+                let message = match line {
+                    Ok(line) => line.trim().parse().map_err(NetworkError::Protocol),
+                    Err(error) => Err(NetworkError::Io(error))
+                };
+                let is_err = message.is_err();
+                // stop reading if the receiving end has been dropped
+                if sender.send(message).is_err() || is_err {
+                    return;
+                }
+            }
+            // ignore send error; the receiving end may have been dropped already
+            let _ = sender.send(Err(NetworkError::Disconnected));
+        });
+
+        Ok(Self{stream, incoming})
+    }
+
+    /// Connects to the given address and wraps the resulting [TcpStream]
+    pub fn connect(address: impl ToSocketAddrs) -> io::Result<Self>
+    {
+        Self::new(TcpStream::connect(address)?)
+    }
+
+    /// Sends a message to the other side
+    pub fn send(&self, message: &Message) -> Result<(), NetworkError>
+    {
+        let mut stream = &self.stream;
+        writeln!(stream, "{}", message)?;
+        stream.flush()?;
+        Ok(())
+    }
+
+    /// Returns the next incoming message, or `Ok(None)` if no message is waiting
+    pub fn try_recv(&self) -> Result<Option<Message>, NetworkError>
+    {
+        match self.incoming.try_recv() {
+            Ok(message) => message.map(Some),
+            Err(TryRecvError::Empty) => Ok(None),
+            Err(TryRecvError::Disconnected) => Err(NetworkError::Disconnected)
+        }
+    }
+
+    /// Blocks until a message arrives or `timeout` passes
+    ///
+    /// Returns `Ok(None)` if no message arrived in time
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<Option<Message>, NetworkError>
+    {
+        match self.incoming.recv_timeout(timeout) {
+            Ok(message) => message.map(Some),
+            Err(RecvTimeoutError::Timeout) => Ok(None),
+            Err(RecvTimeoutError::Disconnected) => Err(NetworkError::Disconnected)
+        }
+    }
+
+    /// Blocks until a message arrives
+    pub fn recv(&self) -> Result<Message, NetworkError>
+    {
+        match self.incoming.recv() {
+            Ok(message) => message,
+            Err(_) => Err(NetworkError::Disconnected)
+        }
+    }
+
+    /// Performs the host side of the handshake
+    ///
+    /// Waits for the joiner's `HELLO`, replies with our own, then sends `settings` and waits
+    /// for the joiner to accept them.
+    pub fn host_handshake(&self, settings: &NetworkSettings) -> Result<(), NetworkError>
+    {
+        let role = self.exchange_hello(Role::Player)?;
+        if role != Role::Player {
+            let reason = "the game has not started yet";
+            self.reject(reason);
+            return Err(NetworkError::Rejected(reason.to_owned()));
+        }
+        self.send(&Message::Settings(settings.clone()))?;
+        match self.recv()? {
+            Message::Accept => Ok(()),
+            Message::Reject(reason) => Err(NetworkError::Rejected(reason)),
+            other => Err(NetworkError::UnexpectedMessage(other))
+        }
+    }
+
+    /// Performs the joiner side of the handshake
+    ///
+    /// Exchanges `HELLO`s with the host, then accepts and returns the host's settings,
+    /// unless they are for a mode that can't be played over the network
+    pub fn join_handshake(&self) -> Result<NetworkSettings, NetworkError>
+    {
+        self.exchange_hello(Role::Player)?;
+        match self.recv()? {
+            Message::Settings(settings) if !settings.game_mode.is_classic() => {
+                let reason = format!("game mode '{}' can't be played over the network", settings.game_mode.name());
+                self.reject(&reason);
+                Err(NetworkError::Rejected(reason))
+            },
+            Message::Settings(settings) => {
+                self.send(&Message::Accept)?;
+                Ok(settings)
+            },
+            Message::Reject(reason) => Err(NetworkError::Rejected(reason)),
+            other => Err(NetworkError::UnexpectedMessage(other))
+        }
+    }
+
+    /// Performs the spectator side of the handshake
+    ///
+    /// Exchanges `HELLO`s with the host, then returns the host's settings.
+    /// Unlike a joining player, a spectator does not reply to the settings.
+    pub fn spectator_handshake(&self) -> Result<NetworkSettings, NetworkError>
+    {
+        self.exchange_hello(Role::Spectator)?;
+        match self.recv()? {
+            Message::Settings(settings) if !settings.game_mode.is_classic() => Err(NetworkError::Rejected(
+                format!("game mode '{}' can't be watched over the network", settings.game_mode.name())
+            )),
+            Message::Settings(settings) => Ok(settings),
+            Message::Reject(reason) => Err(NetworkError::Rejected(reason)),
+            other => Err(NetworkError::UnexpectedMessage(other))
+        }
+    }
+
+    /// Sends our `HELLO` and checks the version in the other side's `HELLO`
+    /// 
+    /// Returns the role given in the other side's `HELLO`
+    fn exchange_hello(&self, role: Role) -> Result<Role, NetworkError>
+    {
+        self.send(&Message::Hello(PROTOCOL_VERSION, role))?;
+        match self.recv()? {
+            Message::Hello(PROTOCOL_VERSION, role) => Ok(role),
+            Message::Hello(version, _) => {
+                self.reject(&format!("unsupported protocol version {}", version));
+                Err(NetworkError::VersionMismatch(version))
+            },
+            Message::Reject(reason) => Err(NetworkError::Rejected(reason)),
+            other => Err(NetworkError::UnexpectedMessage(other))
+        }
+    }
+
+    /// Tells the other side why we are refusing the connection
+    pub fn reject(&self, reason: &str)
+    {
+        // ignore failure as we are leaving anyway
+        let _ = self.send(&Message::Reject(reason.to_owned()));
+    }
+}
+
+impl Drop for Connection {
+    /// Shuts down the connection, which also stops the reading thread.
+    /// [Read More](https://doc.rust-lang.org/1.62.1/core/ops/trait.Drop.html#tymethod.drop)
+    fn drop(&mut self) {
+        // ignore failure; the connection may already be closed
+        let _ = self.stream.shutdown(std::net::Shutdown::Both);
+    }
+}
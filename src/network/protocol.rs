@@ -0,0 +1,363 @@
+//! Messages exchanged between networked `tactic` instances
+//!
+//! The protocol is line based; each message is a single line of ASCII text,
+//! made up of a keyword followed by zero or more space separated fields.
+//!
+//! | Message                                       | Sent by | Meaning                                           |
+//! |-----------------------------------------------|---------|---------------------------------------------------|
+//! | `HELLO <version> [role]`                      | both    | First message on a connection; protocol version   |
+//! | `SETTINGS <mode> <limit> <value> <side>`      | host    | Game settings, and which side the joiner plays    |
+//! | `ACCEPT`                                      | joiner  | Joiner accepts the settings; the game begins      |
+//! | `REJECT <reason>`                             | both    | Connection or game refused (e.g. illegal move)    |
+//! | `MOVE <x> <y>`                                | both    | Active player claimed the space at `(x,y)`        |
+//! | `RESIGN`                                      | both    | Sender forfeits the current game                  |
+//! | `REMATCH <yes\|no>`                           | both    | Sender's answer to "play again?"                  |
+//! | `QUIT`                                        | both    | Sender is leaving; the connection will be closed  |
+//!
+//! `<mode>` is the [name](GameMode::name) of a game mode, `<limit>` is one of `unlimited`,
+//! `games`, `wins` or `score`, `<value>` is the game limit value and `<side>` is `X` or `O`.
+//! Only [classic](GameMode::is_classic) modes (`classic` and `reverse`) can be played over the
+//! network, so the joiner answers `SETTINGS` with any other mode with `REJECT`.
+//! 
+//! A `REJECT` during a game ends it: it is sent in response to a `MOVE` made out of turn
+//! or onto an occupied space, and the connection is then closed.
+//!
+//! `[role]` is `player` (the default if omitted) or `spectator`. A spectator connects to the
+//! host after the game has started and receives the `SETTINGS` followed by the messages below,
+//! but never sends anything other than `HELLO` and `QUIT`. Spectators also receive every `MOVE`.
+//!
+//! | Message                                       | Meaning                                                     |
+//! |-----------------------------------------------|-------------------------------------------------------------|
+//! | `BOARD <spaces> <side>`                       | Current board and whose turn it is; sent at each game start |
+//! | `SCORE <x> <o> <draws>`                       | Current scores; sent after each game                        |
+//! | `RESIGNED <side>`                             | The given side resigned the current game                    |
+//!
+//! `<spaces>` is 9 characters (`X`, `O`, or `-` for empty), one per space from
+//! top left to bottom right, row by row.
+
+use std::{fmt::Display, str::FromStr};
+
+use crate::{
+    gameboard::{GameBoard, BoardSpace, BoardSpaceLocation},
+    active_player::ActivePlayer,
+    game_settings::{GameMode, GameAutoquitMode}
+};
+
+/// The version of the protocol implemented by this module
+///
+/// Instances only play together if both sides report the same version in their `HELLO`
+pub const PROTOCOL_VERSION: u32 = 2;
+
+/// The role of a connecting `tactic` instance, as given in its `HELLO`
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Role {
+    /// Plays one side of the game
+    Player,
+    /// Only watches the game
+    Spectator
+}
+
+/// Game settings chosen by the host and sent to the joining player
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct NetworkSettings {
+    pub game_mode: GameMode,
+    pub autoquit_mode: GameAutoquitMode,
+    pub autoquit_value: u32,
+    /// The side played by the joining player
+    pub joiner_side: ActivePlayer
+}
+
+/// A single protocol message
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Message {
+    Hello(u32, Role),
+    Settings(NetworkSettings),
+    Accept,
+    Reject(String),
+    Move(BoardSpaceLocation),
+    Resign,
+    Rematch(bool),
+    Quit,
+    Board(GameBoard, ActivePlayer),
+    Score{player_x: u32, player_o: u32, draws: u32},
+    Resigned(ActivePlayer)
+}
+
+/// Reasons why a line could not be parsed into a [Message]
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum ProtocolError {
+    /// The line was empty
+    Empty,
+    /// The line started with an unrecognized keyword
+    UnknownMessage(String),
+    /// A required field was not present
+    MissingField(&'static str),
+    /// A field was present but its value was invalid
+    InvalidField(&'static str, String)
+}
+
+impl Display for ProtocolError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Empty => write!(f, "empty message"),
+            Self::UnknownMessage(keyword) => write!(f, "unknown message '{}'", keyword),
+            Self::MissingField(field) => write!(f, "missing field '{}'", field),
+            Self::InvalidField(field, value) => write!(f, "invalid {} '{}'", field, value)
+        }
+    }
+}
+
+impl std::error::Error for ProtocolError {}
+
+impl Display for Message {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Hello(version, role) => write!(f, "HELLO {} {}", version, match role {
+                Role::Player => "player",
+                Role::Spectator => "spectator"
+            }),
+            Self::Settings(settings) => write!(f, "SETTINGS {} {} {} {}",
+                settings.game_mode.name(),
+                autoquit_mode_name(settings.autoquit_mode),
+                settings.autoquit_value,
+                settings.joiner_side.get_char()
+            ),
+            Self::Accept => write!(f, "ACCEPT"),
+            Self::Reject(reason) => write!(f, "REJECT {}", reason),
+            Self::Move(location) => {
+                let (x, y) = location.as_coordinates();
+                write!(f, "MOVE {} {}", x, y)
+            },
+            Self::Resign => write!(f, "RESIGN"),
+            Self::Rematch(answer) => write!(f, "REMATCH {}", if *answer {"yes"} else {"no"}),
+            Self::Quit => write!(f, "QUIT"),
+            Self::Board(board, active_player) => {
+                let spaces: String = board.all_spaces().map(|(_, space)|{
+                    match space {
+                        BoardSpace::Empty => '-',
+                        other => other.get_char()
+                    }
+                }).collect();
+                write!(f, "BOARD {} {}", spaces, active_player.get_char())
+            },
+            Self::Score{player_x, player_o, draws} => 
+                write!(f, "SCORE {} {} {}", player_x, player_o, draws),
+            Self::Resigned(side) => write!(f, "RESIGNED {}", side.get_char())
+        }
+    }
+}
+
+impl FromStr for Message {
+    type Err = ProtocolError;
+
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        let mut fields = line.split_whitespace();
+        let keyword = fields.next().ok_or(ProtocolError::Empty)?;
+
+        let message = match keyword {
+            "HELLO" => {
+                let version = parse_field(fields.next(), "version")?;
+                let role = match fields.next() {
+                    None | Some("player") => Role::Player,
+                    Some("spectator") => Role::Spectator,
+                    Some(other) => return Err(ProtocolError::InvalidField("role", other.to_owned()))
+                };
+                Self::Hello(version, role)
+            },
+            "SETTINGS" => {
+                let game_mode = required(fields.next(), "mode")?;
+                let game_mode = game_mode.parse()
+                    .map_err(|_|{ProtocolError::InvalidField("mode", game_mode.to_owned())})?;
+                let autoquit_mode = match required(fields.next(), "limit")? {
+                    "unlimited" => GameAutoquitMode::Unlimited,
+                    "games" => GameAutoquitMode::GameNumberLimit,
+                    "wins" => GameAutoquitMode::NonDrawNumberLimit,
+                    "score" => GameAutoquitMode::ScoreNumberLimit,
+                    other => return Err(ProtocolError::InvalidField("limit", other.to_owned()))
+                };
+                let autoquit_value = parse_field(fields.next(), "value")?;
+                let joiner_side = parse_side(required(fields.next(), "side")?)?;
+                Self::Settings(NetworkSettings{game_mode, autoquit_mode, autoquit_value, joiner_side})
+            },
+            "ACCEPT" => Self::Accept,
+            "REJECT" => Self::Reject(fields.collect::<Vec<&str>>().join(" ")),
+            "MOVE" => {
+                let x: u8 = parse_field(fields.next(), "x")?;
+                let y: u8 = parse_field(fields.next(), "y")?;
+                if x > 2 || y > 2 {
+                    return Err(ProtocolError::InvalidField("coordinates", format!("{} {}", x, y)));
+                }
+                Self::Move(BoardSpaceLocation::from_coordinates((x, y)))
+            },
+            "RESIGN" => Self::Resign,
+            "REMATCH" => match required(fields.next(), "answer")? {
+                "yes" => Self::Rematch(true),
+                "no" => Self::Rematch(false),
+                other => return Err(ProtocolError::InvalidField("answer", other.to_owned()))
+            },
+            "QUIT" => Self::Quit,
+            "BOARD" => {
+                let spaces = required(fields.next(), "spaces")?;
+                if spaces.len() != 9 {
+                    return Err(ProtocolError::InvalidField("spaces", spaces.to_owned()));
+                }
+                let mut board = GameBoard::new();
+                for (location, space_char) in BoardSpaceLocation::all().zip(spaces.chars()) {
+                    *board.space_mut(location) = match space_char {
+                        '-' => BoardSpace::Empty,
+                        'X' => BoardSpace::X,
+                        'O' => BoardSpace::O,
+                        _ => return Err(ProtocolError::InvalidField("spaces", spaces.to_owned()))
+                    };
+                }
+                Self::Board(board, parse_side(required(fields.next(), "side")?)?)
+            },
+            "SCORE" => Self::Score{
+                player_x: parse_field(fields.next(), "x")?,
+                player_o: parse_field(fields.next(), "o")?,
+                draws: parse_field(fields.next(), "draws")?
+            },
+            "RESIGNED" => Self::Resigned(parse_side(required(fields.next(), "side")?)?),
+            other => return Err(ProtocolError::UnknownMessage(other.to_owned()))
+        };
+
+        Ok(message)
+    }
+}
+
+/// Returns the protocol name of the given [GameAutoquitMode]
+fn autoquit_mode_name(autoquit_mode: GameAutoquitMode) -> &'static str
+{
+    match autoquit_mode {
+        GameAutoquitMode::Unlimited => "unlimited",
+        GameAutoquitMode::GameNumberLimit => "games",
+        GameAutoquitMode::NonDrawNumberLimit => "wins",
+        GameAutoquitMode::ScoreNumberLimit => "score"
+    }
+}
+
+/// Parses a side (`X` or `O`) into an [ActivePlayer]
+fn parse_side(field: &str) -> Result<ActivePlayer, ProtocolError>
+{
+    match field {
+        "X" => Ok(ActivePlayer::PlayerX),
+        "O" => Ok(ActivePlayer::PlayerO),
+        other => Err(ProtocolError::InvalidField("side", other.to_owned()))
+    }
+}
+
+/// Returns the field, or [ProtocolError::MissingField] if it is not present
+fn required<'a>(field: Option<&'a str>, name: &'static str) -> Result<&'a str, ProtocolError>
+{
+    field.ok_or(ProtocolError::MissingField(name))
+}
+
+/// Parses a required field into any type implementing [FromStr]
+fn parse_field<T: FromStr>(field: Option<&str>, name: &'static str) -> Result<T, ProtocolError>
+{
+    let field = required(field, name)?;
+    field.parse().map_err(|_|{ProtocolError::InvalidField(name, field.to_owned())})
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns one of each kind of message
+    fn every_message() -> Vec<Message>
+    {
+        let mut board = GameBoard::new();
+        *board.space_mut(BoardSpaceLocation::TopLeft) = BoardSpace::X;
+        *board.space_mut(BoardSpaceLocation::BottomRight) = BoardSpace::O;
+
+        let mut messages = vec![
+            Message::Hello(PROTOCOL_VERSION, Role::Player),
+            Message::Hello(PROTOCOL_VERSION, Role::Spectator),
+            Message::Accept,
+            Message::Reject("illegal move".to_owned()),
+            Message::Move(BoardSpaceLocation::MiddleRight),
+            Message::Resign,
+            Message::Rematch(true),
+            Message::Rematch(false),
+            Message::Quit,
+            Message::Board(board, ActivePlayer::PlayerX),
+            Message::Score{player_x: 3, player_o: 1, draws: 2},
+            Message::Resigned(ActivePlayer::PlayerO)
+        ];
+        let autoquit_modes = [
+            GameAutoquitMode::Unlimited,
+            GameAutoquitMode::GameNumberLimit,
+            GameAutoquitMode::NonDrawNumberLimit,
+            GameAutoquitMode::ScoreNumberLimit
+        ];
+        for (game_mode, autoquit_mode) in GameMode::ALL.into_iter().zip(autoquit_modes.into_iter().cycle()) {
+            messages.push(Message::Settings(NetworkSettings{
+                game_mode,
+                autoquit_mode,
+                autoquit_value: 5,
+                joiner_side: ActivePlayer::PlayerO
+            }));
+        }
+        messages
+    }
+
+    #[test]
+    fn messages_round_trip()
+    {
+        for message in every_message() {
+            assert_eq!(message.to_string().parse(), Ok(message));
+        }
+    }
+
+    #[test]
+    fn hello_role_defaults_to_player()
+    {
+        assert_eq!("HELLO 2".parse(), Ok(Message::Hello(2, Role::Player)));
+    }
+
+    #[test]
+    fn reject_keeps_every_word_of_the_reason()
+    {
+        assert_eq!("REJECT the game  is full".parse(), Ok(Message::Reject("the game is full".to_owned())));
+    }
+
+    #[test]
+    fn empty_and_unknown_messages_are_errors()
+    {
+        assert_eq!("".parse::<Message>(), Err(ProtocolError::Empty));
+        assert_eq!("   ".parse::<Message>(), Err(ProtocolError::Empty));
+        assert_eq!("hello 2".parse::<Message>(), Err(ProtocolError::UnknownMessage("hello".to_owned())));
+        assert_eq!("JUMP 1 1".parse::<Message>(), Err(ProtocolError::UnknownMessage("JUMP".to_owned())));
+    }
+
+    #[test]
+    fn missing_fields_are_errors()
+    {
+        assert_eq!("HELLO".parse::<Message>(), Err(ProtocolError::MissingField("version")));
+        assert_eq!("MOVE 1".parse::<Message>(), Err(ProtocolError::MissingField("y")));
+        assert_eq!("SETTINGS classic games".parse::<Message>(), Err(ProtocolError::MissingField("value")));
+        assert_eq!("REMATCH".parse::<Message>(), Err(ProtocolError::MissingField("answer")));
+        assert_eq!("BOARD X--------".parse::<Message>(), Err(ProtocolError::MissingField("side")));
+        assert_eq!("SCORE 1 2".parse::<Message>(), Err(ProtocolError::MissingField("draws")));
+    }
+
+    #[test]
+    fn invalid_fields_are_errors()
+    {
+        let invalid = |field: &'static str, value: &str|{Err(ProtocolError::InvalidField(field, value.to_owned()))};
+
+        assert_eq!("HELLO two".parse::<Message>(), invalid("version", "two"));
+        assert_eq!("HELLO 2 referee".parse::<Message>(), invalid("role", "referee"));
+        assert_eq!("SETTINGS chess unlimited 1 X".parse::<Message>(), invalid("mode", "chess"));
+        assert_eq!("SETTINGS classic forever 1 X".parse::<Message>(), invalid("limit", "forever"));
+        assert_eq!("SETTINGS classic games -1 X".parse::<Message>(), invalid("value", "-1"));
+        assert_eq!("SETTINGS classic games 1 Z".parse::<Message>(), invalid("side", "Z"));
+        assert_eq!("MOVE 3 0".parse::<Message>(), invalid("coordinates", "3 0"));
+        assert_eq!("MOVE -1 0".parse::<Message>(), invalid("x", "-1"));
+        assert_eq!("REMATCH maybe".parse::<Message>(), invalid("answer", "maybe"));
+        assert_eq!("BOARD XO X".parse::<Message>(), invalid("spaces", "XO"));
+        assert_eq!("BOARD XO-----#- X".parse::<Message>(), invalid("spaces", "XO-----#-"));
+        assert_eq!("SCORE 1 two 3".parse::<Message>(), invalid("o", "two"));
+        assert_eq!("RESIGNED x".parse::<Message>(), invalid("side", "x"));
+    }
+}
@@ -0,0 +1,198 @@
+//! Read-only spectator connections for hosted games
+
+use std::{
+    io::ErrorKind,
+    net::{TcpListener, TcpStream},
+    sync::{Arc, Mutex, atomic::{AtomicBool, Ordering}, mpsc::{self, Sender}},
+    thread,
+    time::Duration
+};
+
+use crate::{
+    gameboard::GameBoard,
+    active_player::ActivePlayer
+};
+use super::{
+    Connection,
+    protocol::{Message, NetworkSettings, Role}
+};
+
+/// How long a new spectator may take to complete the handshake before it is disconnected
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long a message may take to send before the spectator is treated as disconnected
+const WRITE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How often the listener checks for new spectators and whether the hub has been dropped
+const ACCEPT_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Accepts spectator connections for a hosted game and forwards game updates to them
+///
+/// Spectators connect to the same port as the joining player did. Each new spectator
+/// is sent the game settings, the current board and the current scores, followed by
+/// every message passed to [SpectatorHub::broadcast].
+///
+/// Each spectator is admitted and sent messages on its own thread, so a slow or silent
+/// spectator never holds up the game or other spectators.
+pub struct SpectatorHub {
+    state: Arc<Mutex<HubState>>,
+    /// set when the hub is dropped, to stop accepting spectators
+    shutdown: Arc<AtomicBool>
+}
+
+/// Everything a newly connected spectator needs to catch up, plus the connected spectators
+struct HubState {
+    /// the queues of messages for each spectator's writing thread
+    spectators: Vec<Sender<Message>>,
+    settings: NetworkSettings,
+    board: GameBoard,
+    active_player: ActivePlayer,
+    score: Message
+}
+
+impl SpectatorHub {
+
+    /// Starts accepting spectators on the given listener in a background thread
+    ///
+    /// The thread stops once the hub is dropped.
+    pub fn start(listener: TcpListener, settings: NetworkSettings) -> std::io::Result<Self>
+    {
+        listener.set_nonblocking(true)?;
+
+        let state = Arc::new(Mutex::new(HubState{
+            spectators: Vec::new(),
+            settings,
+            board: GameBoard::new(),
+            active_player: ActivePlayer::PlayerX,
+            score: Message::Score{player_x: 0, player_o: 0, draws: 0}
+        }));
+        let shutdown = Arc::new(AtomicBool::new(false));
+
+        let thread_state = Arc::clone(&state);
+        let thread_shutdown = Arc::clone(&shutdown);
+        thread::spawn(move ||{
+            while !thread_shutdown.load(Ordering::Relaxed) {
+                match listener.accept() {
+                    Ok((stream, _)) => {
+                        let state = Arc::clone(&thread_state);
+                        thread::spawn(move ||{Self::admit(&state, stream)});
+                    },
+                    Err(error) if error.kind() == ErrorKind::WouldBlock => thread::sleep(ACCEPT_POLL_INTERVAL),
+                    // a failed connection only affects that spectator
+                    Err(_) => {}
+                }
+            }
+        });
+
+        Ok(Self{state, shutdown})
+    }
+
+    /// Sends a message to every spectator
+    ///
+    /// `Board`, `Move` and `Score` messages also update the state sent to new spectators.
+    /// The message is only queued, so this never waits on the network. Spectators that can
+    /// no longer be reached are disconnected.
+    pub fn broadcast(&self, message: &Message)
+    {
+        let mut state = match self.state.lock() {
+            Ok(state) => state,
+            // a poisoned lock means an admitting thread panicked; spectating is best-effort
+            Err(_) => return
+        };
+
+        match message {
+            Message::Board(board, active_player) => {
+                state.board = board.clone();
+                state.active_player = active_player.clone();
+            },
+            Message::Move(location) => {
+                let piece = state.active_player.get_board_space();
+                *state.board.space_mut(*location) = piece;
+                state.active_player.switch();
+            },
+            Message::Score{..} => {
+                state.score = message.clone();
+            },
+            _ => {}
+        }
+
+        // a spectator's queue is closed once sending to it has failed
+        state.spectators.retain(|spectator|{spectator.send(message.clone()).is_ok()});
+    }
+
+    /// Performs the host side of the spectator handshake and, if successful,
+    /// queues the current state for the spectator and adds it to the hub
+    ///
+    /// Spectators that don't complete the handshake within [HANDSHAKE_TIMEOUT] are disconnected.
+    fn admit(state: &Mutex<HubState>, stream: TcpStream)
+    {
+        let connection = match Self::spectator_connection(stream) {
+            Ok(connection) => connection,
+            // ignore connections that fail before the handshake
+            Err(_) => return
+        };
+        match connection.exchange_hello(Role::Player) {
+            Ok(Role::Spectator) => {},
+            Ok(Role::Player) => {
+                connection.reject("the game already has two players");
+                return;
+            },
+            Err(_) => return
+        }
+
+        let mut state = match state.lock() {
+            Ok(state) => state,
+            Err(_) => return
+        };
+
+        let spectator = Self::spawn_writer(connection);
+        let catch_up = [
+            Message::Settings(state.settings.clone()),
+            Message::Board(state.board.clone(), state.active_player.clone()),
+            state.score.clone()
+        ];
+        if catch_up.into_iter().all(|message|{spectator.send(message).is_ok()}) {
+            state.spectators.push(spectator);
+        }
+    }
+
+    /// Wraps an accepted stream, with timeouts so that a spectator can't stall its threads
+    fn spectator_connection(stream: TcpStream) -> std::io::Result<Connection>
+    {
+        stream.set_nonblocking(false)?;
+        // spectators send nothing after the handshake, so reads only ever time out during it
+        stream.set_read_timeout(Some(HANDSHAKE_TIMEOUT))?;
+        stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+        Connection::new(stream)
+    }
+
+    /// Spawns a thread that sends the messages queued on the returned [Sender] to the spectator
+    ///
+    /// The thread disconnects the spectator and stops once a message fails to send or the
+    /// [Sender] is dropped.
+    fn spawn_writer(connection: Connection) -> Sender<Message>
+    {
+        let (sender, receiver) = mpsc::channel::<Message>();
+        thread::spawn(move ||{
+            for message in receiver {
+                if connection.send(&message).is_err() {
+                    return;
+                }
+            }
+        });
+        sender
+    }
+}
+
+impl Drop for SpectatorHub {
+    /// Tells every spectator that the game is over before disconnecting them.
+    /// [Read More](https://doc.rust-lang.org/1.62.1/core/ops/trait.Drop.html#tymethod.drop)
+    fn drop(&mut self) {
+        self.shutdown.store(true, Ordering::Relaxed);
+        self.broadcast(&Message::Quit);
+        // the writing threads send the messages already queued, then disconnect
+        if let Ok(mut state) = self.state.lock() {
+            state.spectators.clear();
+        }
+    }
+}
@@ -0,0 +1,306 @@
+//! Rule sets for classic games, which decide which sets of spaces win
+//!
+//! By default, the usual rows, columns and diagonals win. A rules file can choose which of those
+//! lines win, and add patterns of spaces that also win when one player holds all of them.
+//!
+//!# Rules files
+//!
+//! Rules files are plain text. Blank lines and lines starting with `#` are ignored.
+//!
+//! - `lines` followed by any of `rows`, `columns` and `diagonals` sets which lines win.
+//!   `lines` on its own means no lines win, so only patterns do.
+//!
+//! - `pattern NAME` is followed by three rows of three spaces, where `X` marks a space in
+//!   the pattern and `.` a space that isn't (whitespace between spaces is ignored).
+//!
+//! - `pattern NAME anywhere` is followed by a smaller grid of up to three rows; the pattern
+//!   wins wherever it fits on the board.
+//!
+//! ```text
+//! # only rows and columns, plus the four corners or any 2x2 square
+//! lines rows columns
+//!
+//! pattern corners
+//! X . X
+//! . . .
+//! X . X
+//!
+//! pattern square anywhere
+//! X X
+//! X X
+//! ```
+
+use std::{fmt::Display, fs, path::Path};
+
+use crate::{
+    gameboard::BoardSpaceLocation,
+    game_outcome::{WinPosition, WinPattern}
+};
+
+/// A pattern of spaces that wins a game when one player holds all of them
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pattern {
+    /// The name given to the pattern in the rules file
+    pub name: String,
+    pub spaces: Vec<BoardSpaceLocation>
+}
+
+/// The rules deciding which sets of spaces win a classic game
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rules {
+    /// true if completing a row wins
+    pub rows: bool,
+    /// true if completing a column wins
+    pub columns: bool,
+    /// true if completing a diagonal wins
+    pub diagonals: bool,
+    /// true if lines wrap around the edges of the board, adding the
+    /// [wrapped diagonals](WinPosition::all_wrapped)
+    pub toroidal: bool,
+    /// Patterns that win as well as the lines above, in the order they are checked
+    pub patterns: Vec<Pattern>
+}
+
+impl Default for Rules {
+    fn default() -> Self
+    {
+        Self{
+            rows: true,
+            columns: true,
+            diagonals: true,
+            toroidal: false,
+            patterns: Vec::new()
+        }
+    }
+}
+
+impl Rules {
+
+    /// Reads rules from the file at the given path
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, RulesError>
+    {
+        let contents = fs::read_to_string(path).map_err(|error|{RulesError::Io(error.to_string())})?;
+        Self::parse(&contents)
+    }
+
+    /// Parses rules in the rules file format described in the [module documentation](self)
+    pub fn parse(text: &str) -> Result<Self, RulesError>
+    {
+        let mut rules = Self::default();
+        let mut lines = text.lines().enumerate()
+            .map(|(index, line)|{(index + 1, line.trim())})
+            .filter(|(_, line)|{!line.is_empty() && !line.starts_with('#')})
+            .peekable();
+
+        while let Some((line_number, line)) = lines.next() {
+            let mut words = line.split_whitespace();
+            match words.next() {
+                Some("lines") => {
+                    (rules.rows, rules.columns, rules.diagonals) = (false, false, false);
+                    for word in words {
+                        match word {
+                            "rows" => rules.rows = true,
+                            "columns" => rules.columns = true,
+                            "diagonals" => rules.diagonals = true,
+                            _ => return Err(RulesError::Syntax(line_number, format!("unknown line type '{}'", word)))
+                        }
+                    }
+                },
+                Some("pattern") => {
+                    let name = words.next()
+                        .ok_or_else(||{RulesError::Syntax(line_number, "pattern needs a name".to_owned())})?
+                        .to_owned();
+                    let anywhere = match words.next() {
+                        None => false,
+                        Some("anywhere") => true,
+                        Some(word) => return Err(RulesError::Syntax(line_number, format!("unexpected '{}'", word)))
+                    };
+
+                    // the grid is every following line made up only of spaces in the pattern
+                    let mut grid: Vec<Vec<bool>> = Vec::new();
+                    while let Some((_, row)) = lines.next_if(|(_, row)|{Self::is_grid_row(row)}) {
+                        grid.push(row.chars().filter(|character|{!character.is_whitespace()})
+                            .map(|character|{character != '.'})
+                            .collect());
+                    }
+
+                    for spaces in Self::pattern_placements(&grid, anywhere)
+                        .map_err(|message|{RulesError::Syntax(line_number, format!("pattern '{}' {}", name, message))})? {
+                        rules.patterns.push(Pattern{name: name.clone(), spaces});
+                    }
+                },
+                Some(word) => return Err(RulesError::Syntax(line_number, format!("unknown rule '{}'", word))),
+                None => {}
+            }
+        }
+
+        Ok(rules)
+    }
+
+    /// Returns true if the given line is a row of a pattern grid
+    fn is_grid_row(line: &str) -> bool
+    {
+        line.chars().all(|character|{matches!(character, 'X' | 'x' | '.') || character.is_whitespace()})
+    }
+
+    /// Returns the spaces of every placement of the given grid on the board
+    ///
+    /// A grid that isn't placed `anywhere` must cover the whole board, so it has one placement.
+    /// Returns `Err` with a description of the problem if the grid is the wrong size or empty.
+    fn pattern_placements(grid: &[Vec<bool>], anywhere: bool) -> Result<Vec<Vec<BoardSpaceLocation>>, String>
+    {
+        let height = grid.len();
+        let width = grid.first().map_or(0, Vec::len);
+        if grid.iter().any(|row|{row.len() != width}) {
+            return Err("has rows of different lengths".to_owned());
+        }
+        if anywhere && (width > 3 || height > 3) {
+            return Err("must be at most 3 spaces wide and tall".to_owned());
+        }
+        if !anywhere && (width, height) != (3, 3) {
+            return Err("must be 3 spaces wide and tall (or placed anywhere)".to_owned());
+        }
+        if !grid.iter().flatten().any(|marked|{*marked}) {
+            return Err("has no spaces".to_owned());
+        }
+
+        let mut placements = Vec::new();
+        for offset_y in 0..=(3 - height) {
+            for offset_x in 0..=(3 - width) {
+                let spaces = grid.iter().enumerate().flat_map(|(y, row)|{
+                    row.iter().enumerate()
+                        .filter(|(_, marked)|{**marked})
+                        .map(move |(x, _)|{
+                            BoardSpaceLocation::from_coordinates(((x + offset_x) as u8, (y + offset_y) as u8))
+                        })
+                }).collect();
+                placements.push(spaces);
+            }
+        }
+        Ok(placements)
+    }
+
+    /// Returns every [WinPosition] that wins under these rules, in the order they are checked
+    pub fn win_positions(&self) -> impl Iterator<Item = WinPosition> + '_
+    {
+        let lines = WinPosition::all().filter(|win_position|{
+            match win_position {
+                WinPosition::TopRow | WinPosition::MiddleRow | WinPosition::BottomRow => self.rows,
+                WinPosition::LeftColumn | WinPosition::MiddleColumn | WinPosition::RightColumn => self.columns,
+                _ => self.diagonals
+            }
+        });
+        let wrapped_lines = WinPosition::all_wrapped().filter(|_|{self.diagonals && self.toroidal});
+        let patterns = self.patterns.iter().enumerate().map(|(index, pattern)|{
+            WinPosition::Pattern(WinPattern::new(index, &pattern.spaces))
+        });
+
+        lines.chain(wrapped_lines).chain(patterns)
+    }
+
+    /// Returns the name of the pattern the given [WinPosition] was made from, if it is a pattern
+    pub fn pattern_name(&self, win_position: &WinPosition) -> Option<&str>
+    {
+        match win_position {
+            WinPosition::Pattern(win_pattern) => {
+                self.patterns.get(win_pattern.index()).map(|pattern|{pattern.name.as_str()})
+            },
+            _ => None
+        }
+    }
+}
+
+/// Reasons why rules could not be loaded
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RulesError {
+    /// The rules file couldn't be read
+    Io(String),
+    /// The given line of the rules file is invalid
+    Syntax(usize, String)
+}
+
+impl Display for RulesError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "{}", error),
+            Self::Syntax(line, message) => write!(f, "line {}: {}", line, message)
+        }
+    }
+}
+
+impl std::error::Error for RulesError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use BoardSpaceLocation::*;
+
+    #[test]
+    fn empty_rules_are_the_defaults()
+    {
+        assert_eq!(Rules::parse(""), Ok(Rules::default()));
+        assert_eq!(Rules::parse("# just a comment\n\n   \n"), Ok(Rules::default()));
+    }
+
+    #[test]
+    fn lines_choose_which_lines_win()
+    {
+        let rules = Rules::parse("lines rows diagonals").expect("rules should be valid");
+        assert!(rules.rows && !rules.columns && rules.diagonals);
+
+        let rules = Rules::parse("  lines  ").expect("rules should be valid");
+        assert!(!rules.rows && !rules.columns && !rules.diagonals);
+        assert_eq!(rules.win_positions().count(), 0);
+    }
+
+    #[test]
+    fn whole_board_pattern_has_one_placement()
+    {
+        let rules = Rules::parse("pattern corners\nX . X\n...\nx . x\n").expect("rules should be valid");
+        assert_eq!(rules.patterns, vec![Pattern{
+            name: "corners".to_owned(),
+            spaces: vec![TopLeft, TopRight, BottomLeft, BottomRight]
+        }]);
+        assert_eq!(rules.win_positions().count(), 9);
+    }
+
+    #[test]
+    fn anywhere_pattern_is_placed_wherever_it_fits()
+    {
+        let rules = Rules::parse("pattern square anywhere\nX X\nX X\n\npattern dot anywhere\nX").expect("rules should be valid");
+        let placements = |name: &str|{rules.patterns.iter().filter(|pattern|{pattern.name == name}).count()};
+        assert_eq!(placements("square"), 4);
+        assert_eq!(placements("dot"), 9);
+        assert_eq!(rules.patterns[0].spaces, vec![TopLeft, TopMiddle, MiddleLeft, MiddleMiddle]);
+        assert_eq!(rules.patterns[3].spaces, vec![MiddleMiddle, MiddleRight, BottomMiddle, BottomRight]);
+    }
+
+    #[test]
+    fn pattern_names_are_found_from_win_positions()
+    {
+        let rules = Rules::parse("lines\npattern middle anywhere\n.X.").expect("rules should be valid");
+        let win_position = rules.win_positions().next().expect("the pattern should win");
+        assert_eq!(rules.pattern_name(&win_position), Some("middle"));
+        assert_eq!(rules.pattern_name(&WinPosition::TopRow), None);
+    }
+
+    #[test]
+    fn invalid_rules_give_the_line_number()
+    {
+        let syntax = |line: usize, message: &str|{Err(RulesError::Syntax(line, message.to_owned()))};
+
+        assert_eq!(Rules::parse("# comment\n\nlines rows knights"), syntax(3, "unknown line type 'knights'"));
+        assert_eq!(Rules::parse("lines\nwinning"), syntax(2, "unknown rule 'winning'"));
+        assert_eq!(Rules::parse("pattern\nXXX"), syntax(1, "pattern needs a name"));
+        assert_eq!(Rules::parse("pattern square somewhere\nXX"), syntax(1, "unexpected 'somewhere'"));
+        assert_eq!(Rules::parse("pattern short\nXXX\nXXX"),
+            syntax(1, "pattern 'short' must be 3 spaces wide and tall (or placed anywhere)"));
+        assert_eq!(Rules::parse("pattern long anywhere\nXXXX"),
+            syntax(1, "pattern 'long' must be at most 3 spaces wide and tall"));
+        assert_eq!(Rules::parse("pattern uneven anywhere\nXX\nX"),
+            syntax(1, "pattern 'uneven' has rows of different lengths"));
+        assert_eq!(Rules::parse("pattern nothing anywhere\n..\n.."), syntax(1, "pattern 'nothing' has no spaces"));
+        assert_eq!(Rules::parse("pattern missing\nlines"),
+            syntax(1, "pattern 'missing' must be 3 spaces wide and tall (or placed anywhere)"));
+    }
+}
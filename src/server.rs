@@ -0,0 +1,647 @@
+//! A lobby server that pairs up clients and runs their games
+//!
+//! Unlike the player-to-player games in the [network](crate::network) module, the server is
+//! authoritative: clients only send the moves they want to make, and the server checks them
+//! against the rules, keeps the board, and decides the outcome. The server also keeps a
+//! win/draw/loss record for every player.
+//!
+//! # Protocol
+//!
+//! The protocol is line based, so it can be used directly with tools like `nc`.
+//! Clients send one command per line; keywords are not case sensitive. Lines longer than
+//! 256 bytes are dropped with an `ERROR`.
+//! The server replies with one or more lines, each starting with an upper case keyword.
+//!
+//! | Command                 | Meaning                                                         | Replies                   |
+//! |-------------------------|-----------------------------------------------------------------|---------------------------|
+//! | `LOGIN <name>`          | Must be sent first; names are unique among connected players    | `WELCOME <name>`          |
+//! | `LIST`                  | List the players waiting for an opponent                        | `WAITING [<name> ...]`    |
+//! | `PLAY`                  | Play whoever has waited longest, or wait if nobody is waiting   | `START ...` or `QUEUED`   |
+//! | `PLAY <name>`           | Play the given waiting player                                   | `START ...`               |
+//! | `PLAY AI [difficulty]`  | Play a server-side AI; difficulty is 0 to 100 (default 100)     | `START ...`               |
+//! | `CANCEL`                | Stop waiting for an opponent                                    | `CANCELLED`               |
+//! | `MOVE <x> <y>`          | Claim the space at `(x,y)`, where `(0,0)` is the top left       | `BOARD ...` to both       |
+//! | `RESIGN`                | Forfeit the current game                                        | `RESULT ...` to both      |
+//! | `RECORD [name]`         | Look up a player's record (your own by default)                 | `RECORD ...`              |
+//! | `QUIT`                  | Leave the server, forfeiting any game in progress               | `BYE`                     |
+//!
+//! | Reply                                   | Meaning                                             |
+//! |-----------------------------------------|-----------------------------------------------------|
+//! | `START <opponent> <side>`               | A game has started; you play `<side>` (`X` or `O`)  |
+//! | `BOARD <spaces> <side>`                 | The board, and whose turn it is (see below)         |
+//! | `RESULT <win\|draw\|loss>`              | The game is over                                    |
+//! | `RECORD <name> <wins> <draws> <losses>` | A player's record                                   |
+//! | `ERROR <message>`                       | The last command could not be carried out           |
+//!
+//! `<spaces>` is 9 characters (`X`, `O`, or `-` for empty), one per space from
+//! top left to bottom right, row by row. X always moves first, and the player who has
+//! waited longest plays X. A `BOARD` is sent at the start of each game and after every move.
+
+use std::{
+    collections::HashMap,
+    io::{self, BufRead, BufReader, Read, Write},
+    net::{TcpListener, TcpStream},
+    sync::{Arc, Mutex, MutexGuard, PoisonError, mpsc::{self, Sender}},
+    thread,
+    time::Duration
+};
+
+use crate::{
+    gameboard::{GameBoard, BoardSpace, BoardSpaceLocation},
+    game_outcome::GameOutcome,
+    active_player::ActivePlayer,
+    ai::AiPlayer
+};
+
+pub mod commands;
+use commands::{Command, Reply, GameResult};
+
+pub mod records;
+use records::{Records, PendingSave};
+
+/// The port the server listens on if no port is specified
+pub const DEFAULT_PORT: u16 = 4041;
+
+/// The longest name a player may log in with
+const MAX_NAME_LENGTH: usize = 16;
+
+/// The longest line a client may send, including its line ending
+///
+/// Commands are much shorter than this, so longer lines are dropped rather than buffered.
+const MAX_LINE_LENGTH: usize = 256;
+
+/// How long a reply may take to send before the client is treated as disconnected
+const WRITE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Accepts clients on the given listener forever, handling each on its own thread
+pub fn run(listener: TcpListener, records: Records) -> io::Result<()>
+{
+    let lobby = Arc::new(Mutex::new(Lobby::new(records)));
+
+    for stream in listener.incoming() {
+        // a failed connection only affects that client
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(_) => continue
+        };
+        let lobby = Arc::clone(&lobby);
+        thread::spawn(move ||{
+            // errors mean the client disconnected, which handle_client cleans up after
+            let _ = handle_client(&lobby, stream);
+        });
+    }
+
+    Ok(())
+}
+
+/// Serves a single client until it quits or disconnects, then removes it from the lobby
+fn handle_client(lobby: &Mutex<Lobby>, stream: TcpStream) -> io::Result<()>
+{
+    stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+    let mut name: Option<String> = None;
+    let result = read_commands(lobby, &stream, &mut name);
+
+    if let Some(name) = name {
+        let deferred_work = {
+            let mut lobby = lock(lobby);
+            lobby.logout(&name);
+            lobby.take_deferred_work()
+        };
+        deferred_work.run(lobby);
+    }
+    result
+}
+
+/// Reads and carries out commands from a client until it quits or disconnects
+/// 
+/// `name` is set once the client logs in
+fn read_commands(lobby: &Mutex<Lobby>, stream: &TcpStream, name: &mut Option<String>) -> io::Result<()>
+{
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut buffer = Vec::new();
+
+    loop {
+        buffer.clear();
+        let read = (&mut reader).take(MAX_LINE_LENGTH as u64).read_until(b'\n', &mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        if read == MAX_LINE_LENGTH && buffer.last() != Some(&b'\n') {
+            // drop the rest of a line that is too long to be a command, rather than buffering it
+            reader.skip_until(b'\n')?;
+            send(stream, &Reply::Error("line too long".to_owned()));
+            continue;
+        }
+        let line = std::str::from_utf8(&buffer)
+            .map_err(|error|{io::Error::new(io::ErrorKind::InvalidData, error)})?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let command = match line.parse::<Command>() {
+            Ok(command) => command,
+            Err(message) => {
+                send(stream, &Reply::Error(message));
+                continue;
+            }
+        };
+
+        // replies to clients that aren't logged in are sent once the lobby is unlocked;
+        // logged in clients' replies are queued for their writer thread
+        let mut lobby_guard = lock(lobby);
+        let (reply, quit) = match (&*name, command) {
+            (None, Command::Quit) => (Some(Reply::Bye), true),
+            (Some(name), Command::Quit) => {
+                lobby_guard.send(name, &Reply::Bye);
+                (None, true)
+            },
+            (None, Command::Login(requested_name)) => {
+                match lobby_guard.login(&requested_name, stream.try_clone()?) {
+                    Ok(()) => {
+                        lobby_guard.send(&requested_name, &Reply::Welcome(requested_name.clone()));
+                        *name = Some(requested_name);
+                        (None, false)
+                    },
+                    Err(message) => (Some(Reply::Error(message)), false)
+                }
+            },
+            (None, _) => (Some(Reply::Error("please LOGIN first".to_owned())), false),
+            (Some(name), Command::Login(_)) => {
+                lobby_guard.send(name, &Reply::Error("already logged in".to_owned()));
+                (None, false)
+            },
+            (Some(name), command) => {
+                lobby_guard.handle_command(name, command);
+                (None, false)
+            }
+        };
+        let deferred_work = lobby_guard.take_deferred_work();
+        drop(lobby_guard);
+
+        if let Some(reply) = reply {
+            send(stream, &reply);
+        }
+        deferred_work.run(lobby);
+        if quit {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Locks the lobby, recovering it if another client's thread panicked while holding the lock
+fn lock(lobby: &Mutex<Lobby>) -> MutexGuard<'_, Lobby>
+{
+    lobby.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Sends a reply on the given stream
+///
+/// Failures are ignored; the client's own thread notices the disconnection
+fn send(mut stream: &TcpStream, reply: &Reply)
+{
+    let _ = writeln!(stream, "{}", reply).and_then(|_|{stream.flush()});
+}
+
+/// Starts a thread that sends the replies queued on the returned [Sender] to the given stream
+///
+/// The thread stops once the sender is dropped and every queued reply has been sent,
+/// or if the client stops reading replies for longer than [WRITE_TIMEOUT].
+fn spawn_writer(stream: TcpStream) -> Sender<String>
+{
+    let (sender, receiver) = mpsc::channel::<String>();
+    thread::spawn(move ||{
+        let mut stream = &stream;
+        for line in receiver {
+            if writeln!(stream, "{}", line).and_then(|_|{stream.flush()}).is_err() {
+                break;
+            }
+        }
+    });
+    sender
+}
+
+/// Work left over from carrying out a command, which is done once the lobby is unlocked so
+/// that it doesn't hold up other clients
+struct DeferredWork {
+    ai_moves: Vec<AiMove>,
+    save: Option<PendingSave>
+}
+
+impl DeferredWork {
+
+    /// Searches for each AI move and plays it, then saves the records if they changed
+    ///
+    /// Playing an AI move can lead to more work, which is done as well.
+    fn run(mut self, lobby: &Mutex<Lobby>)
+    {
+        loop {
+            if let Some(save) = self.save.take() {
+                if let Err(error) = save.write() {
+                    eprintln!("Failed to save records: {}", error);
+                }
+            }
+
+            let ai_move = match self.ai_moves.pop() {
+                Some(ai_move) => ai_move,
+                None => return
+            };
+            let location = ai_move.ai_player.choose_move(&ai_move.board, &ai_move.active_player);
+
+            let mut lobby = lock(lobby);
+            if let Ok(location) = location {
+                lobby.play_ai_move(&ai_move, location);
+            }
+            let more_work = lobby.take_deferred_work();
+            drop(lobby);
+            self.ai_moves.extend(more_work.ai_moves);
+            self.save = more_work.save;
+        }
+    }
+}
+
+/// A move an AI player is to make, searched for without the lobby locked
+struct AiMove {
+    game_id: u64,
+    /// the board when the search started; the move is only played if it hasn't changed
+    board: GameBoard,
+    active_player: ActivePlayer,
+    ai_player: AiPlayer
+}
+
+/// A connected, logged in client
+struct Client {
+    /// the replies waiting to be sent by the client's writer thread
+    replies: Sender<String>,
+    game: Option<u64>
+}
+
+/// One side of a game on the server
+enum Seat {
+    Client(String),
+    Ai(AiPlayer)
+}
+
+/// A game in progress on the server
+struct ServerGame {
+    board: GameBoard,
+    active_player: ActivePlayer,
+    player_x: Seat,
+    player_o: Seat
+}
+
+impl ServerGame {
+    /// Returns the seat of the given player
+    fn seat(&self, player: &ActivePlayer) -> &Seat
+    {
+        match player {
+            ActivePlayer::PlayerX => &self.player_x,
+            ActivePlayer::PlayerO => &self.player_o
+        }
+    }
+
+    /// Returns the side played by the client with the given name, if they are in this game
+    fn side_of(&self, name: &str) -> Option<ActivePlayer>
+    {
+        [ActivePlayer::PlayerX, ActivePlayer::PlayerO].into_iter().find(|player|{
+            matches!(self.seat(player), Seat::Client(seat_name) if seat_name == name)
+        })
+    }
+
+    /// Returns the names of the clients playing in this game
+    fn client_names(&self) -> impl Iterator<Item = &String>
+    {
+        [&self.player_x, &self.player_o].into_iter().filter_map(|seat|{
+            match seat {
+                Seat::Client(name) => Some(name),
+                Seat::Ai(_) => None
+            }
+        })
+    }
+}
+
+/// Shared state of the server: connected clients, waiting players, games and records
+struct Lobby {
+    clients: HashMap<String, Client>,
+    waiting: Vec<String>,
+    games: HashMap<u64, ServerGame>,
+    next_game_id: u64,
+    records: Records,
+    /// AI moves to be searched for once the lobby is unlocked
+    ai_moves: Vec<AiMove>
+}
+
+impl Lobby {
+
+    fn new(records: Records) -> Self
+    {
+        Self{
+            clients: HashMap::new(),
+            waiting: Vec::new(),
+            games: HashMap::new(),
+            next_game_id: 0,
+            records,
+            ai_moves: Vec::new()
+        }
+    }
+
+    /// Queues a reply to the named client, if connected
+    ///
+    /// Failures are ignored; the client's own thread notices the disconnection
+    fn send(&self, name: &str, reply: &Reply)
+    {
+        if let Some(client) = self.clients.get(name) {
+            let _ = client.replies.send(reply.to_string());
+        }
+    }
+
+    /// Takes the work to be done once the lobby is unlocked
+    fn take_deferred_work(&mut self) -> DeferredWork
+    {
+        DeferredWork{
+            ai_moves: std::mem::take(&mut self.ai_moves),
+            save: self.records.take_pending_save()
+        }
+    }
+
+    /// Registers a client under the given name
+    ///
+    /// Returns `Err` with a message for the client if the name is invalid or taken
+    fn login(&mut self, name: &str, stream: TcpStream) -> Result<(), String>
+    {
+        let valid = !name.is_empty() && name.len() <= MAX_NAME_LENGTH &&
+            name.chars().all(|c|{c.is_ascii_alphanumeric() || c == '_' || c == '-'});
+        if !valid {
+            return Err(format!(
+                "names must be 1 to {} letters, digits, '_' or '-'", MAX_NAME_LENGTH));
+        }
+        if name.eq_ignore_ascii_case("AI") {
+            return Err("the name AI is reserved".to_owned());
+        }
+        if self.clients.contains_key(name) {
+            return Err(format!("{} is already logged in", name));
+        }
+
+        self.clients.insert(name.to_owned(), Client{replies: spawn_writer(stream), game: None});
+        Ok(())
+    }
+
+    /// Removes a client, forfeiting any game they are playing
+    fn logout(&mut self, name: &str)
+    {
+        self.waiting.retain(|waiting_name|{waiting_name != name});
+        self.resign(name);
+        self.clients.remove(name);
+    }
+
+    /// Carries out a command from a logged in client
+    fn handle_command(&mut self, name: &str, command: Command)
+    {
+        match command {
+            Command::List => {
+                self.send(name, &Reply::Waiting(self.waiting.clone()));
+            },
+            Command::Play(opponent) => self.play(name, opponent),
+            Command::PlayAi(difficulty) => {
+                if self.check_available(name) {
+                    self.waiting.retain(|waiting_name|{waiting_name != name});
+                    let ai_player = AiPlayer::new(difficulty as f64 / 100.0);
+                    self.start_game(Seat::Client(name.to_owned()), Seat::Ai(ai_player));
+                }
+            },
+            Command::Cancel => {
+                if self.waiting.iter().any(|waiting_name|{waiting_name == name}) {
+                    self.waiting.retain(|waiting_name|{waiting_name != name});
+                    self.send(name, &Reply::Cancelled);
+                } else {
+                    self.send(name, &Reply::Error("you are not waiting".to_owned()));
+                }
+            },
+            Command::Move(location) => self.play_move(name, location),
+            Command::Resign => {
+                if !self.resign(name) {
+                    self.send(name, &Reply::Error("you are not playing".to_owned()));
+                }
+            },
+            Command::Record(player) => {
+                let player = player.unwrap_or_else(||{name.to_owned()});
+                let record = self.records.get(&player);
+                self.send(name, &Reply::Record(player, record));
+            },
+            // handled by handle_client, which owns the connection
+            Command::Login(_) | Command::Quit => {}
+        }
+    }
+
+    /// Returns true if the named client is not in a game; otherwise tells them they are
+    fn check_available(&self, name: &str) -> bool
+    {
+        let in_game = self.clients.get(name).is_some_and(|client|{client.game.is_some()});
+        if in_game {
+            self.send(name, &Reply::Error("you are already playing".to_owned()));
+        }
+        !in_game
+    }
+
+    /// Pairs the named client with a waiting player, or adds them to the waiting players
+    fn play(&mut self, name: &str, opponent: Option<String>)
+    {
+        if !self.check_available(name) {
+            return;
+        }
+
+        if opponent.as_deref() == Some(name) {
+            self.send(name, &Reply::Error("you can't play against yourself".to_owned()));
+            return;
+        }
+
+        let opponent_index = match &opponent {
+            Some(opponent) => self.waiting.iter().position(|waiting_name|{waiting_name == opponent && waiting_name != name}),
+            None => self.waiting.iter().position(|waiting_name|{waiting_name != name})
+        };
+
+        match (opponent_index, opponent) {
+            (Some(index), _) => {
+                let opponent = self.waiting.remove(index);
+                self.waiting.retain(|waiting_name|{waiting_name != name});
+                // the player who waited plays X
+                self.start_game(Seat::Client(opponent), Seat::Client(name.to_owned()));
+            },
+            (None, Some(opponent)) => {
+                self.send(name, &Reply::Error(format!("{} is not waiting", opponent)));
+            },
+            (None, None) => {
+                if !self.waiting.iter().any(|waiting_name|{waiting_name == name}) {
+                    self.waiting.push(name.to_owned());
+                }
+                self.send(name, &Reply::Queued);
+            }
+        }
+    }
+
+    /// Starts a new game between the given seats
+    fn start_game(&mut self, player_x: Seat, player_o: Seat)
+    {
+        let id = self.next_game_id;
+        self.next_game_id += 1;
+
+        let game = ServerGame{
+            board: GameBoard::new(),
+            active_player: ActivePlayer::PlayerX,
+            player_x,
+            player_o
+        };
+
+        for (side, opponent_side) in [
+            (ActivePlayer::PlayerX, ActivePlayer::PlayerO),
+            (ActivePlayer::PlayerO, ActivePlayer::PlayerX)
+        ] {
+            if let Seat::Client(name) = game.seat(&side) {
+                let opponent = match game.seat(&opponent_side) {
+                    Seat::Client(opponent) => opponent.clone(),
+                    Seat::Ai(_) => "AI".to_owned()
+                };
+                if let Some(client) = self.clients.get_mut(name) {
+                    client.game = Some(id);
+                }
+                self.send(name, &Reply::Start{opponent, side});
+            }
+        }
+
+        self.games.insert(id, game);
+        self.broadcast_board(id);
+        self.advance_game(id);
+    }
+
+    /// Claims a space for the named client, if it is their turn and the space is empty
+    fn play_move(&mut self, name: &str, location: BoardSpaceLocation)
+    {
+        let id = match self.clients.get(name).and_then(|client|{client.game}) {
+            Some(id) => id,
+            None => {
+                self.send(name, &Reply::Error("you are not playing".to_owned()));
+                return;
+            }
+        };
+        let game = match self.games.get_mut(&id) {
+            Some(game) => game,
+            None => return
+        };
+
+        if game.side_of(name).as_ref() != Some(&game.active_player) {
+            self.send(name, &Reply::Error("it is not your turn".to_owned()));
+            return;
+        }
+        if game.board.space(location) != &BoardSpace::Empty {
+            self.send(name, &Reply::Error("that space is taken".to_owned()));
+            return;
+        }
+
+        *game.board.space_mut(location) = game.active_player.get_board_space();
+        game.active_player.switch();
+
+        self.broadcast_board(id);
+        self.advance_game(id);
+    }
+
+    /// Finishes the game if it is over, or queues an AI move if an AI player is to move
+    fn advance_game(&mut self, id: u64)
+    {
+        let game = match self.games.get(&id) {
+            Some(game) => game,
+            None => return
+        };
+
+        let winner = match game.board.game_outcome() {
+            GameOutcome::Incomplete => None,
+            GameOutcome::PlayerX(_) => Some(Some(ActivePlayer::PlayerX)),
+            GameOutcome::PlayerO(_) => Some(Some(ActivePlayer::PlayerO)),
+            GameOutcome::Draw => Some(None)
+        };
+        if let Some(winner) = winner {
+            self.finish_game(id, winner);
+            return;
+        }
+
+        if let Seat::Ai(ai_player) = game.seat(&game.active_player) {
+            self.ai_moves.push(AiMove{
+                game_id: id,
+                board: game.board.clone(),
+                active_player: game.active_player.clone(),
+                ai_player: ai_player.clone()
+            });
+        }
+    }
+
+    /// Plays a move found by an AI search, if its game is still in the position searched
+    fn play_ai_move(&mut self, ai_move: &AiMove, location: BoardSpaceLocation)
+    {
+        let game = match self.games.get_mut(&ai_move.game_id) {
+            Some(game) => game,
+            None => return
+        };
+        if game.board != ai_move.board || game.active_player != ai_move.active_player {
+            return;
+        }
+
+        *game.board.space_mut(location) = game.active_player.get_board_space();
+        game.active_player.switch();
+
+        self.broadcast_board(ai_move.game_id);
+        self.advance_game(ai_move.game_id);
+    }
+
+    /// Sends the board of the given game to its clients
+    fn broadcast_board(&self, id: u64)
+    {
+        if let Some(game) = self.games.get(&id) {
+            let reply = Reply::Board(game.board.clone(), game.active_player.clone());
+            for name in game.client_names() {
+                self.send(name, &reply);
+            }
+        }
+    }
+
+    /// Forfeits the named client's game, if they are playing
+    ///
+    /// Returns true if a game was forfeited
+    fn resign(&mut self, name: &str) -> bool
+    {
+        let id = match self.clients.get(name).and_then(|client|{client.game}) {
+            Some(id) => id,
+            None => return false
+        };
+        let winner = self.games.get(&id)
+            .and_then(|game|{game.side_of(name)})
+            .map(|side|{side.opposite()});
+        self.finish_game(id, winner);
+        true
+    }
+
+    /// Ends the given game, sending results to its clients and updating their records
+    ///
+    /// `winner` is `None` for a draw
+    fn finish_game(&mut self, id: u64, winner: Option<ActivePlayer>)
+    {
+        let game = match self.games.remove(&id) {
+            Some(game) => game,
+            None => return
+        };
+
+        // each client's record is only updated once, even if they somehow played both sides
+        let mut names: Vec<&String> = game.client_names().collect();
+        names.dedup();
+        for name in names {
+            let result = match (&winner, game.side_of(name)) {
+                (None, _) => GameResult::Draw,
+                (Some(winner), Some(side)) if winner == &side => GameResult::Win,
+                _ => GameResult::Loss
+            };
+            self.send(name, &Reply::Result(result));
+            if let Some(client) = self.clients.get_mut(name) {
+                client.game = None;
+            }
+            self.records.add_result(name, result);
+        }
+    }
+}
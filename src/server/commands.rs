@@ -0,0 +1,127 @@
+//! Commands sent to the lobby server by clients, and the server's replies
+
+use std::{fmt::Display, str::FromStr};
+
+use crate::{
+    gameboard::{GameBoard, BoardSpaceLocation},
+    active_player::ActivePlayer,
+    network::protocol::Message
+};
+
+use super::records::Record;
+
+/// A command sent by a client
+#[derive(Debug, PartialEq, Clone)]
+pub enum Command {
+    Login(String),
+    List,
+    /// Play against a specific waiting player, or against whoever is waiting if `None`
+    Play(Option<String>),
+    /// Play against a server-side AI at the given difficulty (0 to 100)
+    PlayAi(u8),
+    Cancel,
+    Move(BoardSpaceLocation),
+    Resign,
+    /// Look up the record of the given player, or of the sender if `None`
+    Record(Option<String>),
+    Quit
+}
+
+/// How a game ended, from the point of view of the player receiving the result
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum GameResult {
+    Win,
+    Draw,
+    Loss
+}
+
+/// A message sent by the server
+#[derive(Debug, PartialEq, Clone)]
+pub enum Reply {
+    Welcome(String),
+    Error(String),
+    Waiting(Vec<String>),
+    Queued,
+    Cancelled,
+    Start{opponent: String, side: ActivePlayer},
+    Board(GameBoard, ActivePlayer),
+    Result(GameResult),
+    Record(String, Record),
+    Bye
+}
+
+impl FromStr for Command {
+    type Err = String;
+
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        let mut fields = line.split_whitespace();
+        let keyword = match fields.next() {
+            Some(keyword) => keyword.to_ascii_uppercase(),
+            None => return Err("empty command".to_owned())
+        };
+
+        let command = match keyword.as_str() {
+            "LOGIN" => match fields.next() {
+                Some(name) => Self::Login(name.to_owned()),
+                None => return Err("LOGIN requires a name".to_owned())
+            },
+            "LIST" => Self::List,
+            "PLAY" => match fields.next() {
+                Some(name) if name.eq_ignore_ascii_case("AI") => {
+                    let difficulty = match fields.next() {
+                        None => 100,
+                        Some(difficulty) => match difficulty.parse() {
+                            Ok(difficulty) if difficulty <= 100 => difficulty,
+                            _ => return Err(format!("invalid difficulty '{}'", difficulty))
+                        }
+                    };
+                    Self::PlayAi(difficulty)
+                },
+                Some(name) => Self::Play(Some(name.to_owned())),
+                None => Self::Play(None)
+            },
+            "CANCEL" => Self::Cancel,
+            // reuse the player-to-player MOVE parsing, which checks coordinate bounds
+            "MOVE" => match line.trim().to_ascii_uppercase().parse::<Message>() {
+                Ok(Message::Move(location)) => Self::Move(location),
+                _ => return Err("MOVE requires coordinates x and y from 0 to 2".to_owned())
+            },
+            "RESIGN" => Self::Resign,
+            "RECORD" => Self::Record(fields.next().map(str::to_owned)),
+            "QUIT" => Self::Quit,
+            other => return Err(format!("unknown command '{}'", other))
+        };
+
+        Ok(command)
+    }
+}
+
+impl Display for Reply {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Welcome(name) => write!(f, "WELCOME {}", name),
+            Self::Error(message) => write!(f, "ERROR {}", message),
+            Self::Waiting(names) => {
+                write!(f, "WAITING")?;
+                for name in names {
+                    write!(f, " {}", name)?;
+                }
+                Ok(())
+            },
+            Self::Queued => write!(f, "QUEUED"),
+            Self::Cancelled => write!(f, "CANCELLED"),
+            Self::Start{opponent, side} => write!(f, "START {} {}", opponent, side.get_char()),
+            // the board is formatted exactly as in the player-to-player protocol
+            Self::Board(board, active_player) => 
+                write!(f, "{}", Message::Board(board.clone(), active_player.clone())),
+            Self::Result(result) => write!(f, "RESULT {}", match result {
+                GameResult::Win => "win",
+                GameResult::Draw => "draw",
+                GameResult::Loss => "loss"
+            }),
+            Self::Record(name, record) => 
+                write!(f, "RECORD {} {} {} {}", name, record.wins, record.draws, record.losses),
+            Self::Bye => write!(f, "BYE")
+        }
+    }
+}
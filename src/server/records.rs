@@ -0,0 +1,145 @@
+//! Per-player win/draw/loss records kept by the lobby server
+
+use std::{
+    collections::HashMap,
+    fs,
+    io,
+    path::PathBuf,
+    sync::{Arc, Mutex, PoisonError}
+};
+
+use super::commands::GameResult;
+
+/// The number of games a player has won, drawn and lost
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub struct Record {
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32
+}
+
+/// Records for every player that has finished a game, optionally saved to a file
+///
+/// The file holds one line per player: `<name> <wins> <draws> <losses>`
+///
+/// Records aren't saved as soon as they change; instead, [Records::take_pending_save] returns
+/// the file's new contents, so that the lobby server can write them without holding its lock.
+pub struct Records {
+    records: HashMap<String, Record>,
+    path: Option<PathBuf>,
+    /// the number of changes made to the records
+    version: u64,
+    /// the version of the records last taken to be saved
+    taken_version: u64,
+    /// the version of the records last written to the file, shared by every [PendingSave]
+    written_version: Arc<Mutex<u64>>
+}
+
+/// The contents of the records file after a change, waiting to be written
+pub struct PendingSave {
+    path: PathBuf,
+    contents: String,
+    version: u64,
+    written_version: Arc<Mutex<u64>>
+}
+
+impl PendingSave {
+
+    /// Writes the records to their file, unless newer records have been written already
+    pub fn write(self) -> io::Result<()>
+    {
+        // holding the lock while writing stops an older save overwriting a newer one
+        let mut written_version = self.written_version.lock().unwrap_or_else(PoisonError::into_inner);
+        if self.version > *written_version {
+            fs::write(&self.path, self.contents)?;
+            *written_version = self.version;
+        }
+        Ok(())
+    }
+}
+
+impl Records {
+
+    /// Creates an empty set of records, which is never saved
+    pub fn new() -> Self
+    {
+        Self::with_records(HashMap::new(), None)
+    }
+
+    /// Loads records from the given file, which will also be used to save them
+    ///
+    /// A missing file is treated as empty; lines that can't be parsed are skipped.
+    pub fn load(path: PathBuf) -> io::Result<Self>
+    {
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(error) => return Err(error)
+        };
+
+        let records = contents.lines().filter_map(|line|{
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            match fields[..] {
+                [name, wins, draws, losses] => Some((name.to_owned(), Record{
+                    wins: wins.parse().ok()?,
+                    draws: draws.parse().ok()?,
+                    losses: losses.parse().ok()?
+                })),
+                _ => None
+            }
+        }).collect();
+
+        Ok(Self::with_records(records, Some(path)))
+    }
+
+    /// Creates records with the given contents, which are saved to `path` if there is one
+    fn with_records(records: HashMap<String, Record>, path: Option<PathBuf>) -> Self
+    {
+        Self{records, path, version: 0, taken_version: 0, written_version: Arc::new(Mutex::new(0))}
+    }
+
+    /// Returns the record of the given player (all zeroes if they haven't finished a game)
+    pub fn get(&self, name: &str) -> Record
+    {
+        self.records.get(name).copied().unwrap_or_default()
+    }
+
+    /// Adds a game result to the given player's record
+    ///
+    /// The change is saved by the next [PendingSave] taken from the records.
+    pub fn add_result(&mut self, name: &str, result: GameResult)
+    {
+        let record = self.records.entry(name.to_owned()).or_default();
+        match result {
+            GameResult::Win => record.wins += 1,
+            GameResult::Draw => record.draws += 1,
+            GameResult::Loss => record.losses += 1
+        }
+        self.version += 1;
+    }
+
+    /// Returns the contents to save to the records file, if the records were loaded from a
+    /// file and have changed since they were last taken to be saved
+    pub fn take_pending_save(&mut self) -> Option<PendingSave>
+    {
+        let path = self.path.clone()?;
+        if self.version == self.taken_version {
+            return None;
+        }
+        self.taken_version = self.version;
+
+        let mut names: Vec<&String> = self.records.keys().collect();
+        names.sort();
+        let contents: String = names.into_iter().map(|name|{
+            let record = self.records[name];
+            format!("{} {} {} {}\n", name, record.wins, record.draws, record.losses)
+        }).collect();
+        Some(PendingSave{path, contents, version: self.version, written_version: Arc::clone(&self.written_version)})
+    }
+}
+
+impl Default for Records {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,19 +1,43 @@
 //! User interface utilities, including the main game loop
 
-use std::io::{stdout, Write};
+use std::time::Duration;
 
 use crate::{
-    gameboard::GameBoard,
+    gameboard::{GameBoard, BoardSpace, BoardSpaceLocation},
     player_type::PlayerType,
     active_player::ActivePlayer,
     ai::AiPlayer,
-    game_settings::{GameAutoquitMode, GameMode}
-};
-use crossterm::{
-    terminal::{self, EnterAlternateScreen, LeaveAlternateScreen},
-    QueueableCommand
+    game_settings::{GameAutoquitMode, GameMode, VariantOptions},
+    network::{Connection, spectators::SpectatorHub},
+    variants::{self, Variant},
+    rules::Rules,
+    multiplayer::{Seat, MultiplayerGame}
 };
 
+//declare terminal module which contains
+//the terminals the UI can be drawn on
+pub mod terminal;
+use self::terminal::{Terminal, LocalTerminal};
+
+//declare key_bindings module which contains
+//the actions performed by each key, and their presets
+pub mod key_bindings;
+use self::key_bindings::KeyBindings;
+
+//declare theme module which contains
+//the colours and text styles boards are drawn in
+pub mod theme;
+use self::theme::Theme;
+
+//declare config module which contains
+//the settings kept in the configuration directory between runs
+pub mod config;
+
+//declare board_geometry module which contains
+//the size and position the classic board is drawn at
+mod board_geometry;
+use self::board_geometry::BoardGeometry;
+
 //declare event_handling module which contains
 //event handling impl's for the UI struct
 mod event_handling;
@@ -22,6 +46,19 @@ mod event_handling;
 //game related impl's for the UI struct
 mod game;
 
+//declare starting_position module which contains
+//impl's for setting up blocked spaces and handicap pieces for the UI struct
+mod starting_position;
+
+//declare variant_game module which contains
+//impl's for playing game variants for the UI struct
+mod variant_game;
+use self::variant_game::VariantSnapshot;
+
+//declare multiplayer_game module which contains
+//impl's for playing games with more than two players for the UI struct
+mod multiplayer_game;
+
 //declare play_again_menu module which contains
 //post-game menu impl's for the UI struct
 mod play_again_menu;
@@ -31,6 +68,21 @@ mod play_again_menu;
 mod setup_menu;
 use self::setup_menu::SetupMenu;
 
+//declare network_game module which contains
+//networked game impl's for the UI struct
+mod network_game;
+
+//declare spectate module which contains
+//spectator impl's for the UI struct
+mod spectate;
+
+//declare serve module which contains
+//impl's for serving games over TCP for the UI struct
+mod serve;
+
+/// The port used by [UI::serve] if no port is specified
+pub const DEFAULT_SERVE_PORT: u16 = 4000;
+
 /// Struct used to manage the game UI
 /// 
 /// Manages setup and cleanup tasks, as well as storing game state
@@ -38,9 +90,9 @@ use self::setup_menu::SetupMenu;
 /// 
 ///# Notes
 /// 
-/// While an instance of this struct is in scope, the terminal will be in 'raw mode' (and
-/// in an alternate screen). This means that many things that operate on [std::io::stdout]
-/// will not work as expected (such as [println!]). 
+/// While an instance of this struct is in scope, its [Terminal] will be in 'raw mode' (and
+/// in an alternate screen). For the local terminal, this means that many things that operate
+/// on [std::io::stdout] will not work as expected (such as [println!]). 
 /// 
 /// To return the terminal to normal, the `UI` instance must be destroyed. 
 /// This can be done by calling [drop] on it it (e.g. `drop(ui_instance)`), 
@@ -48,6 +100,10 @@ use self::setup_menu::SetupMenu;
 pub struct UI{
     player_x: PlayerType,
     player_o: PlayerType,
+    /// the third player, in games with more than two players
+    player_triangle: PlayerType,
+    /// the fourth player, in games with four players
+    player_square: PlayerType,
     active_player: ActivePlayer,
     cursor_x_pos: u8,
     cursor_y_pos: u8,
@@ -55,31 +111,91 @@ pub struct UI{
     terminal_x_size: u16,
     terminal_y_size: u16,
     exit_flag: bool,
-    player_x_score: u32,
-    player_o_score: u32,
+    /// the score of the player in each seat in half points, in [Seat] order: two for each game
+    /// won, and one for each half point won without winning (see [Variant::half_point])
+    half_points: [u32; Seat::COUNT],
+    /// the number of games that were won by a player, rather than drawn
+    games_won: u32,
     number_of_draws: u32,
     game_autoquit_mode: GameAutoquitMode,
     game_autoquit_value: u32,
-    game_mode: GameMode
+    game_mode: GameMode,
+    variant_options: VariantOptions,
+    /// the blocked spaces and handicap pieces chosen by the user, which every game starts with
+    starting_position: Option<Vec<(BoardSpaceLocation, BoardSpace)>>,
+    /// the rules classic and reverse games are played with
+    rules: Rules,
+    /// the action performed by each key
+    key_bindings: KeyBindings,
+    /// the colours and text styles boards are drawn in
+    theme: Theme,
+    /// the size and position the classic board was last drawn at
+    board_geometry: BoardGeometry,
+    /// the spaces claimed in the current classic game, in the order they were claimed
+    move_history: Vec<BoardSpaceLocation>,
+    remote: Option<Connection>,
+    spectators: Option<SpectatorHub>,
+    resigned_player: Option<ActivePlayer>,
+    terminal: Box<dyn Terminal>,
+    /// the game being played, if the game mode is played on a [Variant]
+    variant: Option<Box<dyn Variant>>,
+    /// the index of the selected cell in a variant game
+    variant_cursor: usize,
+    /// the cell of the piece chosen to be moved in a variant game, if any
+    variant_selection: Option<usize>,
+    /// the symbol a human player will place in variants where players choose
+    variant_symbol: BoardSpace,
+    /// the number a human player will place in variants where players place numbers
+    variant_number: u8,
+    /// the number of moves played in the current variant game
+    variant_moves: usize,
+    /// true if the active player may swap sides instead of replying to the first move
+    swap_available: bool,
+    /// true if the players swapped sides in the current variant game, so that player X
+    /// (as chosen in the setup menu) plays O, and player O plays X
+    sides_swapped: bool,
+    /// the state of the current variant game before each move, which undoing the move returns to
+    variant_history: Vec<VariantSnapshot>,
+    /// the cell of the last move in a variant game or a game with more than two players, if any
+    variant_last_move: Option<usize>,
+    /// the game being played, if the game mode has more than two players
+    multiplayer: Option<MultiplayerGame>,
+    /// the seat of the player to move first in the next game with more than two players
+    first_seat: Seat
 }
 
 impl UI{
 
-    const TERMSIZE_MIN_X: u16 = 11;
-    const TERMSIZE_MIN_Y: u16 = 8;
+    /// How long to wait for input before checking for messages from a remote player
+    const NETWORK_POLL_INTERVAL: Duration = Duration::from_millis(50);
 
-    /// Sets up the terminal for running the game
+    /// Sets up the local terminal for running the game
     /// 
     /// Cleanup of the terminal is performed by the [Drop] implementation of this struct
     /// 
     /// The [Default] implementation for `UI` is equivalent to this function's return value.
+    /// 
+    /// Boards are drawn without colour if the `NO_COLOR` environment variable is set
+    /// (see [Theme::from_environment]).
     pub fn new() -> crossterm::Result<Self>
     {
-        Self::setup_terminal()?;
-        let (terminal_x_size, terminal_y_size) = terminal::size()?;
+        let mut new_instance = Self::with_terminal(Box::new(LocalTerminal::new()))?;
+        new_instance.theme = Theme::from_environment();
+        Ok(new_instance)
+    }
+
+    /// Sets up the given [Terminal] for running the game
+    /// 
+    /// Cleanup of the terminal is performed by the [Drop] implementation of this struct
+    pub fn with_terminal(mut terminal: Box<dyn Terminal>) -> crossterm::Result<Self>
+    {
+        terminal.enter()?;
+        let (terminal_x_size, terminal_y_size) = terminal.size()?;
         let new_instance = Self{
             player_x: PlayerType::Human,
             player_o: PlayerType::AI(AiPlayer::default()),
+            player_triangle: PlayerType::AI(AiPlayer::default()),
+            player_square: PlayerType::AI(AiPlayer::default()),
             active_player: ActivePlayer::PlayerX,
             cursor_x_pos: 0,
             cursor_y_pos: 0,
@@ -87,12 +203,35 @@ impl UI{
             terminal_x_size,
             terminal_y_size,
             exit_flag: false,
-            player_x_score: 0,
-            player_o_score: 0,
+            half_points: [0; Seat::COUNT],
+            games_won: 0,
             number_of_draws: 0,
             game_autoquit_mode: GameAutoquitMode::default(),
             game_autoquit_value: 1,
-            game_mode: GameMode::default()
+            game_mode: GameMode::default(),
+            variant_options: VariantOptions::default(),
+            starting_position: None,
+            rules: Rules::default(),
+            key_bindings: KeyBindings::default(),
+            theme: Theme::default(),
+            board_geometry: BoardGeometry::top_left(),
+            move_history: Vec::new(),
+            remote: None,
+            spectators: None,
+            resigned_player: None,
+            terminal,
+            variant: None,
+            variant_cursor: 0,
+            variant_selection: None,
+            variant_symbol: BoardSpace::X,
+            variant_number: 1,
+            variant_moves: 0,
+            swap_available: false,
+            sides_swapped: false,
+            variant_history: Vec::new(),
+            variant_last_move: None,
+            multiplayer: None,
+            first_seat: Seat::X
         };
         Ok(new_instance)
     }
@@ -105,10 +244,10 @@ impl UI{
     /// is called, the game is played with desired settings.
     pub fn setup_menu(&mut self) -> crossterm::Result<()>
     {
-        let mut setup_menu = SetupMenu::new();
+        let mut setup_menu = SetupMenu::new(false, self.theme);
 
         //set exit flag if user chooses to exit during menu
-        if !setup_menu.setup_menu_loop()? {
+        if !setup_menu.setup_menu_loop(self.terminal.as_mut(), &self.key_bindings)? {
             self.exit_flag = true;
         }
 
@@ -117,6 +256,35 @@ impl UI{
         Ok(())
     }
 
+    /// Sets the [Rules] that classic and reverse games are played with
+    /// 
+    /// Whether lines wrap around the edges of the board is still chosen in the setup menu,
+    /// so [Rules::toroidal] is ignored.
+    pub fn set_rules(&mut self, rules: Rules)
+    {
+        self.rules = rules;
+    }
+
+    /// Sets the [Theme] boards are drawn in
+    /// 
+    /// This is the theme first selected in the setup menu.
+    pub fn set_theme(&mut self, theme: Theme)
+    {
+        self.theme = theme;
+    }
+
+    /// Returns the [Theme] boards are drawn in, which may have been chosen in the setup menu
+    pub fn theme(&self) -> Theme
+    {
+        self.theme
+    }
+
+    /// Sets the [KeyBindings] used in menus and games
+    pub fn set_key_bindings(&mut self, key_bindings: KeyBindings)
+    {
+        self.key_bindings = key_bindings;
+    }
+
     /// Returns true if the autoquit condition is satisfied, false otherwise
     /// 
     /// The exact set of circumstances that lead to this method returning true
@@ -129,15 +297,31 @@ impl UI{
                 self.number_of_games() >= self.game_autoquit_value
             },
             GameAutoquitMode::NonDrawNumberLimit => {
-                self.player_x_score() + self.player_o_score() >= self.game_autoquit_value
+                self.games_won >= self.game_autoquit_value
             },
             GameAutoquitMode::ScoreNumberLimit => {
-                self.player_x_score() >= self.game_autoquit_value ||
-                self.player_o_score() >= self.game_autoquit_value
+                self.half_points.iter().any(|half_points|{*half_points >= self.game_autoquit_value * 2})
             }
         }
     }
 
+    /// Returns how far the games played so far are towards the autoquit condition,
+    /// such as "Game 2 of 5"
+    /// 
+    /// If there is no autoquit condition, only the number of the current game is returned.
+    pub fn autoquit_progress(&self) -> String
+    {
+        let game_number = self.number_of_games() + 1;
+        match self.game_autoquit_mode {
+            GameAutoquitMode::Unlimited => format!("Game {}", game_number),
+            GameAutoquitMode::GameNumberLimit => format!("Game {} of {}", game_number, self.game_autoquit_value),
+            GameAutoquitMode::NonDrawNumberLimit => format!(
+                "Won games: {} of {}", self.games_won, self.game_autoquit_value
+            ),
+            GameAutoquitMode::ScoreNumberLimit => format!("First to {} wins", self.game_autoquit_value)
+        }
+    }
+
     /// Returns a reference to the [GameBoard] of this `UI`
     /// 
     /// Unlike [UI::take_game_board], this does not consume the `UI` instance.
@@ -159,6 +343,22 @@ impl UI{
         game_board
     }
 
+    /// Returns the last game's board(s) as text
+    /// 
+    /// For classic game modes, this is the [GameBoard]'s [Display](std::fmt::Display) text.
+    pub fn final_board(&self) -> String
+    {
+        match (&self.variant, &self.multiplayer) {
+            (_, Some(game)) if self.game_mode == GameMode::Multiplayer => {
+                format!("\n{}\n", variants::boards_to_string(&game.boards()))
+            },
+            (Some(variant), _) if !self.game_mode.is_classic() => {
+                format!("\n{}\n", variants::boards_to_string(&variant.boards()))
+            },
+            _ => self.game_board.to_string()
+        }
+    }
+
     /// Returns a reference to the [PlayerType] of the X player
     pub fn player_x(&self) -> &PlayerType
     {
@@ -171,16 +371,46 @@ impl UI{
         &self.player_o
     }
 
-    /// Returns the score (number of games won) of the X player
-    pub fn player_x_score(&self) -> u32
+    /// Returns a reference to the [PlayerType] of the player in the given seat
+    pub fn seat_player_type(&self, seat: Seat) -> &PlayerType
     {
-        self.player_x_score
+        match seat {
+            Seat::X => &self.player_x,
+            Seat::O => &self.player_o,
+            Seat::Triangle => &self.player_triangle,
+            Seat::Square => &self.player_square
+        }
     }
 
-    /// Returns the score (number of games won) of the O player
-    pub fn player_o_score(&self) -> u32
+    /// Returns an iterator over the seats of every player in the chosen game mode, in turn order
+    pub fn seats(&self) -> impl Iterator<Item = Seat>
+    {
+        let players = match self.game_mode {
+            GameMode::Multiplayer => self.variant_options.players,
+            _ => 2
+        };
+        Seat::first(players)
+    }
+
+    /// Returns the score of the player in the given seat
+    /// 
+    /// This is the number of games they won, plus half a point for each game in which they
+    /// won half a point (see [Variant::half_point]).
+    pub fn score(&self, seat: Seat) -> f64
     {
-        self.player_o_score
+        self.half_points[seat.index()] as f64 / 2.0
+    }
+
+    /// Returns the score of the X player (see [UI::score])
+    pub fn player_x_score(&self) -> f64
+    {
+        self.score(Seat::X)
+    }
+
+    /// Returns the score of the O player (see [UI::score])
+    pub fn player_o_score(&self) -> f64
+    {
+        self.score(Seat::O)
     }
 
     /// Returns the number of games that resulted in a draw
@@ -192,43 +422,39 @@ impl UI{
     /// Returns the total number of games played
     pub fn number_of_games(&self) -> u32
     {
-        self.player_x_score + self.player_o_score + self.number_of_draws
+        self.games_won + self.number_of_draws
     }
 
-    /// Returns a reference to the currently active player
-    pub fn active_player(&self) -> &ActivePlayer
+    /// Adds a win to the score of the player in the given seat
+    fn add_win(&mut self, seat: impl Into<Seat>)
     {
-        &self.active_player
+        self.half_points[seat.into().index()] += 2;
+        self.games_won += 1;
     }
 
-    /// Returns a mutable reference to the currently active player
-    pub fn active_player_mut(&mut self) -> &mut ActivePlayer
+    /// Adds half a point to the score of the player in the given seat, without counting
+    /// another game
+    fn add_half_point(&mut self, seat: impl Into<Seat>)
     {
-        &mut self.active_player
+        self.half_points[seat.into().index()] += 1;
     }
 
-    /// Performs setup tasks needed by the UI
-    /// 
-    /// Called by the constructor of this struct
-    fn setup_terminal() -> crossterm::Result<()>
+    /// Counts a drawn game
+    fn add_draw(&mut self)
     {
-        terminal::enable_raw_mode()?;
-        stdout()
-            .queue(EnterAlternateScreen)?
-            .flush()?;
-        Ok(())
+        self.number_of_draws += 1;
     }
 
-    /// Performs cleanup tasks needed by the UI
-    /// 
-    /// Called by the [Drop] implementation of this struct
-    fn cleanup_terminal() -> crossterm::Result<()>
+    /// Returns a reference to the currently active player
+    pub fn active_player(&self) -> &ActivePlayer
     {
-        stdout()
-            .queue(LeaveAlternateScreen)?
-            .flush()?;
-        terminal::disable_raw_mode()?;
-        Ok(())
+        &self.active_player
+    }
+
+    /// Returns a mutable reference to the currently active player
+    pub fn active_player_mut(&mut self) -> &mut ActivePlayer
+    {
+        &mut self.active_player
     }
 
     /// Resets cursor position to (1,1)
@@ -238,10 +464,22 @@ impl UI{
         self.cursor_y_pos = 1;
     }
 
+    /// Returns the player (as chosen in the setup menu) who is playing the given side
+    /// 
+    /// This is the same player unless the players swapped sides under the swap rule
+    fn player_identity(&self, side: ActivePlayer) -> ActivePlayer
+    {
+        if self.sides_swapped {
+            side.opposite()
+        } else {
+            side
+        }
+    }
+
     /// Returns the PlayerType of the currently active player
     fn active_player_type(&self) -> &PlayerType
     {
-        match self.active_player{
+        match self.player_identity(self.active_player.clone()){
             ActivePlayer::PlayerO => &self.player_o,
             ActivePlayer::PlayerX => &self.player_x
         }
@@ -253,7 +491,7 @@ impl Drop for UI {
     /// [Read More](https://doc.rust-lang.org/1.62.1/core/ops/trait.Drop.html#tymethod.drop)
     fn drop(&mut self) 
     {
-        if UI::cleanup_terminal().is_err(){
+        if self.terminal.leave().is_err(){
             panic!("Failed to cleanup terminal when dropping UI");
         }
     }
@@ -0,0 +1,222 @@
+//! The size and position of the classic board, which is centred and scaled to fit the terminal
+
+use crate::gameboard::{BoardSpace, BoardSpaceLocation};
+
+/// How large the spaces of the classic board are drawn
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum BoardScale {
+    /// Each space is one row of three columns, with its symbol in the middle
+    Compact,
+    /// Each space is three rows of seven columns, with its symbol drawn in block characters
+    Large,
+    /// Each space is five rows of thirteen columns, with its symbol drawn in block characters
+    Huge
+}
+
+impl BoardScale {
+
+    /// Every scale, from largest to smallest
+    const LARGEST_FIRST: [BoardScale; 3] = [BoardScale::Huge, BoardScale::Large, BoardScale::Compact];
+
+    /// Returns the number of columns and rows of each space
+    pub fn cell_size(&self) -> (u16, u16)
+    {
+        match self {
+            BoardScale::Compact => (3, 1),
+            BoardScale::Large => (7, 3),
+            BoardScale::Huge => (13, 5)
+        }
+    }
+
+    /// Returns the number of columns and rows of the whole board, including the lines
+    /// between spaces
+    pub fn board_size(&self) -> (u16, u16)
+    {
+        let (cell_width, cell_height) = self.cell_size();
+        (cell_width * 3 + 2, cell_height * 3 + 2)
+    }
+
+    /// Returns the lines of text drawn in a space containing the given [BoardSpace]
+    ///
+    /// There is one line for each row of the space, each as wide as the space.
+    pub fn cell_lines(&self, space: &BoardSpace) -> Vec<String>
+    {
+        let (cell_width, cell_height) = self.cell_size();
+        let glyph: &[&str] = match (self, space) {
+            (BoardScale::Compact, _) | (_, BoardSpace::Empty) => &[],
+            (BoardScale::Large, BoardSpace::X) => &["▀▄ ▄▀", "  █  ", "▄▀ ▀▄"],
+            (BoardScale::Large, BoardSpace::O) => &["▄▀▀▀▄", "█   █", "▀▄▄▄▀"],
+            (BoardScale::Large, BoardSpace::Blocked) => &["▓▓▓▓▓"; 3],
+            (BoardScale::Huge, BoardSpace::X) => &[
+                "██     ██",
+                "  ██ ██  ",
+                "    █    ",
+                "  ██ ██  ",
+                "██     ██"
+            ],
+            (BoardScale::Huge, BoardSpace::O) => &[
+                "  █████  ",
+                "██     ██",
+                "██     ██",
+                "██     ██",
+                "  █████  "
+            ],
+            (BoardScale::Huge, BoardSpace::Blocked) => &["▓▓▓▓▓▓▓▓▓"; 5]
+        };
+
+        (0..cell_height as usize).map(|row|{
+            let text = match glyph.get(row) {
+                Some(text) => text.to_string(),
+                // compact spaces show their symbol, as does the middle row of an empty space
+                None if row == cell_height as usize / 2 && glyph.is_empty() => space.get_char().to_string(),
+                None => String::new()
+            };
+            format!("{:^width$}", text, width = cell_width as usize)
+        }).collect()
+    }
+
+    /// Returns the text of a line between two rows of spaces
+    pub fn horizontal_line(&self) -> String
+    {
+        let (cell_width, _) = self.cell_size();
+        match self {
+            BoardScale::Compact => "-".repeat(cell_width as usize * 3 + 2),
+            _ => vec!["─".repeat(cell_width as usize); 3].join("┼")
+        }
+    }
+
+    /// Returns the character drawn between two spaces in a row
+    pub fn vertical_line(&self) -> char
+    {
+        match self {
+            BoardScale::Compact => '|',
+            _ => '│'
+        }
+    }
+}
+
+/// The position and [BoardScale] of the classic board, and of the side panel and the lines of
+/// text drawn with it
+///
+/// The board, the panel (if any) to its right, and the text below them are centred in the
+/// terminal as a single block.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct BoardGeometry {
+    /// How large the spaces of the board are drawn
+    pub scale: BoardScale,
+    /// The column of the left edge of the board
+    pub left: u16,
+    /// The row of the top edge of the board
+    pub top: u16,
+    /// The column of the left edge of the side panel, if there is room for one
+    pub panel_left: Option<u16>,
+    /// The column the text below the board starts at
+    pub text_left: u16,
+    /// The row of the first line of text below the board
+    pub text_top: u16
+}
+
+impl BoardGeometry {
+
+    /// Columns between the board and the side panel
+    pub const PANEL_GAP: u16 = 3;
+
+    /// Calculates the geometry of the board in a terminal of the given size
+    ///
+    /// `text_size` is the number of columns and rows of the text drawn below the board, and
+    /// `panel_size` is the size of the side panel, if one should be drawn. The largest scale
+    /// that fits (with the side panel, if any) is chosen; if even a compact board doesn't fit
+    /// with the side panel, a compact board is drawn without one. The text may be wider than the
+    /// terminal, but is moved left to show as much of it as possible.
+    ///
+    /// Returns `None` if the terminal is too small for a compact board and the text below it.
+    pub fn new(terminal_size: (u16, u16), text_size: (u16, u16), panel_size: Option<(u16, u16)>) -> Option<Self>
+    {
+        let mut candidates = BoardScale::LARGEST_FIRST.map(|scale|{(scale, panel_size)}).to_vec();
+        if panel_size.is_some() {
+            candidates.push((BoardScale::Compact, None));
+        }
+        candidates.into_iter()
+            .find_map(|(scale, panel_size)|{Self::with_scale(scale, terminal_size, text_size, panel_size)})
+    }
+
+    /// Returns the geometry of a compact board in the top left corner, with no side panel,
+    /// for drawing a board in a terminal too small for [BoardGeometry::new] to fit one
+    pub fn top_left() -> Self
+    {
+        let (_, board_height) = BoardScale::Compact.board_size();
+        Self{
+            scale: BoardScale::Compact,
+            left: 0,
+            top: 0,
+            panel_left: None,
+            text_left: 0,
+            text_top: board_height + 1
+        }
+    }
+
+    /// Calculates the geometry of a board of the given scale, if it fits in the terminal
+    fn with_scale(
+        scale: BoardScale,
+        (terminal_width, terminal_height): (u16, u16),
+        (text_width, text_height): (u16, u16),
+        panel_size: Option<(u16, u16)>
+    ) -> Option<Self>
+    {
+        let (board_width, board_height) = scale.board_size();
+        let (panel_width, panel_height) = panel_size.map_or((0, 0), |(width, height)|{(width + Self::PANEL_GAP, height)});
+
+        // a blank line is left between the board and the text below it
+        let block_width = board_width + panel_width;
+        let block_height = board_height.max(panel_height) + 1 + text_height;
+        if block_width > terminal_width || block_height > terminal_height {
+            return None;
+        }
+
+        let left = (terminal_width - block_width) / 2;
+        let top = (terminal_height - block_height) / 2;
+        Some(Self{
+            scale,
+            left,
+            top,
+            panel_left: panel_size.map(|_|{left + board_width + Self::PANEL_GAP}),
+            text_left: left.min(terminal_width.saturating_sub(text_width)),
+            text_top: top + board_height.max(panel_height) + 1
+        })
+    }
+
+    /// Returns the number of columns and rows of the board
+    pub fn board_size(&self) -> (u16, u16)
+    {
+        self.scale.board_size()
+    }
+
+    /// Returns the column and row of the top left corner of the given space
+    pub fn cell_origin(&self, location: BoardSpaceLocation) -> (u16, u16)
+    {
+        let (x, y) = location.as_coordinates();
+        let (cell_width, cell_height) = self.scale.cell_size();
+        (self.left + x as u16 * (cell_width + 1), self.top + y as u16 * (cell_height + 1))
+    }
+
+    /// Returns the column and row of the middle of the given space, where the terminal's
+    /// cursor is placed
+    pub fn cell_centre(&self, location: BoardSpaceLocation) -> (u16, u16)
+    {
+        let (column, row) = self.cell_origin(location);
+        let (cell_width, cell_height) = self.scale.cell_size();
+        (column + cell_width / 2, row + cell_height / 2)
+    }
+
+    /// Returns the space drawn at the given column and row of the terminal, if any
+    ///
+    /// The lines between spaces are not part of any space.
+    pub fn space_at(&self, column: u16, row: u16) -> Option<BoardSpaceLocation>
+    {
+        let (cell_width, cell_height) = self.scale.cell_size();
+        let (column, row) = (column.checked_sub(self.left)?, row.checked_sub(self.top)?);
+        let (x, y) = (column / (cell_width + 1), row / (cell_height + 1));
+        (column % (cell_width + 1) != cell_width && row % (cell_height + 1) != cell_height && x < 3 && y < 3)
+            .then(||{BoardSpaceLocation::from_coordinates((x as u8, y as u8))})
+    }
+}
@@ -0,0 +1,89 @@
+//! The configuration directory, which holds settings kept between runs
+//!
+//! The directory is `$XDG_CONFIG_HOME/tactic`, or `~/.config/tactic` if `XDG_CONFIG_HOME`
+//! isn't set (`%APPDATA%\tactic` on Windows). It may contain:
+//!
+//! - `keys`: key bindings, in the [key bindings file](super::key_bindings) format. These are
+//!   used unless `--keys` is given on the command line.
+//!
+//! - `theme`: the name of a theme, as given to `--theme`. This theme is used (even if
+//!   `NO_COLOR` is set) unless `--theme` is given, and is replaced whenever another theme is
+//!   chosen in the setup menu.
+
+use std::{
+    env,
+    fs,
+    io,
+    path::PathBuf
+};
+
+use super::{
+    key_bindings::{KeyBindings, KeyBindingsError},
+    theme::Theme
+};
+
+/// Returns the configuration directory, if it can be found
+pub fn directory() -> Option<PathBuf>
+{
+    let non_empty = |name: &str|{env::var_os(name).filter(|value|{!value.is_empty()}).map(PathBuf::from)};
+
+    if cfg!(windows) {
+        return non_empty("APPDATA").map(|path|{path.join("tactic")});
+    }
+    non_empty("XDG_CONFIG_HOME")
+        .or_else(||{non_empty("HOME").map(|home|{home.join(".config")})})
+        .map(|path|{path.join("tactic")})
+}
+
+/// Returns the path of the key bindings file in the configuration directory, if it can be found
+pub fn key_bindings_path() -> Option<PathBuf>
+{
+    directory().map(|directory|{directory.join("keys")})
+}
+
+/// Returns the path of the theme file in the configuration directory, if it can be found
+pub fn theme_path() -> Option<PathBuf>
+{
+    directory().map(|directory|{directory.join("theme")})
+}
+
+/// Loads the key bindings file from the configuration directory
+///
+/// Returns `Ok(None)` if there is no such file.
+pub fn load_key_bindings() -> Result<Option<KeyBindings>, KeyBindingsError>
+{
+    let contents = match key_bindings_path().map(fs::read_to_string) {
+        None => return Ok(None),
+        Some(Err(error)) if error.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Some(Err(error)) => return Err(KeyBindingsError::Io(error.to_string())),
+        Some(Ok(contents)) => contents
+    };
+    KeyBindings::parse(&contents).map(Some)
+}
+
+/// Loads the theme from the configuration directory
+///
+/// Returns `Ok(None)` if there is no theme file, or `Err` with a message if it can't be read
+/// or doesn't name a theme.
+pub fn load_theme() -> Result<Option<Theme>, String>
+{
+    let contents = match theme_path().map(fs::read_to_string) {
+        None => return Ok(None),
+        Some(Err(error)) if error.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Some(Err(error)) => return Err(error.to_string()),
+        Some(Ok(contents)) => contents
+    };
+    let name = contents.trim();
+    Theme::from_name(name).map(Some).ok_or_else(||{format!("unknown theme '{}'", name)})
+}
+
+/// Saves the given theme to the configuration directory, creating the directory if needed
+pub fn save_theme(theme: Theme) -> io::Result<()>
+{
+    let path = theme_path()
+        .ok_or_else(||{io::Error::new(io::ErrorKind::NotFound, "no configuration directory could be found")})?;
+    if let Some(directory) = path.parent() {
+        fs::create_dir_all(directory)?;
+    }
+    fs::write(path, format!("{}\n", theme.command_line_name()))
+}
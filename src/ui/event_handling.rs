@@ -1,15 +1,19 @@
 //! UI implementations for event handling
 
 use crossterm::{
-    event::{self, Event, KeyEvent, KeyCode, KeyModifiers},
-    terminal::{self, Clear, ClearType},
+    event::{Event, KeyEvent, KeyCode, KeyModifiers, MouseEvent, MouseEventKind, MouseButton},
+    terminal::{Clear, ClearType},
     ExecutableCommand
 };
 use crate::{
     gameboard::{BoardSpaceLocation, BoardSpace},
-    active_player::ActivePlayer
+    active_player::ActivePlayer,
+    player_type::PlayerType,
+    ai::AiPlayer,
+    network::protocol::Message
 };
-use std::io::stdout;
+
+use super::key_bindings::Action;
 
 impl super::UI {
 
@@ -65,27 +69,39 @@ impl super::UI {
         }
     }
 
-    /// Claim the selected space for the active player if possible
+    /// Moves the cursor to the space clicked with the given [MouseEvent], if any
+    ///
+    /// Returns `true` if a space was left-clicked
+    pub(super) fn click_board_space(&mut self, mouse_event: MouseEvent) -> bool
+    {
+        let MouseEvent{kind: MouseEventKind::Down(MouseButton::Left), column, row, ..} = mouse_event else {
+            return false;
+        };
+        match self.board_geometry.space_at(column, row) {
+            Some(location) => {
+                (self.cursor_x_pos, self.cursor_y_pos) = location.as_coordinates();
+                true
+            },
+            None => false
+        }
+    }
+
+    /// Claims the given space for the active player and switches players
     /// 
-    /// Returns `true` if successful, `false` if not
-    pub(super) fn claim_space(&mut self) -> bool
+    /// Any spectators are notified of the move, as is the remote player if `notify_remote` is true
+    pub(super) fn place_piece(&mut self, location: BoardSpaceLocation, notify_remote: bool) -> crossterm::Result<()>
     {
-        let desired_location = 
-            BoardSpaceLocation::from_coordinates((self.cursor_x_pos, self.cursor_y_pos));
-        let desired_space = 
-            self.game_board.space_mut(desired_location);
-        
-        // only update space and switch players if selected space is empty
-        if desired_space == &BoardSpace::Empty {
-            //write active player letter to this space
-            *desired_space = match self.active_player {
-                ActivePlayer::PlayerX => BoardSpace::X,
-                ActivePlayer::PlayerO => BoardSpace::O
-            };
-            true
-        } else {
-            false
+        *self.game_board.space_mut(location) = self.active_player.get_board_space();
+        self.move_history.push(location);
+
+        let message = Message::Move(location);
+        if notify_remote {
+            self.send_to_remote(&message)?;
         }
+        self.broadcast_to_spectators(&message);
+
+        self.switch_active_player();
+        Ok(())
     }
 
     /// Switches the active player and resets cursor position
@@ -98,59 +114,149 @@ impl super::UI {
         self.reset_cursor_pos();
     }
 
+    /// Claims the selected space for the active player if they are a local human player
+    /// and the space is empty
+    /// 
+    /// Does nothing if the space cannot be claimed
+    pub(super) fn play_selected_space(&mut self) -> crossterm::Result<()>
+    {
+        let location = 
+            BoardSpaceLocation::from_coordinates((self.cursor_x_pos, self.cursor_y_pos));
+
+        // only claim space and switch players if selected space is empty
+        if self.active_player_type() == &PlayerType::Human && 
+            self.game_board.space(location) == &BoardSpace::Empty {
+            self.place_piece(location, true)?;
+        }
+        Ok(())
+    }
+
+    /// Takes back the last move, and the moves of any AI players since, so that it is a human
+    /// player's turn again
+    /// 
+    /// Does nothing in a networked game, or if no moves have been played
+    pub(super) fn undo_move(&mut self)
+    {
+        if self.remote.is_some() {
+            return;
+        }
+        while let Some(location) = self.move_history.pop() {
+            *self.game_board.space_mut(location) = BoardSpace::Empty;
+            self.switch_active_player();
+            if self.active_player_type() == &PlayerType::Human {
+                break;
+            }
+        }
+        self.broadcast_to_spectators(&Message::Board(self.game_board.clone(), self.active_player.clone()));
+    }
+
+    /// Moves the cursor to the space the strongest AI player would claim for the active player
+    /// 
+    /// Hints are only given in local games.
+    pub(super) fn show_hint(&mut self)
+    {
+        if let Ok(location) = AiPlayer::new(1.0).choose_move(&self.game_board, &self.active_player) {
+            (self.cursor_x_pos, self.cursor_y_pos) = location.as_coordinates();
+        }
+    }
+
+    /// Returns the on-screen help for choosing a space (or other `target`) with the cursor
+    /// 
+    /// The help names the keys that move the cursor, place a piece (or `place_verb`), and quit
+    /// (or `quit_verb`). `symbol` is another key that places a piece, if there is one.
+    pub(super) fn selection_help(&self, target: &str, symbol: Option<char>, place_verb: &str, quit_verb: &str) -> String
+    {
+        let key_bindings = &self.key_bindings;
+        let place_keys = match symbol {
+            Some(symbol) => format!("'{}' or '{}'", key_bindings.key_name(Action::Place), symbol),
+            None => format!("'{}'", key_bindings.key_name(Action::Place))
+        };
+        format!(
+            "Use {} to select {}. Press {} to {}. Press {} to {}.",
+            key_bindings.movement_keys(), target, place_keys, place_verb, key_bindings.key_name(Action::Quit), quit_verb
+        )
+    }
+
     /// Blocks until a [crossterm::event::Event] is available, then handles it
+    /// 
+    /// In a networked game, this also returns after handling a message from the remote player
     pub(super) fn handle_next_event(&mut self) -> crossterm::Result<()>
     {
-        match event::read()? {
+        if self.remote.is_some(){
+            self.handle_next_event_or_message()
+        } else {
+            let event = self.terminal.read_event()?;
+            self.handle_event(event)
+        }
+    }
+
+    /// Handles the given [crossterm::event::Event]
+    pub(super) fn handle_event(&mut self, event: Event) -> crossterm::Result<()>
+    {
+        match event {
+            Event::Key(KeyEvent{code:KeyCode::Char('c'), modifiers:KeyModifiers::CONTROL, ..}) => {
+                self.send_to_remote(&Message::Quit)?;
+                self.exit_flag = true;
+            },
             Event::Key(key_event) => {
-                match key_event {
-                    KeyEvent{code:KeyCode::Right, ..} => {
+                match (self.key_bindings.action(&key_event), key_event.code) {
+                    (Some(Action::Right), _) => {
                         self.move_cursor_right();
                     },
-                    KeyEvent{code:KeyCode::Left, ..} => {
+                    (Some(Action::Left), _) => {
                         self.move_cursor_left();
                     },
-                    KeyEvent{code:KeyCode::Down, ..} => {
+                    (Some(Action::Down), _) => {
                         self.move_cursor_down();
                     },
-                    KeyEvent{code:KeyCode::Up, ..} => {
+                    (Some(Action::Up), _) => {
                         self.move_cursor_up();
                     },
-                    KeyEvent{code:KeyCode::Enter, ..} => {
+                    (Some(Action::Place), _) => {
                         //attempt to claim space and switch turns if successful
-                        if self.claim_space(){
-                            self.switch_active_player();
+                        self.play_selected_space()?;
+                    },
+                    (Some(Action::PlaceAt(location)), _) => {
+                        (self.cursor_x_pos, self.cursor_y_pos) = location.as_coordinates();
+                        self.play_selected_space()?;
+                    },
+                    (Some(Action::Undo), _) => {
+                        self.undo_move();
+                    },
+                    (Some(Action::Hint), _) if self.remote.is_none() => {
+                        self.show_hint();
+                    },
+                    (Some(Action::Quit), _) => {
+                        //in a networked game, quitting forfeits the current game instead
+                        if self.remote.is_some(){
+                            self.resign()?;
+                        } else {
+                            self.exit_flag = true;
                         }
                     },
-                    KeyEvent{code:KeyCode::Char('x'), ..} => {
+                    (None, KeyCode::Char('x')) if self.active_player == ActivePlayer::PlayerX => {
                         //attempt to claim space if active player is X
-                        if self.active_player == ActivePlayer::PlayerX && self.claim_space(){
-                            self.switch_active_player();
-                        }
+                        self.play_selected_space()?;
                     },
-                    KeyEvent{code:KeyCode::Char('o'), ..} => {
+                    (None, KeyCode::Char('o')) if self.active_player == ActivePlayer::PlayerO => {
                         //attempt to claim space if active player is O
-                        if self.active_player == ActivePlayer::PlayerO && self.claim_space(){
-                            self.switch_active_player();
-                        }
+                        self.play_selected_space()?;
                     },
-                    KeyEvent{code:KeyCode::Char('q'), ..} => {
-                        self.exit_flag = true;
-                    },
-                    KeyEvent{code:KeyCode::Char('c'), modifiers:KeyModifiers::CONTROL, ..} => {
-                        self.exit_flag = true;
-                    }
                     _ => {
                         //ignore other KeyEvents
                     }
                 }
             },
+            //clicking a space claims it, like selecting it and pressing Enter
+            Event::Mouse(mouse_event) if self.click_board_space(mouse_event) => {
+                self.play_selected_space()?;
+            },
             //ignore size returned by resize event as it is currently (as of crossterm 0.25) wrong on Windows
             Event::Resize(_,_) => {
-                let (new_x, new_y) = terminal::size()?;
+                let (new_x, new_y) = self.terminal.size()?;
                 self.terminal_x_size = new_x;
                 self.terminal_y_size = new_y;
-                stdout().execute(Clear(ClearType::All))?;
+                self.terminal.execute(Clear(ClearType::All))?;
             }
             _ => {
                 //ignore other Events
@@ -1,24 +1,35 @@
 //! UI implementations for playing games of tic-tac-toe
 
-use std::io::{stdout, Write};
+use std::{io::Write, sync::Arc};
 
 use crossterm::{
-    terminal::{self, Clear, ClearType},
-    style::{Print, StyledContent, Stylize},
-    cursor::{self, MoveToColumn, MoveToRow, MoveToNextLine},
+    terminal::{Clear, ClearType},
+    style::{Print, ContentStyle},
+    cursor::{self, MoveTo, MoveToColumn, MoveToRow},
     QueueableCommand,
     ExecutableCommand
 };
 
 use crate::{
     game_outcome::{GameOutcome, WinPosition},
-    gameboard::{GameBoard, BoardSpaceLocation},
+    gameboard::{GameBoard, BoardSpace, BoardSpaceLocation},
     player_type::PlayerType,
+    active_player::ActivePlayer,
     ai::AiError, 
-    game_settings::GameMode
+    game_settings::GameMode,
+    network::protocol::Message,
+    variants::Outcome,
+    rules::Rules,
+    multiplayer::Seat
 };
 
+use super::{key_bindings::Action, theme, board_geometry::BoardGeometry};
+
 impl super::UI{
+
+    /// The width of the side panel drawn beside the board(s)
+    pub(super) const SIDE_PANEL_WIDTH: u16 = 20;
+
     /// The main game loop
     ///
     /// Allows player X to claim one space, then allows player O to claim one space.
@@ -26,57 +37,51 @@ impl super::UI{
     /// quits the game.
     pub fn game_loop(&mut self) -> crossterm::Result<GameOutcome>
     {
+        // don't start (or clear the board for) a new game if the user has already quit
+        if self.exit_flag {
+            return Ok(self.game_board.game_outcome());
+        }
+
         //update terminal size
-        (self.terminal_x_size, self.terminal_y_size) = terminal::size()?;
+        (self.terminal_x_size, self.terminal_y_size) = self.terminal.size()?;
         
         self.reset_cursor_pos();
 
-        self.game_board = GameBoard::new();
+        self.game_board = GameBoard::with_rules(Arc::new(Rules{
+            toroidal: self.variant_options.toroidal,
+            ..self.rules.clone()
+        }));
+        self.move_history.clear();
+        self.set_up_starting_position()?;
+        self.resigned_player = None;
         let mut game_outcome = self.game_board.game_outcome();
+
+        self.broadcast_to_spectators(&Message::Board(self.game_board.clone(), self.active_player.clone()));
         
-        stdout().execute(Clear(ClearType::All))?;
-
-        // keep playing game until game outcome is finished 
-        // or exit flag is set (because user chose to quit)
-        while !(game_outcome.game_finished() || self.exit_flag){
-            stdout()
-                //hide the cursor while drawing game board
-                .queue(cursor::Hide)?
-                .queue(MoveToColumn(0))?
-                .queue(MoveToRow(0))?
-                .flush()?;
-
-            // only print game board if terminal is large enough
-            if self.terminal_x_size >= Self::TERMSIZE_MIN_X && self.terminal_y_size >= Self::TERMSIZE_MIN_Y {
-                self.draw_game(None)?;
-                stdout()
-                    .queue(MoveToRow(6))?
-                    .queue(Print(format!("{}'s turn", self.active_player.get_char())))?
-                    .queue(MoveToRow(7))?.queue(MoveToColumn(0))?
-                    .queue(Print(format!(
-                        "Use arrow keys to select space. Press 'Enter' or '{}' to place. Press q to quit.",
-                        self.active_player.get_char()
-                    )))?
-                    // position cursor in the appropriate space
-                    .queue(MoveToColumn(((self.cursor_x_pos as u16) * 4) + 1))?
-                    .queue(MoveToRow((self.cursor_y_pos as u16) * 2))?
-                    // show the cursor again
-                    .queue(cursor::Show)?
-
-                    .flush()?;
-            } else {
-                // print error message instead of game board if terminal is too small
-                stdout()
-                    .execute(Print(format!("Terminal too small ({} x {})! Please enlarge terminal", self.terminal_x_size, self.terminal_y_size)))?;
-            }
+        self.terminal.execute(Clear(ClearType::All))?;
+
+        // keep playing game until game outcome is finished,
+        // exit flag is set (because user chose to quit), or a player resigns
+        while !(game_outcome.game_finished() || self.exit_flag || self.resigned_player.is_some()){
+            let cursor = (self.active_player_type() == &PlayerType::Human)
+                .then(||{BoardSpaceLocation::from_coordinates((self.cursor_x_pos, self.cursor_y_pos))});
+            let status = format!("{}'s turn.", self.active_player.get_char());
+            let key_text = self.turn_key_text();
+            let help_text = self.selection_help(
+                "space",
+                Some(self.active_player.get_char()),
+                "place",
+                if self.remote.is_some() {"resign"} else {"quit"}
+            );
+            self.draw_classic_screen(&status, &key_text, &help_text, cursor)?;
 
             match self.active_player_type() {
                 PlayerType::Human => self.handle_next_event()?,
+                PlayerType::Remote => self.handle_next_event_or_message()?,
                 PlayerType::AI(ai_player) => {
-                    match ai_player.do_turn(&self.game_board, &self.active_player){
-                        Ok(new_board) =>{
-                            self.game_board = new_board;
-                            self.switch_active_player();
+                    match ai_player.choose_move(&self.game_board, &self.active_player){
+                        Ok(location) =>{
+                            self.place_piece(location, true)?;
                         },
                         Err(ai_error) => {
                             if ai_error == AiError::NoMovesFound{
@@ -90,115 +95,370 @@ impl super::UI{
             game_outcome = self.game_board.game_outcome();
         }
 
+        // a resignation is a loss for the resigning player regardless of game mode
+        if let Some(resigned_player) = &self.resigned_player {
+            self.add_win(resigned_player.opposite());
+        } else {
+            self.score_game_outcome(&game_outcome);
+        }
+
+        self.broadcast_to_spectators(&Message::Score{
+            // classic games are only won by whole points
+            player_x: self.player_x_score() as u32,
+            player_o: self.player_o_score() as u32,
+            draws: self.number_of_draws
+        });
+
+        Ok(game_outcome)
+    }
+
+    /// Returns the keys for undoing moves, hints, and placing pieces directly, where they are
+    /// available, as shown above the help text during a classic game
+    fn turn_key_text(&self) -> String
+    {
+        let key_bindings = &self.key_bindings;
+        let mut key_text = Vec::new();
+        if self.remote.is_none() {
+            match (key_bindings.is_bound(Action::Undo), key_bindings.is_bound(Action::Hint)) {
+                (true, true) => key_text.push(format!(
+                    "Press {} to undo or {} for a hint.",
+                    key_bindings.key_name(Action::Undo), key_bindings.key_name(Action::Hint)
+                )),
+                (true, false) => key_text.push(format!("Press {} to undo.", key_bindings.key_name(Action::Undo))),
+                (false, true) => key_text.push(format!("Press {} for a hint.", key_bindings.key_name(Action::Hint))),
+                (false, false) => {}
+            }
+        }
+        if let Some(keys) = key_bindings.direct_placement_keys() {
+            key_text.push(format!("Keys {} place directly.", keys));
+        }
+        key_text.join(" ")
+    }
+
+    /// Draws the classic board, scaled and centred in the terminal, with the side panel and
+    /// two lines of text below it
+    ///
+    /// `status` is shown at the top of the side panel, followed by the players, the scores
+    /// and as many of the last moves as fit beside the board.
+    /// `details` and `help_text` are the lines below the board; if there is no room for the
+    /// side panel, `status` starts the first of them instead. The terminal's cursor is placed
+    /// in the `cursor` space, if any, or hidden otherwise.
+    ///
+    /// If the terminal is too small for the board, an error message is drawn instead.
+    pub(super) fn draw_classic_screen(
+        &mut self,
+        status: &str,
+        details: &str,
+        help_text: &str,
+        cursor: Option<BoardSpaceLocation>
+    ) -> crossterm::Result<()>
+    {
+        self.terminal
+            //hide the cursor while drawing game board
+            .queue(cursor::Hide)?
+            .queue(MoveToColumn(0))?
+            .queue(MoveToRow(0))?
+            .flush()?;
+
+        let mut panel_lines = self.status_panel_lines(status);
+        panel_lines.push((String::new(), ContentStyle::new()));
+        panel_lines.push(("Moves".to_owned(), ContentStyle::new()));
+        let first_line_without_panel = format!("{} {}", status, details);
+        let text_width = first_line_without_panel.chars().count().max(help_text.chars().count()) as u16;
+        // leave room for at least the last move
+        let panel_height = panel_lines.len() as u16 + 1;
+        let geometry = BoardGeometry::new(
+            (self.terminal_x_size, self.terminal_y_size),
+            (text_width, 2),
+            Some((Self::SIDE_PANEL_WIDTH, panel_height))
+        );
+
+        // only print game board if terminal is large enough
+        let Some(geometry) = geometry else {
+            // print error message instead of game board if terminal is too small
+            self.terminal
+                .execute(Print(format!("Terminal too small ({} x {})! Please enlarge terminal", self.terminal_x_size, self.terminal_y_size)))?;
+            return Ok(());
+        };
+
+        self.draw_game(geometry, None, cursor)?;
+        let first_line = match geometry.panel_left {
+            Some(panel_left) => {
+                // list as many of the last moves as fit beside the board
+                let (_, board_height) = geometry.board_size();
+                let move_rows = board_height.max(panel_height) as usize - panel_lines.len();
+                panel_lines.extend(self.move_list_lines(move_rows));
+                self.draw_side_panel(panel_lines, panel_left, geometry.top)?;
+                details.to_owned()
+            },
+            None => first_line_without_panel
+        };
+        self.terminal
+            .queue(MoveTo(geometry.text_left, geometry.text_top))?
+            .queue(Print(first_line.trim_end()))?
+            .queue(Clear(ClearType::UntilNewLine))?
+            .queue(MoveTo(geometry.text_left, geometry.text_top + 1))?
+            .queue(Print(help_text))?
+            .queue(Clear(ClearType::UntilNewLine))?;
+
+        if let Some(cursor) = cursor {
+            // position cursor in the appropriate space
+            let (column, row) = geometry.cell_centre(cursor);
+            self.terminal
+                .queue(MoveTo(column, row))?
+                // show the cursor again
+                .queue(cursor::Show)?;
+        }
+        self.terminal.flush()?;
+        Ok(())
+    }
+
+    /// Returns the last moves of the game so far, one per line with its style, as listed in
+    /// the side panel beside the classic board
+    /// 
+    /// Exactly `rows` lines are returned, padded with empty lines if there are fewer moves.
+    /// The last move is highlighted like its space on the board.
+    fn move_list_lines(&self, rows: usize) -> Vec<(String, ContentStyle)>
+    {
+        let first_shown = self.move_history.len().saturating_sub(rows);
+        let mut lines: Vec<_> = self.move_history.iter().enumerate().skip(first_shown).map(|(number, location)|{
+            let symbol = self.game_board.space(*location).get_char();
+            let mut style = self.theme.symbol(symbol);
+            if number + 1 == self.move_history.len() {
+                style = theme::combine(style, self.theme.last_move());
+            }
+            (format!("{}. {} {}", number + 1, symbol, location.name()), style)
+        }).collect();
+        lines.resize(rows, (String::new(), ContentStyle::new()));
+        lines
+    }
+
+    /// Returns the lines of the status panel drawn beside the board(s), each with its style:
+    /// `status`, then each player's type and score, the number of draws, and the progress
+    /// towards the autoquit condition
+    pub(super) fn status_panel_lines(&self, status: &str) -> Vec<(String, ContentStyle)>
+    {
+        let mut lines = vec![(status.to_owned(), ContentStyle::new()), (String::new(), ContentStyle::new())];
+        for seat in self.seats() {
+            let player = match self.seat_player_type(seat) {
+                PlayerType::Human => "Human".to_owned(),
+                PlayerType::AI(ai_player) => format!("AI ({:.0})", ai_player.difficulty() * 100.0),
+                PlayerType::Remote => "Remote".to_owned()
+            };
+            lines.push((format!("{}  {:<11}{:>4}", seat.get_char(), player, self.score(seat)), self.theme.seat(seat)));
+        }
+        lines.push((format!("{:<14}{:>4}", "Draws", self.number_of_draws()), ContentStyle::new()));
+        lines.push((self.autoquit_progress(), ContentStyle::new()));
+        lines
+    }
+
+    /// Draws the given lines of a side panel, with its top left corner at the given column
+    /// and row
+    /// 
+    /// Each line is padded to [SIDE_PANEL_WIDTH](Self::SIDE_PANEL_WIDTH) columns, covering
+    /// whatever was drawn there before.
+    pub(super) fn draw_side_panel(&mut self, lines: Vec<(String, ContentStyle)>, column: u16, row: u16) -> crossterm::Result<()>
+    {
+        for (row, (text, style)) in (row..).zip(lines) {
+            let padding = (Self::SIDE_PANEL_WIDTH as usize).saturating_sub(text.chars().count());
+            self.terminal
+                .queue(MoveTo(column, row))?
+                .queue(Print(style.apply(text)))?
+                .queue(Print(" ".repeat(padding)))?;
+        }
+        Ok(())
+    }
+
+    /// Plays games until the user quits, the autoquit condition is satisfied,
+    /// or (in a networked game) either player declines a rematch
+    /// 
+    /// After each game, the play again menu is shown. The loser of a game starts the next one,
+    /// as does a player who resigned; after a draw, the other player starts. In games with more
+    /// than two players, the next player in turn order starts the next game.
+    pub fn play_games(&mut self) -> crossterm::Result<()>
+    {
+        loop {
+            let first_player = self.active_player.clone();
+            // the player to start the next game, unless a player resigned
+            let (quit, next_player) = if self.game_mode.is_classic() {
+                let game_outcome = self.game_loop()?;
+                let next_player = match game_outcome {
+                    GameOutcome::PlayerX(_) => Some(ActivePlayer::PlayerO),
+                    GameOutcome::PlayerO(_) => Some(ActivePlayer::PlayerX),
+                    // the active player will flip-flop naturally if neither player won
+                    _ => None
+                };
+                (game_outcome == GameOutcome::Incomplete && self.resigned_player.is_none(), next_player)
+            } else if self.game_mode == GameMode::Multiplayer {
+                let outcome = self.multiplayer_game_loop()?;
+                self.first_seat = self.first_seat.next(self.variant_options.players);
+                (!outcome.game_finished(), None)
+            } else {
+                match self.variant_game_loop()? {
+                    // the loser starts, even if they played the other side after swapping
+                    Outcome::Win(winner) => (false, Some(self.player_identity(winner).opposite())),
+                    Outcome::Draw => (false, Some(first_player.opposite())),
+                    Outcome::Incomplete => (true, None)
+                }
+            };
+
+            if quit || self.autoquit_satisfied() || !self.play_again_menu()? {
+                break;
+            } else if let Some(resigned_player) = &self.resigned_player {
+                self.active_player = resigned_player.clone();
+            } else if let Some(next_player) = next_player {
+                self.active_player = next_player;
+            }
+        }
+        Ok(())
+    }
+
+    /// Updates the scores according to the given [GameOutcome] and the game mode
+    fn score_game_outcome(&mut self, game_outcome: &GameOutcome)
+    {
         match game_outcome {
             GameOutcome::PlayerX(_) => {
                 match self.game_mode {
-                    GameMode::Classic => {
-                        self.player_x_score +=1;
-                    },
                     GameMode::Reverse => {
-                        self.player_o_score +=1;
+                        self.add_win(Seat::O);
+                    },
+                    _ => {
+                        self.add_win(Seat::X);
                     }
                 }
                 
             },
             GameOutcome::PlayerO(_) => {
                 match self.game_mode {
-                    GameMode::Classic => {
-                        self.player_o_score +=1;
-                    },
                     GameMode::Reverse => {
-                        self.player_x_score +=1;
+                        self.add_win(Seat::X);
+                    },
+                    _ => {
+                        self.add_win(Seat::O);
                     }
                 }
                 
             },
             GameOutcome::Draw => {
-                self.number_of_draws +=1;
+                self.add_draw();
             }
             GameOutcome::Incomplete => {
                 //do nothing
             }
         }
-
-        Ok(game_outcome)
     }
 
-    /// Writes the game board's state to stdout
+    /// Writes the game board's state to the terminal, at the position and scale of the given
+    /// [BoardGeometry]
     /// 
-    /// If a [WinPosition] is passed, highlights the winning spaces
+    /// If a [WinPosition] is passed, highlights the winning spaces. A win position that
+    /// [wraps around](WinPosition::is_wrapped) the board is also marked by arrows at the
+    /// edges it wraps around. The space of the last move and the `cursor` space (if any) are
+    /// highlighted as well, in the colours of the chosen [Theme](super::theme::Theme).
     /// 
-    /// Causes no change in cursor position, as its position is reset after drawing.
-    pub(crate) fn draw_game(&self, win_position: Option<WinPosition>) -> crossterm::Result<()>
+    /// The geometry is kept so that mouse clicks can be matched to the spaces drawn.
+    /// Leaves the cursor in the top left corner.
+    pub(crate) fn draw_game(
+        &mut self,
+        geometry: BoardGeometry,
+        win_position: Option<WinPosition>,
+        cursor: Option<BoardSpaceLocation>
+    ) -> crossterm::Result<()>
     {   
-        const HORIZ_LINE: &str = "-----------"; 
+        self.board_geometry = geometry;
+        let scale = geometry.scale;
+        let (_, cell_height) = scale.cell_size();
+        let separator = scale.vertical_line().to_string();
 
-        let (cursor_col, cursor_row) = cursor::position()?;
+        for y in 0..3 {
+            let locations = [0, 1, 2].map(|x|{BoardSpaceLocation::from_coordinates((x, y))});
+            let cells = locations.map(|location|{
+                let style = self.space_style(location, win_position, cursor);
+                scale.cell_lines(self.game_board.space(location)).into_iter()
+                    .map(|line|{style.apply(line).to_string()})
+                    .collect::<Vec<_>>()
+            });
+            let (_, top) = geometry.cell_origin(locations[0]);
+            for line in 0..cell_height as usize {
+                let row_text = cells.iter().map(|cell|{cell[line].as_str()}).collect::<Vec<_>>().join(&separator);
+                self.terminal
+                    .queue(MoveTo(geometry.left, top + line as u16))?
+                    .queue(Print(row_text))?;
+            }
+            if y < 2 {
+                self.terminal
+                    .queue(MoveTo(geometry.left, top + cell_height))?
+                    .queue(Print(scale.horizontal_line()))?;
+            }
+        }
 
-        let top_row = format!(" {} | {} | {}",
-            Self::get_styled_space(BoardSpaceLocation::TopLeft, &self.game_board, win_position),
-            Self::get_styled_space(BoardSpaceLocation::TopMiddle, &self.game_board, win_position),
-            Self::get_styled_space(BoardSpaceLocation::TopRight, &self.game_board, win_position)
-        );
-        let middle_row = format!(" {} | {} | {}",
-            Self::get_styled_space(BoardSpaceLocation::MiddleLeft, &self.game_board, win_position),
-            Self::get_styled_space(BoardSpaceLocation::MiddleMiddle, &self.game_board, win_position),
-            Self::get_styled_space(BoardSpaceLocation::MiddleRight, &self.game_board, win_position)
-        );
-        let bottom_row = format!(" {} | {} | {}",
-        Self::get_styled_space(BoardSpaceLocation::BottomLeft, &self.game_board, win_position),
-        Self::get_styled_space(BoardSpaceLocation::BottomMiddle, &self.game_board, win_position),
-        Self::get_styled_space(BoardSpaceLocation::BottomRight, &self.game_board, win_position)
-        );
-        
-        stdout()
-            .queue(Print(top_row))?
-            .queue(MoveToNextLine(1))?
-            .queue(MoveToColumn(cursor_col))?
-            
-            .queue(Print(HORIZ_LINE))?
-            .queue(MoveToNextLine(1))?
-            .queue(MoveToColumn(cursor_col))?
-            
-            .queue(Print(middle_row))?
-            .queue(MoveToNextLine(1))?
-            .queue(MoveToColumn(cursor_col))?
-            
-            .queue(Print(HORIZ_LINE))?
-            .queue(MoveToNextLine(1))?
-            .queue(MoveToColumn(cursor_col))?
-
-            .queue(Print(bottom_row))?
-            .queue(MoveToNextLine(1))?
-            .queue(MoveToColumn(cursor_col))?
-            
-            .queue(MoveToRow(cursor_row))?
-            .queue(MoveToColumn(cursor_col))?;
+        if let Some(win_position) = win_position.filter(WinPosition::is_wrapped) {
+            self.draw_wrap_arrows(geometry, win_position)?;
+        }
+
+        self.terminal
+            .queue(MoveToRow(0))?
+            .queue(MoveToColumn(0))?;
             Ok(())
     }
 
-    /// Returns the char at the given [BoardSpaceLocation], highlighted
-    /// if the location is included in the given [WinPosition]
+    /// Draws arrows beside the rows where the given [WinPosition] leaves the board
+    /// and where it comes back in on the other side
+    fn draw_wrap_arrows(&mut self, geometry: BoardGeometry, win_position: WinPosition) -> crossterm::Result<()>
+    {
+        // the column just inside the left and right edges of the board
+        let (board_width, _) = geometry.board_size();
+        let left_edge = geometry.left;
+        let right_edge = geometry.left + board_width - 1;
+
+        for pair in win_position.spaces().windows(2) {
+            let (from_x, _) = pair[0].as_coordinates();
+            let (to_x, _) = pair[1].as_coordinates();
+            let (arrow, exit_column, entry_column) = match to_x as i8 - from_x as i8 {
+                -2 => ('>', right_edge, left_edge),
+                2 => ('<', left_edge, right_edge),
+                _ => continue
+            };
+            let (_, exit_row) = geometry.cell_centre(pair[0]);
+            let (_, entry_row) = geometry.cell_centre(pair[1]);
+            self.terminal
+                .queue(MoveTo(exit_column, exit_row))?
+                .queue(Print(self.theme.win_line().apply(arrow)))?
+                .queue(MoveTo(entry_column, entry_row))?
+                .queue(Print(self.theme.win_line().apply(arrow)))?;
+        }
+        Ok(())
+    }
+
+    /// Returns the style of the space at the given [BoardSpaceLocation], for the piece in it
+    /// and highlighted if the location is included in the given [WinPosition], is the
+    /// location of the last move, or is the `cursor` location
     /// 
-    /// If `win_position` is [None], all letters will be styled normally
-    fn get_styled_space(
+    /// If `win_position` is [None], no spaces are highlighted as winning
+    fn space_style(
+        &self,
         location: BoardSpaceLocation, 
-        game_board: &GameBoard,
-        win_position: Option<WinPosition>
-    ) -> StyledContent<char>
+        win_position: Option<WinPosition>,
+        cursor: Option<BoardSpaceLocation>
+    ) -> ContentStyle
     {
-        let space_char = game_board.space(location).get_char();
+        let space = self.game_board.space(location);
 
-        if let Some(win_position) = win_position {
-            let win_locations = win_position.as_board_spaces();
-            
-            if win_locations.contains(&location) {
-                space_char.negative()
+        if win_position.is_some_and(|win_position|{win_position.spaces().contains(&location)}) {
+            self.theme.win_line()
+        } else {
+            let mut style = if space == &BoardSpace::Blocked {
+                self.theme.inactive()
             } else {
-                space_char.stylize()
+                self.theme.symbol(space.get_char())
+            };
+            if self.move_history.last() == Some(&location) {
+                style = theme::combine(style, self.theme.last_move());
             }
-        } else {
-            space_char.stylize()
+            if cursor == Some(location) {
+                style = theme::combine(style, self.theme.cursor());
+            }
+            style
         }
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,460 @@
+//! Key bindings, which decide what each key does in menus and games
+//!
+//! Every key press is looked up in the active [KeyBindings] to find the [Action] it performs.
+//! Bindings start from one of the [Preset]s, and can be changed with a key bindings file.
+//! Ctrl+C always quits, whatever the bindings are.
+//!
+//!# Key bindings files
+//!
+//! Key bindings files are plain text. Blank lines and lines starting with `#` are ignored.
+//!
+//! - `preset NAME` replaces every binding with those of the preset `arrows`, `vim` or `numpad`.
+//!   Files that don't start with a preset change the `arrows` preset.
+//!
+//! - `bind KEY ACTION` makes KEY perform ACTION, instead of anything it did before.
+//!
+//! - `unbind KEY` makes KEY do nothing.
+//!
+//! A key is a single character (such as `w` or `+`), or one of `up`, `down`, `left`, `right`,
+//! `enter`, `space`, `tab`, `backtab`, `esc`, `backspace`, `home`, `end`, `pageup` and `pagedown`.
+//! The actions are `up`, `down`, `left`, `right`, `place`, `place-1` to `place-9` (which place
+//! a piece in a space of a classic board, numbered as on a numeric keypad), `next-board`,
+//! `previous-board`, `cancel`, `swap`, `toggle-symbol`, `undo`, `hint`, `yes`, `no` and `quit`.
+//!
+//! ```text
+//! # play with WASD, and space to place
+//! preset arrows
+//! bind w up
+//! bind a left
+//! bind s down
+//! bind d right
+//! bind space place
+//!
+//! # undo with Backspace as well as u
+//! bind backspace undo
+//! ```
+
+use std::{fmt::Display, fs, path::Path};
+
+use crossterm::event::{KeyEvent, KeyCode, KeyModifiers};
+
+use crate::gameboard::BoardSpaceLocation;
+
+/// Something the user can do by pressing a key
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    /// Move the cursor (or the selected menu option) up
+    Up,
+    /// Move the cursor (or the selected menu option) down
+    Down,
+    /// Move the cursor left, or choose the previous value of a menu option
+    Left,
+    /// Move the cursor right, or choose the next value of a menu option
+    Right,
+    /// Place a piece in the selected space, or accept the menu
+    Place,
+    /// Place a piece in the given space of a classic board
+    PlaceAt(BoardSpaceLocation),
+    /// Move the cursor to the next board
+    NextBoard,
+    /// Move the cursor to the previous board
+    PreviousBoard,
+    /// Deselect the selected piece
+    Cancel,
+    /// Swap sides under the swap rule
+    Swap,
+    /// Switch between placing X and O, in modes where either player may place either symbol
+    ToggleSymbol,
+    /// Take back the last move
+    Undo,
+    /// Move the cursor to the move the AI would play
+    Hint,
+    /// Answer yes, such as to playing again
+    Yes,
+    /// Answer no, such as to playing again
+    No,
+    /// Quit, or resign a networked game
+    Quit
+}
+
+impl Action {
+
+    /// Returns the action with the given name in key bindings files, if any
+    fn from_name(name: &str) -> Option<Self>
+    {
+        let action = match name {
+            "up" => Self::Up,
+            "down" => Self::Down,
+            "left" => Self::Left,
+            "right" => Self::Right,
+            "place" => Self::Place,
+            "next-board" => Self::NextBoard,
+            "previous-board" => Self::PreviousBoard,
+            "cancel" => Self::Cancel,
+            "swap" => Self::Swap,
+            "toggle-symbol" => Self::ToggleSymbol,
+            "undo" => Self::Undo,
+            "hint" => Self::Hint,
+            "yes" => Self::Yes,
+            "no" => Self::No,
+            "quit" => Self::Quit,
+            _ => {
+                let number = name.strip_prefix("place-")?.parse().ok()?;
+                Self::PlaceAt(numpad_location(number)?)
+            }
+        };
+        Some(action)
+    }
+}
+
+/// Returns the space of a classic board with the given number on a numeric keypad, from 1
+/// (bottom left) to 9 (top right)
+fn numpad_location(number: u8) -> Option<BoardSpaceLocation>
+{
+    (1..=9).contains(&number)
+        .then(||{BoardSpaceLocation::from_coordinates(((number - 1) % 3, 2 - (number - 1) / 3))})
+}
+
+/// A starting set of key bindings
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Preset {
+    /// The arrow keys move the cursor
+    #[default]
+    Arrows,
+    /// `h`, `j`, `k` and `l` move the cursor, as in vim (the arrow keys still work too)
+    Vim,
+    /// The arrow keys move the cursor, and the number keys 1 to 9 place a piece in a space
+    /// of a classic board directly, laid out like a numeric keypad
+    Numpad
+}
+
+impl Preset {
+
+    /// Returns the preset with the given name in key bindings files, if any
+    fn from_name(name: &str) -> Option<Self>
+    {
+        match name {
+            "arrows" => Some(Self::Arrows),
+            "vim" => Some(Self::Vim),
+            "numpad" => Some(Self::Numpad),
+            _ => None
+        }
+    }
+}
+
+/// The action performed by each key
+///
+/// Several keys can perform the same action. On-screen help names the first key bound to
+/// each action.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyBindings {
+    bindings: Vec<(KeyCode, Action)>
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self
+    {
+        Self::preset(Preset::default())
+    }
+}
+
+impl KeyBindings {
+
+    /// Returns the key bindings of the given [Preset]
+    pub fn preset(preset: Preset) -> Self
+    {
+        let arrows = [
+            (KeyCode::Up, Action::Up),
+            (KeyCode::Down, Action::Down),
+            (KeyCode::Left, Action::Left),
+            (KeyCode::Right, Action::Right)
+        ];
+        let mut bindings = Vec::new();
+        if preset == Preset::Vim {
+            bindings.extend([
+                (KeyCode::Char('h'), Action::Left),
+                (KeyCode::Char('j'), Action::Down),
+                (KeyCode::Char('k'), Action::Up),
+                (KeyCode::Char('l'), Action::Right)
+            ]);
+        }
+        bindings.extend(arrows);
+        if preset == Preset::Numpad {
+            bindings.extend((1..=9).filter_map(|number: u8|{
+                Some((KeyCode::Char(char::from(b'0' + number)), Action::PlaceAt(numpad_location(number)?)))
+            }));
+        }
+        bindings.extend([
+            (KeyCode::Enter, Action::Place),
+            (KeyCode::Tab, Action::NextBoard),
+            (KeyCode::BackTab, Action::PreviousBoard),
+            (KeyCode::Esc, Action::Cancel),
+            (KeyCode::Char('s'), Action::Swap),
+            (KeyCode::Char('t'), Action::ToggleSymbol),
+            (KeyCode::Char('u'), Action::Undo),
+            (KeyCode::Char('?'), Action::Hint),
+            (KeyCode::Char('y'), Action::Yes),
+            (KeyCode::Char('n'), Action::No),
+            (KeyCode::Char('q'), Action::Quit)
+        ]);
+        Self{bindings}
+    }
+
+    /// Reads key bindings from the file at the given path
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, KeyBindingsError>
+    {
+        let contents = fs::read_to_string(path).map_err(|error|{KeyBindingsError::Io(error.to_string())})?;
+        Self::parse(&contents)
+    }
+
+    /// Parses key bindings in the key bindings file format described in the
+    /// [module documentation](self)
+    pub fn parse(text: &str) -> Result<Self, KeyBindingsError>
+    {
+        let mut key_bindings = Self::default();
+        let lines = text.lines().enumerate()
+            .map(|(index, line)|{(index + 1, line.trim())})
+            .filter(|(_, line)|{!line.is_empty() && !line.starts_with('#')});
+
+        for (line_number, line) in lines {
+            let syntax_error = |message: String|{KeyBindingsError::Syntax(line_number, message)};
+            let words: Vec<&str> = line.split_whitespace().collect();
+            match words[..] {
+                ["preset", name] => {
+                    let preset = Preset::from_name(name)
+                        .ok_or_else(||{syntax_error(format!("unknown preset '{}'", name))})?;
+                    key_bindings = Self::preset(preset);
+                },
+                ["bind", key, action] => {
+                    let key = key_from_name(key).ok_or_else(||{syntax_error(format!("unknown key '{}'", key))})?;
+                    let action = Action::from_name(action)
+                        .ok_or_else(||{syntax_error(format!("unknown action '{}'", action))})?;
+                    key_bindings.unbind(key);
+                    key_bindings.bindings.push((key, action));
+                },
+                ["unbind", key] => {
+                    let key = key_from_name(key).ok_or_else(||{syntax_error(format!("unknown key '{}'", key))})?;
+                    key_bindings.unbind(key);
+                },
+                ["preset" | "bind" | "unbind", ..] => {
+                    return Err(syntax_error(format!("wrong number of arguments to '{}'", words[0])));
+                },
+                _ => return Err(syntax_error(format!("unknown setting '{}'", words[0])))
+            }
+        }
+
+        Ok(key_bindings)
+    }
+
+    /// Removes any binding of the given key
+    fn unbind(&mut self, key: KeyCode)
+    {
+        self.bindings.retain(|(bound_key, _)|{*bound_key != key});
+    }
+
+    /// Returns the action performed by the given key press, if any
+    ///
+    /// Key presses with Ctrl or Alt held never perform an action.
+    pub fn action(&self, key_event: &KeyEvent) -> Option<Action>
+    {
+        if key_event.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) {
+            return None;
+        }
+        self.bindings.iter()
+            .find(|(key, _)|{*key == key_event.code})
+            .map(|(_, action)|{*action})
+    }
+
+    /// Returns the name of the first key bound to the given action, for on-screen help
+    ///
+    /// Returns `"(unbound)"` if no key performs the action.
+    pub fn key_name(&self, action: Action) -> String
+    {
+        self.bindings.iter()
+            .find(|(_, bound_action)|{*bound_action == action})
+            .map_or_else(||{"(unbound)".to_owned()}, |(key, _)|{key_display_name(*key)})
+    }
+
+    /// Returns true if any key performs the given action
+    pub fn is_bound(&self, action: Action) -> bool
+    {
+        self.bindings.iter().any(|(_, bound_action)|{*bound_action == action})
+    }
+
+    /// Returns a description of the keys that move the cursor, for on-screen help,
+    /// such as `"arrow keys"` or `"h/j/k/l"`
+    pub fn movement_keys(&self) -> String
+    {
+        let keys = [Action::Left, Action::Down, Action::Up, Action::Right].map(|action|{self.key_name(action)});
+        if keys == ["Left", "Down", "Up", "Right"] {
+            "arrow keys".to_owned()
+        } else {
+            keys.join("/")
+        }
+    }
+
+    /// Returns a description of the keys that place a piece in a space directly, for on-screen
+    /// help, such as `"1-9"`
+    ///
+    /// Returns `None` unless every space has such a key.
+    pub fn direct_placement_keys(&self) -> Option<String>
+    {
+        let keys: Option<Vec<String>> = (1..=9)
+            .map(|number|{
+                let action = Action::PlaceAt(numpad_location(number)?);
+                self.is_bound(action).then(||{self.key_name(action)})
+            })
+            .collect();
+        let keys = keys?;
+        if keys.iter().enumerate().all(|(index, key)|{*key == (index + 1).to_string()}) {
+            Some("1-9".to_owned())
+        } else {
+            Some(keys.join(""))
+        }
+    }
+}
+
+/// Returns the key with the given name in key bindings files, if any
+fn key_from_name(name: &str) -> Option<KeyCode>
+{
+    let mut characters = name.chars();
+    if let (Some(character), None) = (characters.next(), characters.next()) {
+        return Some(KeyCode::Char(character));
+    }
+    let key = match name.to_lowercase().as_str() {
+        "up" => KeyCode::Up,
+        "down" => KeyCode::Down,
+        "left" => KeyCode::Left,
+        "right" => KeyCode::Right,
+        "enter" => KeyCode::Enter,
+        "space" => KeyCode::Char(' '),
+        "tab" => KeyCode::Tab,
+        "backtab" => KeyCode::BackTab,
+        "esc" => KeyCode::Esc,
+        "backspace" => KeyCode::Backspace,
+        "home" => KeyCode::Home,
+        "end" => KeyCode::End,
+        "pageup" => KeyCode::PageUp,
+        "pagedown" => KeyCode::PageDown,
+        _ => return None
+    };
+    Some(key)
+}
+
+/// Returns the name of the given key as shown in on-screen help
+fn key_display_name(key: KeyCode) -> String
+{
+    match key {
+        KeyCode::Char(' ') => "Space".to_owned(),
+        KeyCode::Char(character) => character.to_string(),
+        KeyCode::BackTab => "Shift+Tab".to_owned(),
+        KeyCode::PageUp => "PageUp".to_owned(),
+        KeyCode::PageDown => "PageDown".to_owned(),
+        other => format!("{:?}", other)
+    }
+}
+
+/// Reasons why key bindings could not be loaded
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeyBindingsError {
+    /// The key bindings file couldn't be read
+    Io(String),
+    /// The given line of the key bindings file is invalid
+    Syntax(usize, String)
+}
+
+impl Display for KeyBindingsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "{}", error),
+            Self::Syntax(line, message) => write!(f, "line {}: {}", line, message)
+        }
+    }
+}
+
+impl std::error::Error for KeyBindingsError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns the action performed by pressing the given key without modifiers
+    fn action(key_bindings: &KeyBindings, key: KeyCode) -> Option<Action>
+    {
+        key_bindings.action(&KeyEvent::new(key, KeyModifiers::NONE))
+    }
+
+    #[test]
+    fn empty_file_gives_the_default_bindings()
+    {
+        assert_eq!(KeyBindings::parse(""), Ok(KeyBindings::default()));
+        assert_eq!(KeyBindings::parse("# nothing here\n\n"), Ok(KeyBindings::default()));
+    }
+
+    #[test]
+    fn preset_replaces_every_binding()
+    {
+        let key_bindings = KeyBindings::parse("unbind q\npreset vim").expect("bindings should be valid");
+        assert_eq!(key_bindings, KeyBindings::preset(Preset::Vim));
+        assert_eq!(action(&key_bindings, KeyCode::Char('q')), Some(Action::Quit));
+        assert_eq!(key_bindings.movement_keys(), "h/j/k/l");
+    }
+
+    #[test]
+    fn bind_replaces_what_the_key_did()
+    {
+        let key_bindings = KeyBindings::parse("bind w up\nbind space place\nbind q toggle-symbol")
+            .expect("bindings should be valid");
+        assert_eq!(action(&key_bindings, KeyCode::Char('w')), Some(Action::Up));
+        assert_eq!(action(&key_bindings, KeyCode::Char(' ')), Some(Action::Place));
+        assert_eq!(action(&key_bindings, KeyCode::Char('q')), Some(Action::ToggleSymbol));
+        assert!(!key_bindings.is_bound(Action::Quit));
+        assert_eq!(key_bindings.key_name(Action::Quit), "(unbound)");
+        // keys bound earlier are still named first in help
+        assert_eq!(key_bindings.key_name(Action::Up), "Up");
+    }
+
+    #[test]
+    fn unbind_removes_the_binding()
+    {
+        let key_bindings = KeyBindings::parse("unbind Enter").expect("bindings should be valid");
+        assert_eq!(action(&key_bindings, KeyCode::Enter), None);
+        assert!(!key_bindings.is_bound(Action::Place));
+    }
+
+    #[test]
+    fn place_actions_are_numbered_like_a_keypad()
+    {
+        let key_bindings = KeyBindings::parse("bind 7 place-7\nbind 3 place-3").expect("bindings should be valid");
+        assert_eq!(action(&key_bindings, KeyCode::Char('7')), Some(Action::PlaceAt(BoardSpaceLocation::TopLeft)));
+        assert_eq!(action(&key_bindings, KeyCode::Char('3')), Some(Action::PlaceAt(BoardSpaceLocation::BottomRight)));
+        assert_eq!(key_bindings.direct_placement_keys(), None);
+
+        let key_bindings = KeyBindings::parse("preset numpad").expect("bindings should be valid");
+        assert_eq!(key_bindings.direct_placement_keys().as_deref(), Some("1-9"));
+    }
+
+    #[test]
+    fn modifiers_stop_keys_performing_actions()
+    {
+        let key_bindings = KeyBindings::default();
+        assert_eq!(key_bindings.action(&KeyEvent::new(KeyCode::Char('q'), KeyModifiers::CONTROL)), None);
+        assert_eq!(key_bindings.action(&KeyEvent::new(KeyCode::Char('q'), KeyModifiers::ALT)), None);
+    }
+
+    #[test]
+    fn invalid_bindings_give_the_line_number()
+    {
+        let syntax = |line: usize, message: &str|{Err(KeyBindingsError::Syntax(line, message.to_owned()))};
+
+        assert_eq!(KeyBindings::parse("preset emacs"), syntax(1, "unknown preset 'emacs'"));
+        assert_eq!(KeyBindings::parse("# comment\n\nbind f1 quit"), syntax(3, "unknown key 'f1'"));
+        assert_eq!(KeyBindings::parse("bind x jump"), syntax(1, "unknown action 'jump'"));
+        assert_eq!(KeyBindings::parse("bind 0 place-0"), syntax(1, "unknown action 'place-0'"));
+        assert_eq!(KeyBindings::parse("unbind insert"), syntax(1, "unknown key 'insert'"));
+        assert_eq!(KeyBindings::parse("bind x"), syntax(1, "wrong number of arguments to 'bind'"));
+        assert_eq!(KeyBindings::parse("unbind x y"), syntax(1, "wrong number of arguments to 'unbind'"));
+        assert_eq!(KeyBindings::parse("preset"), syntax(1, "wrong number of arguments to 'preset'"));
+        assert_eq!(KeyBindings::parse("bind x up\nremap x up"), syntax(2, "unknown setting 'remap'"));
+    }
+}
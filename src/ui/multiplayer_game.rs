@@ -0,0 +1,244 @@
+//! UI implementations for playing games with more than two players
+//!
+//! The board is described by [MultiplayerGame::boards], so it is drawn the same way as a
+//! [Variant](crate::variants::Variant) board, with each player's pieces in their own colour.
+
+use std::{
+    io::{self, Write},
+    sync::mpsc,
+    thread
+};
+
+use crossterm::{
+    terminal::{Clear, ClearType},
+    style::{Print, PrintStyledContent},
+    cursor::{self, MoveTo},
+    event::{Event, KeyEvent, KeyCode, KeyModifiers, MouseEvent, MouseEventKind, MouseButton},
+    QueueableCommand,
+    ExecutableCommand
+};
+
+use crate::{
+    player_type::PlayerType,
+    ai::{AiPlayer, AiError},
+    multiplayer::{MultiplayerGame, MultiplayerOutcome},
+    variants::Layout
+};
+
+use super::key_bindings::Action;
+
+impl super::UI {
+
+    /// The game loop for games with more than two players
+    ///
+    /// Starts a new game with the player in the first seat to move first, then lets each player
+    /// move in turn until either the game is finished or the user quits the game.
+    pub fn multiplayer_game_loop(&mut self) -> crossterm::Result<MultiplayerOutcome>
+    {
+        // don't start (or clear the board for) a new game if the user has already quit
+        if self.exit_flag {
+            return Ok(self.multiplayer.as_ref().map_or(MultiplayerOutcome::Incomplete, |game|{game.outcome()}));
+        }
+
+        (self.terminal_x_size, self.terminal_y_size) = self.terminal.size()?;
+
+        let options = self.variant_options;
+        let game = MultiplayerGame::new(
+            options.players,
+            self.first_seat,
+            options.board_columns,
+            options.board_rows,
+            options.line_length
+        );
+        self.variant_cursor = Self::centre_cell(&game.boards());
+        self.multiplayer = Some(game);
+        self.variant_last_move = None;
+
+        self.terminal.execute(Clear(ClearType::All))?;
+
+        let mut outcome = self.multiplayer_game().outcome();
+        while !(outcome.game_finished() || self.exit_flag) {
+            self.draw_multiplayer_game()?;
+
+            match self.seat_player_type(self.multiplayer_game().active_seat()) {
+                // only classic games are played over the network
+                PlayerType::Remote => {
+                    return Err(io::Error::new(io::ErrorKind::Unsupported, "games for more than two players can't be played over the network"));
+                },
+                PlayerType::Human => {
+                    let event = self.terminal.read_event()?;
+                    self.handle_multiplayer_event(event)?;
+                },
+                PlayerType::AI(ai_player) => {
+                    let difficulty = ai_player.difficulty();
+                    if let Some(cell) = self.wait_for_multiplayer_ai_move(difficulty)? {
+                        self.play_multiplayer_move(cell);
+                    }
+                }
+            }
+
+            outcome = self.multiplayer_game().outcome();
+        }
+
+        match outcome {
+            MultiplayerOutcome::Win(winner) => self.add_win(winner),
+            MultiplayerOutcome::Draw => self.add_draw(),
+            MultiplayerOutcome::Incomplete => {}
+        }
+
+        Ok(outcome)
+    }
+
+    /// Returns the game with more than two players currently being played
+    ///
+    ///# Panics
+    ///
+    /// This method panics if no such game has been started
+    fn multiplayer_game(&self) -> &MultiplayerGame
+    {
+        self.multiplayer.as_ref().expect("no multiplayer game has been started")
+    }
+
+    /// Claims the given cell for the active player, if it is empty
+    fn play_multiplayer_move(&mut self, cell: usize)
+    {
+        if let Some(game) = self.multiplayer.as_mut() {
+            if game.legal_moves().contains(&cell) {
+                game.play_move(cell);
+                self.variant_last_move = Some(cell);
+            }
+        }
+    }
+
+    /// Chooses a move for the active AI player on another thread, handling input while waiting
+    ///
+    /// Returns `None` if the user quit before a move was chosen.
+    fn wait_for_multiplayer_ai_move(&mut self, difficulty: f64) -> crossterm::Result<Option<usize>>
+    {
+        let game = self.multiplayer_game().clone();
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move ||{
+            // the receiver is gone if the user quit while the move was being chosen
+            let _ = sender.send(AiPlayer::new(difficulty).choose_multiplayer_move(&game));
+        });
+
+        loop {
+            match receiver.try_recv() {
+                Ok(Ok(cell)) => return Ok(Some(cell)),
+                Ok(Err(AiError::NoMovesFound)) => panic!("No moves found despite game not being finished"),
+                Ok(Err(AiError::GameFinished)) | Err(mpsc::TryRecvError::Disconnected) => return Ok(None),
+                Err(mpsc::TryRecvError::Empty) => {}
+            }
+
+            if self.terminal.poll_event(Self::AI_POLL_INTERVAL)? {
+                let event = self.terminal.read_event()?;
+                self.handle_multiplayer_event(event)?;
+                if self.exit_flag {
+                    return Ok(None);
+                }
+                self.draw_multiplayer_game()?;
+            }
+        }
+    }
+
+    /// Handles the given [Event] during a game with more than two players
+    fn handle_multiplayer_event(&mut self, event: Event) -> crossterm::Result<()>
+    {
+        let game = self.multiplayer_game();
+        let human_turn = self.seat_player_type(game.active_seat()) == &PlayerType::Human;
+        let (columns, cells) = (game.columns(), game.columns() * game.rows());
+        let cursor = self.variant_cursor;
+        match event {
+            Event::Key(KeyEvent{code:KeyCode::Char('c'), modifiers:KeyModifiers::CONTROL, ..}) => {
+                self.exit_flag = true;
+            },
+            Event::Key(key_event) => {
+                match self.key_bindings.action(&key_event) {
+                    Some(Action::Right) if human_turn && cursor % columns < columns - 1 => {
+                        self.variant_cursor += 1;
+                    },
+                    Some(Action::Left) if human_turn && !cursor.is_multiple_of(columns) => {
+                        self.variant_cursor -= 1;
+                    },
+                    Some(Action::Down) if human_turn && cursor + columns < cells => {
+                        self.variant_cursor += columns;
+                    },
+                    Some(Action::Up) if human_turn && cursor >= columns => {
+                        self.variant_cursor -= columns;
+                    },
+                    Some(Action::Place) if human_turn => {
+                        self.play_multiplayer_move(cursor);
+                    },
+                    Some(Action::Quit) => {
+                        self.exit_flag = true;
+                    },
+                    _ => {
+                        //ignore other KeyEvents
+                    }
+                }
+            },
+            //clicking a cell claims it, as if it was selected and Enter was pressed
+            Event::Mouse(MouseEvent{kind: MouseEventKind::Down(MouseButton::Left), column, row, ..}) if human_turn => {
+                let boards = game.boards();
+                if let Some(cell) = Layout::new(&boards).cell_at(&boards, (column, row)) {
+                    self.variant_cursor = cell;
+                    self.play_multiplayer_move(cell);
+                }
+            },
+            //ignore size returned by resize event as it is currently (as of crossterm 0.25) wrong on Windows
+            Event::Resize(_,_) => {
+                (self.terminal_x_size, self.terminal_y_size) = self.terminal.size()?;
+                self.terminal.execute(Clear(ClearType::All))?;
+            },
+            _ => {
+                //ignore other Events
+            }
+        }
+        Ok(())
+    }
+
+    /// Draws the game with the turn and help text below it, and positions the cursor
+    /// in the selected cell if a local human player is choosing a move
+    fn draw_multiplayer_game(&mut self) -> crossterm::Result<()>
+    {
+        let game = self.multiplayer_game();
+        let boards = game.boards();
+        let layout = Layout::new(&boards);
+        let seat = game.active_seat();
+
+        self.terminal.queue(cursor::Hide)?;
+
+        if self.terminal_x_size < layout.width || self.terminal_y_size < layout.height + 3 {
+            // print error message instead of game board if terminal is too small
+            self.terminal
+                .queue(MoveTo(0, 0))?
+                .queue(Clear(ClearType::CurrentLine))?
+                .queue(Print(format!("Terminal too small ({} x {})! Please enlarge terminal", self.terminal_x_size, self.terminal_y_size)))?
+                .flush()?;
+            return Ok(());
+        }
+
+        let human_turn = self.seat_player_type(seat) == &PlayerType::Human;
+        self.draw_variant_boards(&boards, &layout, human_turn.then_some(self.variant_cursor))?;
+        self.draw_status_panel_beside(&layout, &format!("{}'s turn.", seat.get_char()))?;
+
+        let help_text = self.selection_help("space", None, "place", "quit");
+        self.terminal
+            .queue(MoveTo(0, layout.height + 1))?
+            .queue(Clear(ClearType::CurrentLine))?
+            .queue(PrintStyledContent(self.theme.seat(seat).apply(seat.get_char())))?
+            .queue(Print("'s turn"))?
+            .queue(MoveTo(0, layout.height + 2))?
+            .queue(Clear(ClearType::CurrentLine))?
+            .queue(Print(help_text))?;
+
+        if human_turn {
+            let (x, y) = layout.cell_position(&boards[0], self.variant_cursor);
+            self.terminal
+                .queue(MoveTo(x, y))?
+                .queue(cursor::Show)?;
+        }
+        self.terminal.flush()?;
+        Ok(())
+    }
+}
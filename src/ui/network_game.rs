@@ -0,0 +1,280 @@
+//! UI implementations for games played over the network
+
+use std::{
+    io::{Write, ErrorKind},
+    net::TcpListener
+};
+
+use crossterm::{
+    style::Print,
+    cursor::{self, MoveToColumn, MoveToRow},
+    terminal::{Clear, ClearType},
+    event::{Event, KeyEvent, KeyCode, KeyModifiers},
+    QueueableCommand
+};
+
+use crate::{
+    gameboard::BoardSpace,
+    player_type::PlayerType,
+    active_player::ActivePlayer,
+    network::{
+        Connection,
+        NetworkError,
+        protocol::{Message, NetworkSettings},
+        spectators::SpectatorHub
+    }
+};
+
+use super::{setup_menu::SetupMenu, key_bindings::{KeyBindings, Action}};
+
+impl super::UI {
+
+    /// The pre-game menu for a networked game, followed by waiting for an opponent
+    ///
+    /// Works like [UI::setup_menu], except one of the players may (and must) be set to
+    /// [PlayerType::Remote]. Once settings are chosen, listens on the given port until
+    /// another `tactic` instance joins the game, then sends it the chosen settings.
+    ///
+    /// If the user exits the menu or stops waiting, no connection is made and
+    /// [UI::game_loop] will return immediately.
+    pub fn host_game(&mut self, port: u16) -> crossterm::Result<()>
+    {
+        let mut setup_menu = SetupMenu::new(true, self.theme);
+
+        if !setup_menu.setup_menu_loop(self.terminal.as_mut(), &self.key_bindings)? {
+            self.exit_flag = true;
+            return Ok(());
+        }
+
+        setup_menu.apply_settings(self);
+
+        let listener = TcpListener::bind(("0.0.0.0", port))?;
+        listener.set_nonblocking(true)?;
+
+        self.draw_network_status(&format!(
+            "Waiting for an opponent to connect on port {}. Press {} to cancel",
+            port, self.key_bindings.key_name(Action::Quit)))?;
+
+        let stream = loop {
+            match listener.accept() {
+                Ok((stream, _)) => break stream,
+                Err(error) if error.kind() == ErrorKind::WouldBlock => {
+                    if self.terminal.poll_event(Self::NETWORK_POLL_INTERVAL)? &&
+                        Self::is_quit_event(&self.key_bindings, &self.terminal.read_event()?) {
+                        self.exit_flag = true;
+                        return Ok(());
+                    }
+                },
+                Err(error) => return Err(error)
+            }
+        };
+        stream.set_nonblocking(false)?;
+
+        let connection = Connection::new(stream)?;
+        let joiner_side = if self.player_x == PlayerType::Remote {
+            ActivePlayer::PlayerX
+        } else {
+            ActivePlayer::PlayerO
+        };
+        let settings = NetworkSettings{
+            game_mode: self.game_mode,
+            autoquit_mode: self.game_autoquit_mode,
+            autoquit_value: self.game_autoquit_value,
+            joiner_side
+        };
+        connection.host_handshake(&settings)?;
+
+        self.remote = Some(connection);
+
+        // keep listening so that others can watch the game
+        self.spectators = Some(SpectatorHub::start(listener, settings)?);
+        Ok(())
+    }
+
+    /// Connects to a game hosted by another `tactic` instance
+    ///
+    /// Replaces the setup menu for the joining player; the game settings
+    /// are chosen by the host.
+    pub fn join_game(&mut self, address: &str) -> crossterm::Result<()>
+    {
+        self.draw_network_status(&format!("Connecting to {}...", address))?;
+
+        let connection = Connection::connect(address)?;
+        let settings = connection.join_handshake()?;
+
+        (self.player_x, self.player_o) = match settings.joiner_side {
+            ActivePlayer::PlayerX => (PlayerType::Human, PlayerType::Remote),
+            ActivePlayer::PlayerO => (PlayerType::Remote, PlayerType::Human)
+        };
+        self.game_mode = settings.game_mode;
+        self.game_autoquit_mode = settings.autoquit_mode;
+        self.game_autoquit_value = settings.autoquit_value;
+
+        self.remote = Some(connection);
+        Ok(())
+    }
+
+    /// Returns the player that resigned the most recent game, if any
+    pub fn resigned_player(&self) -> Option<&ActivePlayer>
+    {
+        self.resigned_player.as_ref()
+    }
+
+    /// Sends a message to the remote player, if playing a networked game
+    pub(super) fn send_to_remote(&self, message: &Message) -> crossterm::Result<()>
+    {
+        if let Some(remote) = &self.remote {
+            remote.send(message)?;
+        }
+        Ok(())
+    }
+
+    /// Sends a message to any spectators watching a hosted game
+    pub(super) fn broadcast_to_spectators(&self, message: &Message)
+    {
+        if let Some(spectators) = &self.spectators {
+            spectators.broadcast(message);
+        }
+    }
+
+    /// Forfeits the current game on behalf of the local player and notifies the remote player
+    pub(super) fn resign(&mut self) -> crossterm::Result<()>
+    {
+        let local_player = if self.player_x == PlayerType::Remote {
+            ActivePlayer::PlayerO
+        } else {
+            ActivePlayer::PlayerX
+        };
+        self.send_to_remote(&Message::Resign)?;
+        self.broadcast_to_spectators(&Message::Resigned(local_player.clone()));
+        self.resigned_player = Some(local_player);
+        Ok(())
+    }
+
+    /// Blocks until either a [crossterm::event::Event] or a message from the remote
+    /// player is available, then handles it
+    pub(super) fn handle_next_event_or_message(&mut self) -> crossterm::Result<()>
+    {
+        loop {
+            let message = match &self.remote {
+                Some(remote) => remote.try_recv(),
+                None => return Ok(())
+            };
+
+            match message {
+                Ok(Some(message)) => return self.handle_remote_message(message),
+                Ok(None) => {},
+                Err(NetworkError::Disconnected) => {
+                    self.remote = None;
+                    self.exit_flag = true;
+                    return Ok(());
+                },
+                Err(error) => return Err(error.into())
+            }
+
+            if self.terminal.poll_event(Self::NETWORK_POLL_INTERVAL)? {
+                let event = self.terminal.read_event()?;
+                return self.handle_event(event);
+            }
+        }
+    }
+
+    /// Asks the remote player whether to play again, given the local player's answer
+    ///
+    /// Returns `true` only if both players want to play again.
+    /// If no game is being played over the network, the local player's answer is returned.
+    pub(super) fn negotiate_rematch(&mut self, play_again: bool) -> crossterm::Result<bool>
+    {
+        let remote = match &self.remote {
+            Some(remote) => remote,
+            None => return Ok(play_again)
+        };
+
+        // ignore send failure; a closed connection means no rematch either way
+        if remote.send(&Message::Rematch(play_again)).is_err() || !play_again {
+            return Ok(false);
+        }
+
+        self.terminal
+            .queue(MoveToColumn(0))?
+            .queue(Clear(ClearType::CurrentLine))?
+            .queue(Print(format!("Waiting for opponent... Press {} to leave", self.key_bindings.key_name(Action::Quit))))?
+            .flush()?;
+
+        loop {
+            match remote.recv_timeout(Self::NETWORK_POLL_INTERVAL) {
+                Ok(Some(Message::Rematch(answer))) => return Ok(answer),
+                Ok(Some(Message::Quit)) | Err(NetworkError::Disconnected) => return Ok(false),
+                Ok(Some(other)) => return Err(NetworkError::UnexpectedMessage(other).into()),
+                Ok(None) => {},
+                Err(error) => return Err(error.into())
+            }
+
+            if self.terminal.poll_event(Self::NETWORK_POLL_INTERVAL)? &&
+                Self::is_quit_event(&self.key_bindings, &self.terminal.read_event()?) {
+                // ignore send failure as we are leaving anyway
+                let _ = remote.send(&Message::Quit);
+                return Ok(false);
+            }
+        }
+    }
+
+    /// Handles a message received from the remote player during a game
+    fn handle_remote_message(&mut self, message: Message) -> crossterm::Result<()>
+    {
+        match message {
+            Message::Move(location)
+                if self.active_player_type() == &PlayerType::Remote &&
+                    self.game_board.space(location) == &BoardSpace::Empty => {
+                self.place_piece(location, false)?;
+            },
+            Message::Resign => {
+                let remote_player = if self.player_x == PlayerType::Remote {
+                    ActivePlayer::PlayerX
+                } else {
+                    ActivePlayer::PlayerO
+                };
+                self.broadcast_to_spectators(&Message::Resigned(remote_player.clone()));
+                self.resigned_player = Some(remote_player);
+            },
+            Message::Quit | Message::Reject(_) => {
+                self.remote = None;
+                self.exit_flag = true;
+            },
+            Message::Move(_) => {
+                // a move out of turn or onto an occupied space means the two sides disagree
+                // about the game, so it can't continue
+                if let Some(remote) = self.remote.take() {
+                    remote.reject("illegal move");
+                }
+                self.exit_flag = true;
+            },
+            other => return Err(NetworkError::UnexpectedMessage(other).into())
+        }
+        Ok(())
+    }
+
+    /// Clears the screen and prints a single status line
+    pub(super) fn draw_network_status(&mut self, status: &str) -> crossterm::Result<()>
+    {
+        self.terminal
+            .queue(Clear(ClearType::All))?
+            .queue(cursor::Hide)?
+            .queue(MoveToColumn(0))?
+            .queue(MoveToRow(0))?
+            .queue(Print(status))?
+            .flush()?;
+        Ok(())
+    }
+
+    /// Returns true if the given event is a request to quit (a key bound to [Action::Quit] in
+    /// the given [KeyBindings], or Ctrl+C)
+    pub(super) fn is_quit_event(key_bindings: &KeyBindings, event: &Event) -> bool
+    {
+        match event {
+            Event::Key(KeyEvent{code: KeyCode::Char('c'), modifiers: KeyModifiers::CONTROL, ..}) => true,
+            Event::Key(key_event) => key_bindings.action(key_event) == Some(Action::Quit),
+            _ => false
+        }
+    }
+}
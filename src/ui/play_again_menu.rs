@@ -1,18 +1,36 @@
 //! UI implementations for the post-game menu
 
-use std::io::{stdout, Write};
+use std::io::Write;
 use crossterm::{
     style::Print,
     cursor::{self, MoveToColumn, MoveToRow, MoveToNextLine},
     terminal::{Clear, ClearType},
-    event::{self, Event, KeyEvent, KeyCode, KeyModifiers},
+    event::{Event, KeyEvent, KeyCode, KeyModifiers, MouseEvent, MouseEventKind, MouseButton},
     QueueableCommand,
     ExecutableCommand
 };
 
-use crate::{game_outcome::GameOutcome, game_settings::GameMode};
+use crate::{
+    game_outcome::{GameOutcome, WinPosition},
+    game_settings::GameMode,
+    active_player::ActivePlayer,
+    variants::{Outcome, Layout},
+    multiplayer::MultiplayerOutcome
+};
+
+use super::{key_bindings::Action, board_geometry::BoardGeometry};
 
 impl super::UI{
+
+    /// The buttons of the post-game menu, and whether each one chooses to play again
+    const PLAY_AGAIN_BUTTONS: [(&'static str, bool); 2] = [("[ Yes ]", true), ("[ No ]", false)];
+    /// Columns between the buttons of the post-game menu
+    const PLAY_AGAIN_BUTTON_GAP: u16 = 3;
+    /// Lines left below the game summary, for the play again prompt and buttons
+    /// or the spectator's help text
+    const SUMMARY_ROWS_BELOW: u16 = 3;
+    /// The width of the widest line usually drawn in or below the game summary
+    const SUMMARY_TEXT_WIDTH: usize = 60;
     
     /// The post-game menu 
     /// 
@@ -20,28 +38,22 @@ impl super::UI{
     /// and choose whether to play another game.
     /// 
     /// Returns `true` if user chooses to play another game, `false` otherwise
-    pub fn play_again_menu(&self) -> crossterm::Result<bool>
+    pub fn play_again_menu(&mut self) -> crossterm::Result<bool>
     {
-        self.draw_play_again_menu()?;
+        let (mut buttons_row, mut buttons_left) = self.draw_play_again_menu()?;
 
         //loop until a valid event is read
         let play_again = loop {
-            match event::read()?{
+            match self.terminal.read_event()?{
+                Event::Key(KeyEvent{code:KeyCode::Char('c'), modifiers: KeyModifiers::CONTROL, ..}) => {
+                    break false;
+                },
                 Event::Key(key_event) => {
-                    match key_event {
-                        KeyEvent{code:KeyCode::Char('y'), ..} => {
-                            break true;
-                        },
-                        KeyEvent{code:KeyCode::Enter, ..} => {
+                    match self.key_bindings.action(&key_event) {
+                        Some(Action::Yes | Action::Place) => {
                             break true;
-                        }
-                        KeyEvent{code:KeyCode::Char('n'), ..} => {
-                            break false;
-                        },
-                        KeyEvent{code:KeyCode::Char('q'), ..} => {
-                            break false;
                         },
-                        KeyEvent{code:KeyCode::Char('c'), modifiers: KeyModifiers::CONTROL, ..} => {
+                        Some(Action::No | Action::Quit) => {
                             break false;
                         },
                         _ => {
@@ -49,9 +61,15 @@ impl super::UI{
                         }
                     }
                 },
+                Event::Mouse(MouseEvent{kind: MouseEventKind::Down(MouseButton::Left), column, row, ..})
+                    if row == buttons_row && column >= buttons_left => {
+                    if let Some(play_again) = Self::play_again_button_at(column - buttons_left) {
+                        break play_again;
+                    }
+                },
                 //redraw screen upon resize
                 Event::Resize(_, _) => {
-                    self.draw_play_again_menu()?;
+                    (buttons_row, buttons_left) = self.draw_play_again_menu()?;
                 },
                 _ => {
                     //ignore other type of event
@@ -59,75 +77,192 @@ impl super::UI{
             }
         };
 
-        stdout().execute(cursor::Show)?;
+        //in a networked game, both players must agree to play again
+        let play_again = if self.remote.is_some() {
+            self.negotiate_rematch(play_again)?
+        } else {
+            play_again
+        };
+
+        self.terminal.execute(cursor::Show)?;
         Ok(play_again)
     }
 
+    /// Returns whether the button drawn at the given column of the button row (counted from
+    /// the first button) chooses to play again, or `None` if there is no button there
+    fn play_again_button_at(column: u16) -> Option<bool>
+    {
+        let mut start = 0;
+        for (text, play_again) in Self::PLAY_AGAIN_BUTTONS {
+            let end = start + text.chars().count() as u16;
+            if (start..end).contains(&column) {
+                return Some(play_again);
+            }
+            start = end + Self::PLAY_AGAIN_BUTTON_GAP;
+        }
+        None
+    }
+
     /// Draws the play again menu
-    fn draw_play_again_menu(&self) -> crossterm::Result<()>
+    ///
+    /// Returns the row the buttons are drawn on and the column the first one starts at
+    fn draw_play_again_menu(&mut self) -> crossterm::Result<(u16, u16)>
     {
-        stdout()
+        //ignore size returned by resize event as it is currently (as of crossterm 0.25) wrong on Windows
+        (self.terminal_x_size, self.terminal_y_size) = self.terminal.size()?;
+        self.terminal
             .queue(Clear(ClearType::All))?
             .queue(cursor::Hide)?
             .queue(MoveToColumn(0))?
             .queue(MoveToRow(0))?
             .flush()?;
-        
-        let game_outcome_text = match self.game_board.game_outcome(){
-            GameOutcome::PlayerX(win_position) => {
-                self.draw_game(Some(win_position))?;
-                match self.game_mode {
-                    GameMode::Classic => "Player X wins!",
-                    GameMode::Reverse => "Player O wins!"
-                }
-            },
-            GameOutcome::PlayerO(win_position) => {
-                self.draw_game(Some(win_position))?;
-                match self.game_mode {
-                    GameMode::Classic => "Player O wins!",
-                    GameMode::Reverse => "Player X wins!"
-                }
-            },
-            GameOutcome::Draw => {
-                self.draw_game(None)?;
-                "Draw!"
-            },
-            GameOutcome::Incomplete => {
-                self.draw_game(None)?;
-                "Game finished early!"
+
+        let (summary_end, summary_left) = self.draw_game_summary("Game finished early!")?;
+
+        let buttons = Self::PLAY_AGAIN_BUTTONS.map(|(text, _)|{text})
+            .join(&" ".repeat(Self::PLAY_AGAIN_BUTTON_GAP as usize));
+        self.terminal
+            .queue(MoveToNextLine(2))?
+            .queue(MoveToColumn(summary_left))?
+            .queue(Print(format!(
+                "Play again? Press {} or {} for yes; Press {} or {} for no ",
+                self.key_bindings.key_name(Action::Yes),
+                self.key_bindings.key_name(Action::Place),
+                self.key_bindings.key_name(Action::Quit),
+                self.key_bindings.key_name(Action::No)
+            )))?
+            .queue(MoveToNextLine(1))?
+            .queue(MoveToColumn(summary_left))?
+            .queue(Print(buttons))?
+            .flush()?;
+        Ok((summary_end + 3, summary_left))
+    }
+
+    /// Draws the game board, the outcome of the game and the scores
+    /// 
+    /// `incomplete_text` is shown in place of the outcome if the game is not finished.
+    /// The classic board is centred in the terminal, with the summary below it and room for
+    /// [SUMMARY_ROWS_BELOW](Self::SUMMARY_ROWS_BELOW) more lines.
+    /// Leaves the cursor at the end of the last line drawn, and returns the row of that line
+    /// and the column the summary's lines start at.
+    pub(super) fn draw_game_summary(&mut self, incomplete_text: &str) -> crossterm::Result<(u16, u16)>
+    {
+        // the outcome is shown on the row below the board(s), and the scores below it
+        let outcome_row;
+        let mut summary_left = 0;
+        let game_outcome_text = if let (Some(game), GameMode::Multiplayer) = (&self.multiplayer, self.game_mode) {
+            let boards = game.boards();
+            let outcome = game.outcome();
+            let layout = Layout::new(&boards);
+            self.draw_variant_boards(&boards, &layout, None)?;
+            outcome_row = layout.height + 1;
+            match outcome {
+                MultiplayerOutcome::Win(winner) => format!("Player {} wins!", winner.get_char()),
+                MultiplayerOutcome::Draw => "Draw!".to_owned(),
+                MultiplayerOutcome::Incomplete => incomplete_text.to_owned()
             }
+        } else if let (Some(variant), false) = (&self.variant, self.game_mode.is_classic()) {
+            let boards = variant.boards();
+            let outcome = variant.outcome();
+            let half_point_text = variant.half_point()
+                .map(|player|{format!(" Player {} wins half a point.", self.player_identity(player).get_char())})
+                .unwrap_or_default();
+            let layout = Layout::new(&boards);
+            self.draw_variant_boards(&boards, &layout, None)?;
+            outcome_row = layout.height + 1;
+            let outcome_text = match outcome {
+                Outcome::Win(winner) if self.sides_swapped => format!(
+                    "Player {} wins, playing {} after swapping sides!",
+                    self.player_identity(winner.clone()).get_char(),
+                    winner.get_char()
+                ),
+                Outcome::Win(ActivePlayer::PlayerX) => "Player X wins!".to_owned(),
+                Outcome::Win(ActivePlayer::PlayerO) => "Player O wins!".to_owned(),
+                Outcome::Draw => "Draw!".to_owned(),
+                Outcome::Incomplete => incomplete_text.to_owned()
+            };
+            outcome_text + &half_point_text
+        } else {
+            let (win_position, game_outcome_text) = if let Some(resigned_player) = self.resigned_player.clone() {
+                (None, match resigned_player {
+                    ActivePlayer::PlayerX => "Player X resigned!",
+                    ActivePlayer::PlayerO => "Player O resigned!"
+                }.to_owned())
+            } else {
+                match self.game_board.game_outcome(){
+                    GameOutcome::PlayerX(win_position) => (Some(win_position), match self.game_mode {
+                        GameMode::Reverse => self.win_text('O', &win_position),
+                        _ => self.win_text('X', &win_position)
+                    }),
+                    GameOutcome::PlayerO(win_position) => (Some(win_position), match self.game_mode {
+                        GameMode::Reverse => self.win_text('X', &win_position),
+                        _ => self.win_text('O', &win_position)
+                    }),
+                    GameOutcome::Draw => (None, "Draw!".to_owned()),
+                    GameOutcome::Incomplete => (None, incomplete_text.to_owned())
+                }
+            };
+
+            // the summary is centred with the board, leaving room for the lines drawn below it
+            let text_width = game_outcome_text.chars().count().max(Self::SUMMARY_TEXT_WIDTH) as u16;
+            let text_height = self.seats().count() as u16 + 3 + Self::SUMMARY_ROWS_BELOW;
+            let geometry = BoardGeometry::new((self.terminal_x_size, self.terminal_y_size), (text_width, text_height), None)
+                .unwrap_or_else(BoardGeometry::top_left);
+            self.draw_game(geometry, win_position, None)?;
+            (outcome_row, summary_left) = (geometry.text_top, geometry.text_left);
+            game_outcome_text
         };
-        let player_x_score = self.player_x_score();
-        let player_o_score = self.player_o_score();
         let number_of_draws = self.number_of_draws();
         let number_of_games = self.number_of_games();
-        stdout()
-            .queue(MoveToRow(5))?
-            .queue(Print(game_outcome_text))?
-            
-            .queue(MoveToNextLine(1))?
-            .queue(MoveToColumn(0))?
-            .queue(Print(format!("X score:     {}\t({:.2}%)", player_x_score, 
-                    ((player_x_score as f64)/(number_of_games as f64))*100.0)))?
-            
-            .queue(MoveToNextLine(1))?
-            .queue(MoveToColumn(0))?
-            .queue(Print(format!("O score:     {}\t({:.2}%)", player_o_score, 
-                ((player_o_score as f64)/(number_of_games as f64))*100.0)))?
+        self.terminal
+            .queue(MoveToRow(outcome_row))?
+            .queue(MoveToColumn(summary_left))?
+            .queue(Clear(ClearType::CurrentLine))?
+            .queue(Print(&game_outcome_text))?;
+
+        let seats: Vec<_> = self.seats().collect();
+        // one line for each score, then the draws and the total
+        let last_row = outcome_row + seats.len() as u16 + 2;
+        for seat in seats {
+            let score = self.score(seat);
+            self.terminal
+                .queue(MoveToNextLine(1))?
+                .queue(MoveToColumn(summary_left))?
+                .queue(Print(format!("{} score:     {}\t({:.2}%)", seat.get_char(), score,
+                    Self::percentage(score, number_of_games))))?;
+        }
 
+        self.terminal
             .queue(MoveToNextLine(1))?
-            .queue(MoveToColumn(0))?
+            .queue(MoveToColumn(summary_left))?
             .queue(Print(format!("Draws:       {}\t({:.2}%)", number_of_draws,
-                ((number_of_draws as f64)/(number_of_games as f64))*100.0)))?
+                Self::percentage(number_of_draws as f64, number_of_games))))?
 
             .queue(MoveToNextLine(1))?
-            .queue(MoveToColumn(0))?
+            .queue(MoveToColumn(summary_left))?
             .queue(Print(format!("Total games: {}", number_of_games)))?
-            
-            .queue(MoveToNextLine(2))?
-            .queue(MoveToColumn(0))?
-            .queue(Print("Play again? Press y or Enter for yes; Press q or n for no "))?
             .flush()?;
-        Ok(())
+        Ok((last_row, summary_left))
+    }
+
+    /// Returns the text announcing that `winner` won a classic game with the given [WinPosition]
+    /// 
+    /// Wins with a pattern from the [Rules](crate::rules::Rules) name the pattern.
+    fn win_text(&self, winner: char, win_position: &WinPosition) -> String
+    {
+        match self.game_board.rules().pattern_name(win_position) {
+            Some(pattern_name) => format!("Player {} wins with {}!", winner, pattern_name),
+            None => format!("Player {} wins!", winner)
+        }
+    }
+
+    /// Returns `count` as a percentage of `number_of_games`, or 0 if no games have been played
+    fn percentage(count: f64, number_of_games: u32) -> f64
+    {
+        if number_of_games != 0 {
+            (count/(number_of_games as f64))*100.0
+        } else {
+            0.0
+        }
     }
 }
\ No newline at end of file
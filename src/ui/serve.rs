@@ -0,0 +1,50 @@
+//! UI implementations for serving games to clients connecting over TCP
+
+use std::{
+    io,
+    net::{TcpListener, TcpStream},
+    thread
+};
+
+use super::terminal::SocketTerminal;
+
+impl super::UI {
+
+    /// Serves a separate session to every client that connects to the given listener
+    ///
+    /// Each client is shown the setup menu, then plays games (against the AI by default)
+    /// until they quit, just like in the local terminal. The UI is drawn using ANSI escape
+    /// sequences written to the client's connection, and key presses are read from it.
+    ///
+    /// Sessions run on their own threads, so this only returns if the listener fails.
+    pub fn serve(listener: TcpListener) -> io::Result<()>
+    {
+        for stream in listener.incoming() {
+            // a failed connection only affects that client
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(_) => continue
+            };
+            thread::spawn(move ||{
+                let client = match stream.peer_addr() {
+                    Ok(address) => address.to_string(),
+                    Err(_) => "unknown client".to_owned()
+                };
+                println!("{} connected", client);
+                // errors mean the client disconnected, which ends their session either way
+                let _ = Self::serve_session(stream);
+                println!("{} disconnected", client);
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Runs the setup menu and then plays games over the given connection
+    fn serve_session(stream: TcpStream) -> crossterm::Result<()>
+    {
+        let mut ui = Self::with_terminal(Box::new(SocketTerminal::new(stream)?))?;
+        ui.setup_menu()?;
+        ui.play_games()
+    }
+}
@@ -24,17 +24,25 @@ use menu_options::{
     DifficultyMenuOption,
     AutoquitModeMenuOption,
     AutoquitValueMenuOption,
-    GameModeMenuOption
+    GameModeMenuOption,
+    DiagonalMovesMenuOption,
+    NumberMenuOption,
+    WrapAroundMenuOption,
+    SwapRuleMenuOption,
+    OrderRoleMenuOption,
+    StartingPositionMenuOption,
+    ThemeMenuOption
 };
 
 use crate::{
-    active_player::ActivePlayer, 
+    multiplayer::{Seat, MultiplayerGame}, 
     player_type::PlayerType, 
     ai::AiPlayer,
-    game_settings::{GameMode, GameAutoquitMode}
+    game_settings::{GameMode, VariantOptions},
+    variants::{notakto::Notakto, gravity::Gravity}
 };
 
-use super::UI;
+use super::{UI, theme::Theme};
 
 /// Representation of the state of the menu
 /// 
@@ -57,11 +65,64 @@ pub(super) struct SetupMenu {
     /// only used if player o is AI
     player_o_ai: DifficultyMenuOption,
 
+    /// only used in games with three or more players
+    player_triangle_type: PlayerTypeMenuOption,
+
+    /// only used in games with four players
+    player_square_type: PlayerTypeMenuOption,
+
+    /// only used in games with three or more players, if player △ is AI
+    player_triangle_ai: DifficultyMenuOption,
+
+    /// only used in games with four players, if player □ is AI
+    player_square_ai: DifficultyMenuOption,
+
     autoquit_mode: AutoquitModeMenuOption,
 
     autoquit_value: AutoquitValueMenuOption,
 
     game_mode: GameModeMenuOption,
+
+    /// only used in games with more than two players
+    player_count: NumberMenuOption,
+
+    /// only used in Three Men's Morris
+    diagonal_moves: DiagonalMovesMenuOption,
+
+    /// only used in Notakto
+    board_count: NumberMenuOption,
+
+    /// only used in Gravity and games with more than two players
+    board_columns: NumberMenuOption,
+
+    /// only used in Gravity and games with more than two players
+    board_rows: NumberMenuOption,
+
+    /// only used in Gravity and games with more than two players
+    line_length: NumberMenuOption,
+
+    /// only used in Order and Chaos
+    order_role: OrderRoleMenuOption,
+
+    /// only used in two player games of modes played on a variant
+    swap_rule: SwapRuleMenuOption,
+
+    /// only used in local classic and reverse games
+    wrap_around: WrapAroundMenuOption,
+
+    /// only used in local classic and reverse games
+    blocked_spaces: NumberMenuOption,
+
+    /// only used in local classic and reverse games
+    handicap_x: NumberMenuOption,
+
+    /// only used in local classic and reverse games
+    handicap_o: NumberMenuOption,
+
+    /// only used in local classic and reverse games
+    starting_position: StartingPositionMenuOption,
+
+    theme: ThemeMenuOption,
     
     selected_option: SelectedOption,
 
@@ -71,117 +132,136 @@ pub(super) struct SetupMenu {
     term_y: u16,
 
     /// scroll position; the index of the first row to be printed
-    scroll_pos: u16
+    scroll_pos: u16,
+
+    /// true if setting up a networked game, in which one player must be remote
+    networked: bool
 
 }
 
 impl SetupMenu{
 
     const TERMSIZE_MIN_X: u16 = 68;
-    const TERMSIZE_MIN_Y: u16 = super::UI::TERMSIZE_MIN_Y;
+    const TERMSIZE_MIN_Y: u16 = 8;
 
     /// Creates and returns a new SetupMenu
-    pub fn new() -> Self
+    /// 
+    /// If `networked` is true, players may be set to [PlayerType::Remote],
+    /// and player O defaults to being remote. `theme` is the [Theme] selected at first.
+    pub fn new(networked: bool, theme: Theme) -> Self
     {
+        let default_options = VariantOptions::default();
         Self {
             player_x_type: PlayerTypeMenuOption::new(
-                ActivePlayer::PlayerX, 
-                PlayerType::Human
+                Seat::X, 
+                PlayerType::Human,
+                networked
             ),
             player_o_type: PlayerTypeMenuOption::new(
-                ActivePlayer::PlayerO,
-                PlayerType::AI(AiPlayer::default())
+                Seat::O,
+                if networked {PlayerType::Remote} else {PlayerType::AI(AiPlayer::default())},
+                networked
+            ),
+            player_x_ai: DifficultyMenuOption::new(Seat::X),
+            player_o_ai: DifficultyMenuOption::new(Seat::O),
+            // games with more than two players are only played locally
+            player_triangle_type: PlayerTypeMenuOption::new(
+                Seat::Triangle,
+                PlayerType::AI(AiPlayer::default()),
+                false
             ),
-            player_x_ai: DifficultyMenuOption::new(ActivePlayer::PlayerX),
-            player_o_ai: DifficultyMenuOption::new(ActivePlayer::PlayerO),
+            player_square_type: PlayerTypeMenuOption::new(
+                Seat::Square,
+                PlayerType::AI(AiPlayer::default()),
+                false
+            ),
+            player_triangle_ai: DifficultyMenuOption::new(Seat::Triangle),
+            player_square_ai: DifficultyMenuOption::new(Seat::Square),
             autoquit_mode: AutoquitModeMenuOption::new(),
             autoquit_value: AutoquitValueMenuOption::new(),
-            game_mode: GameModeMenuOption::new(),
+            game_mode: GameModeMenuOption::new(!networked),
+            player_count: NumberMenuOption::new(
+                "Players",
+                default_options.players,
+                MultiplayerGame::MIN_PLAYERS,
+                MultiplayerGame::MAX_PLAYERS
+            ),
+            diagonal_moves: DiagonalMovesMenuOption::new(),
+            board_count: NumberMenuOption::new(
+                "Boards",
+                default_options.board_count,
+                Notakto::MIN_BOARDS,
+                Notakto::MAX_BOARDS
+            ),
+            board_columns: NumberMenuOption::new(
+                "Columns",
+                default_options.board_columns,
+                Gravity::MIN_COLUMNS,
+                Gravity::MAX_COLUMNS
+            ),
+            board_rows: NumberMenuOption::new(
+                "Rows",
+                default_options.board_rows,
+                Gravity::MIN_ROWS,
+                Gravity::MAX_ROWS
+            ),
+            line_length: NumberMenuOption::new(
+                "Line Length",
+                default_options.line_length,
+                Gravity::MIN_LINE_LENGTH,
+                Gravity::MAX_LINE_LENGTH
+            ),
+            order_role: OrderRoleMenuOption::new(),
+            swap_rule: SwapRuleMenuOption::new(),
+            wrap_around: WrapAroundMenuOption::new(),
+            blocked_spaces: NumberMenuOption::new(
+                "Blocked Spaces",
+                default_options.blocked_spaces,
+                0,
+                UI::MAX_BLOCKED_SPACES
+            ),
+            handicap_x: NumberMenuOption::new(
+                "X Handicap Pieces",
+                default_options.handicap_x,
+                0,
+                UI::MAX_HANDICAP_PIECES
+            ),
+            handicap_o: NumberMenuOption::new(
+                "O Handicap Pieces",
+                default_options.handicap_o,
+                0,
+                UI::MAX_HANDICAP_PIECES
+            ),
+            starting_position: StartingPositionMenuOption::new(),
+            theme: ThemeMenuOption::new(theme),
             selected_option: SelectedOption::PlayerXType,
             term_x: 0,
             term_y: 0,
-            scroll_pos: 0
+            scroll_pos: 0,
+            networked
         }
     }
 
-    /// Selects the next option
+    /// Selects the next enabled option, wrapping around
     pub fn next_option(&mut self)
     {
-        match self.selected_option {
-            SelectedOption::PlayerXType => {
-                if self.player_x_type.value() == &PlayerType::Human{
-                    self.selected_option = SelectedOption::PlayerOType
-                } else {
-                    self.selected_option = SelectedOption::PlayerXAi
-                }
-            },
-            SelectedOption::PlayerXAi => {
-                self.selected_option = SelectedOption::PlayerOType
-            }
-            SelectedOption::PlayerOType => {
-                if self.player_o_type.value() == &PlayerType::Human{
-                    self.selected_option = SelectedOption::AutoquitMode
-                } else {
-                    self.selected_option = SelectedOption::PlayerOAi
-                }
-            },
-            SelectedOption::PlayerOAi => {
-                self.selected_option = SelectedOption::AutoquitMode
-            },
-            SelectedOption::AutoquitMode => {
-                if self.autoquit_mode.value() == &GameAutoquitMode::Unlimited {
-                    self.selected_option = SelectedOption::GameMode
-                } else {
-                    self.selected_option = SelectedOption::AutoquitValue
-                }
-            },
-            SelectedOption::AutoquitValue => {
-                self.selected_option = SelectedOption::GameMode
-            },
-            SelectedOption::GameMode => {
-                self.selected_option = SelectedOption::PlayerXType
-            }
+        let options: Vec<SelectedOption> = SelectedOption::all().collect();
+        let index = self.selected_option.index() as usize;
+        if let Some(option) = (1..options.len()).map(|offset|{options[(index + offset) % options.len()]})
+            .find(|option|{self.option_enabled(*option)}) {
+            self.selected_option = option;
         }
         self.adjust_scrolling(false);
     }
 
-    /// Selects the previous option
+    /// Selects the previous enabled option, wrapping around
     pub fn prev_option(&mut self)
     {
-        match self.selected_option {
-            SelectedOption::PlayerXType => {
-                self.selected_option = SelectedOption::GameMode
-            },
-            SelectedOption::PlayerXAi => {
-                self.selected_option = SelectedOption::PlayerXType
-            },
-            SelectedOption::PlayerOType => {
-                if self.player_x_type.value() == &PlayerType::Human{
-                    self.selected_option = SelectedOption::PlayerXType
-                } else {
-                    self.selected_option = SelectedOption::PlayerXAi
-                }
-            },
-            SelectedOption::PlayerOAi => {
-                self.selected_option = SelectedOption::PlayerOType
-            },
-            SelectedOption::AutoquitMode => {
-                if self.player_o_type.value() == &PlayerType::Human{
-                    self.selected_option = SelectedOption::PlayerOType
-                } else {
-                    self.selected_option = SelectedOption::PlayerOAi
-                }
-            },
-            SelectedOption::AutoquitValue => {
-                self.selected_option = SelectedOption::AutoquitMode
-            },
-            SelectedOption::GameMode => {
-                if self.autoquit_mode.value() == &GameAutoquitMode::Unlimited{
-                    self.selected_option = SelectedOption::AutoquitMode
-                } else {
-                    self.selected_option = SelectedOption::AutoquitValue
-                }
-            }
+        let options: Vec<SelectedOption> = SelectedOption::all().collect();
+        let index = self.selected_option.index() as usize;
+        if let Some(option) = (1..options.len()).map(|offset|{options[(index + options.len() - offset) % options.len()]})
+            .find(|option|{self.option_enabled(*option)}) {
+            self.selected_option = option;
         }
         self.adjust_scrolling(false);
     }
@@ -191,32 +271,72 @@ impl SetupMenu{
     /// Consumes this `SetupMenu` instance
     pub fn apply_settings(self, ui_instance: &mut UI)
     {
-        let game_mode = self.game_mode.value();
-        ui_instance.player_x = match self.player_x_type.value() {
-            PlayerType::Human => PlayerType::Human,
-            PlayerType::AI(_) => {
-                let ai_player = match game_mode {
-                    GameMode::Classic => self.player_x_ai.value(),
-                    GameMode::Reverse => self.player_x_ai.value().reverse_difficulty()
-                };
-                PlayerType::AI(ai_player)
-            }
+        let game_mode = self.game_mode.consume();
+        ui_instance.player_x = Self::chosen_player_type(&self.player_x_type, self.player_x_ai, game_mode);
+        ui_instance.player_o = Self::chosen_player_type(&self.player_o_type, self.player_o_ai, game_mode);
+        ui_instance.player_triangle =
+            Self::chosen_player_type(&self.player_triangle_type, self.player_triangle_ai, game_mode);
+        ui_instance.player_square =
+            Self::chosen_player_type(&self.player_square_type, self.player_square_ai, game_mode);
+
+        ui_instance.game_autoquit_mode = self.autoquit_mode.consume();
+        ui_instance.game_autoquit_value = self.autoquit_value.value();
+        ui_instance.game_mode = game_mode;
+        ui_instance.theme = self.theme.value();
+        ui_instance.variant_options = VariantOptions{
+            diagonal_moves: self.diagonal_moves.value(),
+            board_count: self.board_count.value(),
+            board_columns: self.board_columns.value(),
+            board_rows: self.board_rows.value(),
+            line_length: self.line_length.value(),
+            players: self.player_count.value(),
+            order_role: self.order_role.value(),
+            swap_rule: self.swap_rule.value(),
+            toroidal: self.wrap_around.value(),
+            blocked_spaces: self.blocked_spaces.value(),
+            handicap_x: self.handicap_x.value(),
+            handicap_o: self.handicap_o.value(),
+            choose_starting_position: self.starting_position.value()
         };
+    }
 
-        ui_instance.player_o = match self.player_o_type.value() {
+    /// Returns the [PlayerType] chosen by the given options for one player
+    /// 
+    /// AI players use the chosen difficulty, reversed in [GameMode::Reverse]
+    fn chosen_player_type(
+        type_option: &PlayerTypeMenuOption,
+        ai_option: DifficultyMenuOption,
+        game_mode: GameMode
+    ) -> PlayerType
+    {
+        match type_option.value() {
             PlayerType::Human => PlayerType::Human,
+            PlayerType::Remote => PlayerType::Remote,
             PlayerType::AI(_) => {
                 let ai_player = match game_mode {
-                    GameMode::Classic => self.player_o_ai.value(),
-                    GameMode::Reverse => self.player_o_ai.value().reverse_difficulty()
+                    GameMode::Reverse => ai_option.value().reverse_difficulty(),
+                    _ => ai_option.value()
                 };
                 PlayerType::AI(ai_player)
             }
-        };
+        }
+    }
 
-        ui_instance.game_autoquit_mode = self.autoquit_mode.consume();
-        ui_instance.game_autoquit_value = self.autoquit_value.value();
-        ui_instance.game_mode = game_mode;
+    /// Returns true if the chosen settings can be used to start a game
+    /// 
+    /// A networked game needs exactly one remote player
+    fn settings_valid(&self) -> bool
+    {
+        let remote_players = [self.player_x_type.value(), self.player_o_type.value()]
+            .into_iter()
+            .filter(|player_type|{player_type == &&PlayerType::Remote})
+            .count();
+
+        if self.networked {
+            remote_players == 1
+        } else {
+            remote_players == 0
+        }
     }
 
     /// sets the scroll_pos so that the currently selected option is visible,
@@ -244,23 +364,59 @@ enum SelectedOption{
     PlayerXAi,
     PlayerOType,
     PlayerOAi,
+    PlayerTriangleType,
+    PlayerTriangleAi,
+    PlayerSquareType,
+    PlayerSquareAi,
     AutoquitMode,
     AutoquitValue,
-    GameMode
+    GameMode,
+    PlayerCount,
+    DiagonalMoves,
+    BoardCount,
+    BoardColumns,
+    BoardRows,
+    LineLength,
+    OrderRole,
+    SwapRule,
+    WrapAround,
+    BlockedSpaces,
+    HandicapX,
+    HandicapO,
+    StartingPosition,
+    Theme
 }
 
 impl SelectedOption{
     /// Returns an iterator over all SelectedOption variants
     pub fn all() -> impl Iterator<Item = SelectedOption>
     {
-        const ALL_OPTIONS: [SelectedOption; 7] = [
+        const ALL_OPTIONS: [SelectedOption; 25] = [
             SelectedOption::PlayerXType,
             SelectedOption::PlayerXAi,
             SelectedOption::PlayerOType,
             SelectedOption::PlayerOAi,
+            SelectedOption::PlayerTriangleType,
+            SelectedOption::PlayerTriangleAi,
+            SelectedOption::PlayerSquareType,
+            SelectedOption::PlayerSquareAi,
             SelectedOption::AutoquitMode,
             SelectedOption::AutoquitValue,
-            SelectedOption::GameMode
+            SelectedOption::GameMode,
+            SelectedOption::PlayerCount,
+            SelectedOption::DiagonalMoves,
+            SelectedOption::BoardCount,
+            SelectedOption::BoardColumns,
+            SelectedOption::BoardRows,
+            SelectedOption::LineLength,
+            SelectedOption::OrderRole,
+            SelectedOption::SwapRule,
+            SelectedOption::WrapAround,
+            SelectedOption::BlockedSpaces,
+            SelectedOption::HandicapX,
+            SelectedOption::HandicapO,
+            SelectedOption::StartingPosition,
+            SelectedOption::Theme
             ];
 
         ALL_OPTIONS.into_iter()
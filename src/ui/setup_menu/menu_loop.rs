@@ -1,19 +1,19 @@
 //! SetupMenu implementations for rendering and interaction
 
-use std::{io::{stdout, Write}, convert::TryInto};
+use std::convert::TryInto;
 
 use crossterm::{
-    terminal::{Clear, ClearType, self},
-    cursor::{self, MoveToColumn, MoveToRow, MoveToNextLine},
+    terminal::{Clear, ClearType},
+    cursor::{self, MoveTo, MoveToColumn, MoveToRow, MoveToNextLine},
     style::{Stylize, PrintStyledContent, Print},
-    event::{self, Event, KeyEvent, KeyCode, KeyModifiers},
+    event::{Event, KeyEvent, KeyCode, KeyModifiers, MouseEvent, MouseEventKind, MouseButton},
     QueueableCommand,
     ExecutableCommand
 };
 
 use crate::{
-    player_type::PlayerType, 
-    game_settings::GameAutoquitMode
+    game_settings::{GameAutoquitMode, GameMode},
+    ui::{terminal::Terminal, key_bindings::{KeyBindings, Action}}
 };
 
 use super::{
@@ -26,89 +26,125 @@ impl super::SetupMenu {
     /// Display menu until user submits choices
     /// 
     /// returns false if user exits, returns true if user accepts choices
-    pub fn setup_menu_loop(&mut self) -> crossterm::Result<bool>
+    pub fn setup_menu_loop(&mut self, terminal: &mut dyn Terminal, key_bindings: &KeyBindings) -> crossterm::Result<bool>
     {
         // hide cursor and clear screen
-        stdout()
+        terminal
             .queue(cursor::Hide)?    
             .queue(Clear(ClearType::All))?
             .flush()?;
 
-        (self.term_x, self.term_y) = terminal::size()?;
+        (self.term_x, self.term_y) = terminal.size()?;
 
         let return_val = loop {
             if self.term_x >= Self::TERMSIZE_MIN_X && self.term_y >= Self::TERMSIZE_MIN_Y {
-                self.render_setup_menu()?;
+                self.render_setup_menu(terminal, key_bindings)?;
             } else {
-                stdout()
+                terminal
                     .queue(MoveToColumn(0))?
                     .queue(MoveToRow(0))?
                     .queue(Print(format!("Terminal too small ({} x {})! Please enlarge terminal", self.term_x, self.term_y)))?
                     .flush()?;
             }
 
-            match event::read()? {
+            match terminal.read_event()? {
                 //ignore size returned by resize event as it is currently (as of crossterm 0.25) wrong on Windows
                 Event::Resize(_,_) => {
                     //clear screen if resize is detected
-                    stdout().execute(Clear(ClearType::All))?;
-                    let (new_x, new_y) = terminal::size()?;
+                    terminal.execute(Clear(ClearType::All))?;
+                    let (new_x, new_y) = terminal.size()?;
                     let expanded = new_y > self.term_y;
                     self.term_x = new_x;
                     self.term_y = new_y;
                     self.adjust_scrolling(expanded);
                 },
-                Event::Key(key_event) => match key_event {
-                    KeyEvent{code: KeyCode::Char('c'), modifiers: KeyModifiers::CONTROL, ..} => {
-                        break false;
-                    },
-                    KeyEvent{code: KeyCode::Char('q'), ..} => {
+                Event::Key(KeyEvent{code: KeyCode::Char('c'), modifiers: KeyModifiers::CONTROL, ..}) => {
+                    break false;
+                },
+                Event::Key(key_event) => match key_bindings.action(&key_event) {
+                    Some(Action::Quit) => {
                         break false;
                     },
-                    KeyEvent{code: KeyCode::Enter, ..} => {
+                    Some(Action::Place) if self.settings_valid() => {
                         break true;
                     },
-                    KeyEvent{code: KeyCode::Up, ..} => {
+                    Some(Action::Up) => {
                         self.prev_option();
                     },
-                    KeyEvent{code: KeyCode::Down, ..} => {
+                    Some(Action::Down) => {
                         self.next_option();
                     },
-                    KeyEvent{code: KeyCode::Right, ..} => {
-                        if self.get_option_mut(self.selected_option).next_value().is_err(){
-                            //ignore error
-                        };
+                    Some(Action::Right) => {
+                        //ignore error
+                        let _ = self.get_option_mut(self.selected_option).next_value();
                     }
-                    KeyEvent{code: KeyCode::Left, ..} => {
-                        if self.get_option_mut(self.selected_option).prev_value().is_err(){
-                            //ignore error
-                        };
+                    Some(Action::Left) => {
+                        //ignore error
+                        let _ = self.get_option_mut(self.selected_option).prev_value();
                     },
                     _=>()
                 },
+                Event::Mouse(MouseEvent{kind: MouseEventKind::Down(MouseButton::Left), column, row, ..}) => {
+                    self.click(column, row);
+                },
                 _ => ()
             }
         };
         //show cursor before exiting
-        stdout().execute(cursor::Show)?;
+        terminal.execute(cursor::Show)?;
         Ok(return_val)
     }
 
     /// Returns true if the given option should be rendered as enabled
     /// 
     /// Uses current state to determine whether option should be enabled
-    fn option_enabled(&self, option: SelectedOption) -> bool
+    pub(super) fn option_enabled(&self, option: SelectedOption) -> bool
     {
         match option {
             SelectedOption::PlayerXAi => {
-                self.player_x_type.value() != &PlayerType::Human
+                self.player_x_type.is_ai()
             },
             SelectedOption::PlayerOAi => {
-                self.player_o_type.value() != &PlayerType::Human
+                self.player_o_type.is_ai()
+            },
+            SelectedOption::PlayerTriangleType | SelectedOption::PlayerCount => {
+                self.game_mode.value() == &GameMode::Multiplayer
+            },
+            SelectedOption::PlayerTriangleAi => {
+                self.game_mode.value() == &GameMode::Multiplayer && self.player_triangle_type.is_ai()
+            },
+            SelectedOption::PlayerSquareType => {
+                self.game_mode.value() == &GameMode::Multiplayer && self.player_count.value() > 3
+            },
+            SelectedOption::PlayerSquareAi => {
+                self.game_mode.value() == &GameMode::Multiplayer && self.player_count.value() > 3 &&
+                    self.player_square_type.is_ai()
             },
             SelectedOption::AutoquitValue => {
                 self.autoquit_mode.value() != &GameAutoquitMode::Unlimited
             },
+            SelectedOption::DiagonalMoves => {
+                self.game_mode.value() == &GameMode::Morris
+            },
+            SelectedOption::BoardCount => {
+                self.game_mode.value() == &GameMode::Notakto
+            },
+            SelectedOption::BoardColumns | SelectedOption::BoardRows | SelectedOption::LineLength => {
+                matches!(self.game_mode.value(), GameMode::Gravity | GameMode::Multiplayer)
+            },
+            SelectedOption::OrderRole => {
+                self.game_mode.value() == &GameMode::OrderChaos
+            },
+            SelectedOption::SwapRule => {
+                let game_mode = self.game_mode.value();
+                !game_mode.is_classic() && game_mode != &GameMode::Multiplayer
+            },
+            SelectedOption::WrapAround | SelectedOption::BlockedSpaces | SelectedOption::HandicapX |
+            SelectedOption::HandicapO | SelectedOption::StartingPosition => {
+                // the other player's board wouldn't wrap around or have the same
+                // starting position in a networked game
+                !self.networked && self.game_mode.value().is_classic()
+            },
             _ => true
         }
     }
@@ -121,9 +157,27 @@ impl super::SetupMenu {
             SelectedOption::PlayerOType => &self.player_o_type,
             SelectedOption::PlayerXAi => &self.player_x_ai,
             SelectedOption::PlayerOAi => &self.player_o_ai,
+            SelectedOption::PlayerTriangleType => &self.player_triangle_type,
+            SelectedOption::PlayerTriangleAi => &self.player_triangle_ai,
+            SelectedOption::PlayerSquareType => &self.player_square_type,
+            SelectedOption::PlayerSquareAi => &self.player_square_ai,
             SelectedOption::AutoquitMode => &self.autoquit_mode,
             SelectedOption::AutoquitValue => &self.autoquit_value,
-            SelectedOption::GameMode => &self.game_mode
+            SelectedOption::GameMode => &self.game_mode,
+            SelectedOption::PlayerCount => &self.player_count,
+            SelectedOption::DiagonalMoves => &self.diagonal_moves,
+            SelectedOption::BoardCount => &self.board_count,
+            SelectedOption::BoardColumns => &self.board_columns,
+            SelectedOption::BoardRows => &self.board_rows,
+            SelectedOption::LineLength => &self.line_length,
+            SelectedOption::OrderRole => &self.order_role,
+            SelectedOption::SwapRule => &self.swap_rule,
+            SelectedOption::WrapAround => &self.wrap_around,
+            SelectedOption::BlockedSpaces => &self.blocked_spaces,
+            SelectedOption::HandicapX => &self.handicap_x,
+            SelectedOption::HandicapO => &self.handicap_o,
+            SelectedOption::StartingPosition => &self.starting_position,
+            SelectedOption::Theme => &self.theme
         }
     }
 
@@ -135,27 +189,39 @@ impl super::SetupMenu {
             SelectedOption::PlayerOType => &mut self.player_o_type,
             SelectedOption::PlayerXAi => &mut self.player_x_ai,
             SelectedOption::PlayerOAi => &mut self.player_o_ai,
+            SelectedOption::PlayerTriangleType => &mut self.player_triangle_type,
+            SelectedOption::PlayerTriangleAi => &mut self.player_triangle_ai,
+            SelectedOption::PlayerSquareType => &mut self.player_square_type,
+            SelectedOption::PlayerSquareAi => &mut self.player_square_ai,
             SelectedOption::AutoquitMode => &mut self.autoquit_mode,
             SelectedOption::AutoquitValue => &mut self.autoquit_value,
-            SelectedOption::GameMode => &mut self.game_mode
+            SelectedOption::GameMode => &mut self.game_mode,
+            SelectedOption::PlayerCount => &mut self.player_count,
+            SelectedOption::DiagonalMoves => &mut self.diagonal_moves,
+            SelectedOption::BoardCount => &mut self.board_count,
+            SelectedOption::BoardColumns => &mut self.board_columns,
+            SelectedOption::BoardRows => &mut self.board_rows,
+            SelectedOption::LineLength => &mut self.line_length,
+            SelectedOption::OrderRole => &mut self.order_role,
+            SelectedOption::SwapRule => &mut self.swap_rule,
+            SelectedOption::WrapAround => &mut self.wrap_around,
+            SelectedOption::BlockedSpaces => &mut self.blocked_spaces,
+            SelectedOption::HandicapX => &mut self.handicap_x,
+            SelectedOption::HandicapO => &mut self.handicap_o,
+            SelectedOption::StartingPosition => &mut self.starting_position,
+            SelectedOption::Theme => &mut self.theme
         }
     }
 
     /// Renders the passed option, accounting for enabled-ness, which option
     /// is currently selected by the user, and option bounds
-    fn render_option(&self, option: SelectedOption) -> crossterm::Result<()>
+    fn render_option(&self, terminal: &mut dyn Terminal, option: SelectedOption) -> crossterm::Result<()>
     {   
         let is_enabled = self.option_enabled(option);
 
         let is_currently_selected = option == self.selected_option;
 
-        let tabstring = if option == SelectedOption::PlayerOType
-            || option == SelectedOption::PlayerXType || option == SelectedOption::GameMode 
-            {
-                "\t\t"
-            } else {
-                "\t"
-            };
+        let tabstring = Self::tab_string(option);
 
         let option = self.get_option(option);
 
@@ -181,7 +247,7 @@ impl super::SetupMenu {
             option_text.dark_grey().crossed_out()
         };
 
-        stdout()
+        terminal
             .queue(Clear(ClearType::CurrentLine))?
             .queue(PrintStyledContent(styled_option_text))?
             .flush()?;
@@ -189,45 +255,114 @@ impl super::SetupMenu {
         Ok(())
     }
 
-    /// Renders the setup menu to the terminal
-    fn render_setup_menu(&self) -> crossterm::Result<()>
+    /// Returns each option that fits on the screen, with the row it is rendered on and whether
+    /// its description (if any) is rendered on the row below it
+    fn option_rows(&self) -> Vec<(SelectedOption, u16, bool)>
     {
-        // position cursor
-        stdout()
-            .queue(MoveToColumn(0))?
-            .queue(MoveToRow(0))?
-            .flush()?;
-
+        let mut rows = Vec::new();
+        let mut row = 0;
         for (index, option) in SelectedOption::all().enumerate() {
             let index: u16 = index.try_into().unwrap();
             if index >= self.scroll_pos{
                 let offset_index = index.saturating_sub(self.scroll_pos);
                 if self.term_y > offset_index {
-                    self.render_option(option)?;
-                    stdout()
-                        .queue(MoveToColumn(0))?
-                        .queue(MoveToNextLine(1))?
-                        .flush()?;
-
                     //only render description if there is space
-                    if self.term_y > offset_index+2{
-                        //render description if needed
-                        if let Some(option_desc) = self.get_option(option).description(){
-                            stdout()
-                                .queue(Clear(ClearType::CurrentLine))?
-                                .queue(Print(option_desc))?
-                                .queue(MoveToColumn(0))?
-                                .queue(MoveToNextLine(1))?
-                                .flush()?;
-                        }
-                    }
+                    let described = self.term_y > offset_index+2 && self.get_option(option).description().is_some();
+                    rows.push((option, row, described));
+                    row += if described {2} else {1};
                 }
             }
         }
-        stdout()
+        rows
+    }
+
+    /// Returns the text between an option's name and its value, which aligns the values
+    fn tab_string(option: SelectedOption) -> &'static str
+    {
+        match option {
+            SelectedOption::PlayerXType | SelectedOption::PlayerOType | SelectedOption::PlayerTriangleType |
+            SelectedOption::PlayerSquareType | SelectedOption::GameMode => "\t\t",
+            _ => "\t"
+        }
+    }
+
+    /// Returns the columns of the left and right arrows of the given option, as rendered
+    /// by [render_option](Self::render_option) when it is selected
+    fn arrow_columns(&self, option: SelectedOption) -> (u16, u16)
+    {
+        const TAB_WIDTH: u16 = 8;
+
+        let menu_option = self.get_option(option);
+        let name_width = menu_option.option_name().chars().count() as u16 + 1;
+        // each tab moves to the next tab stop
+        let left = Self::tab_string(option).chars()
+            .fold(name_width, |column, _|{(column / TAB_WIDTH + 1) * TAB_WIDTH});
+        let right = left + menu_option.current_value_name().chars().count() as u16 + 2;
+        (left, right)
+    }
+
+    /// Handles a click at the given position of the terminal
+    ///
+    /// Clicking an enabled option selects it, and clicking its `<` or `>` arrow also changes
+    /// its value, as the Left and Right keys do.
+    fn click(&mut self, column: u16, row: u16)
+    {
+        let Some((option, _, _)) = self.option_rows().into_iter()
+            .find(|(option, option_row, _)|{*option_row == row && self.option_enabled(*option)}) else {
+            return;
+        };
+        self.selected_option = option;
+        self.adjust_scrolling(false);
+
+        let (left, right) = self.arrow_columns(option);
+        //ignore error
+        if column == left {
+            let _ = self.get_option_mut(option).prev_value();
+        } else if column == right {
+            let _ = self.get_option_mut(option).next_value();
+        }
+    }
+
+    /// Renders the setup menu to the given terminal
+    fn render_setup_menu(&self, terminal: &mut dyn Terminal, key_bindings: &KeyBindings) -> crossterm::Result<()>
+    {
+        // position cursor
+        terminal
+            .queue(MoveToColumn(0))?
+            .queue(MoveToRow(0))?
+            .flush()?;
+
+        for (option, row, described) in self.option_rows() {
+            terminal.queue(MoveTo(0, row))?;
+            self.render_option(terminal, option)?;
+            terminal
+                .queue(MoveToColumn(0))?
+                .queue(MoveToNextLine(1))?
+                .flush()?;
+
+            if let (true, Some(option_desc)) = (described, self.get_option(option).description()) {
+                terminal
+                    .queue(Clear(ClearType::CurrentLine))?
+                    .queue(Print(option_desc))?
+                    .queue(MoveToColumn(0))?
+                    .queue(MoveToNextLine(1))?
+                    .flush()?;
+            }
+        }
+        let quit_key = key_bindings.key_name(Action::Quit);
+        let help_text = if self.settings_valid() {
+            format!(
+                "Use {} to select options. Press {} to accept or {} to quit",
+                key_bindings.movement_keys(), key_bindings.key_name(Action::Place), quit_key
+            )
+        } else {
+            format!("Exactly one player must be Remote to host a game. Press {} to quit", quit_key)
+        };
+        terminal
             .queue(Clear(ClearType::CurrentLine))?
             .queue(MoveToNextLine(1))?
-            .queue(Print("Use arrow keys to select options. Press Enter to accept or q to quit"))?
+            .queue(Clear(ClearType::CurrentLine))?
+            .queue(Print(help_text))?
             .flush()?;
 
         Ok(())
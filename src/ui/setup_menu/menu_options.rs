@@ -1,29 +1,54 @@
 //! MenuOption implementors for the setup menu
 
 use crate::{
-    active_player::ActivePlayer, 
+    multiplayer::Seat, 
     player_type::PlayerType, 
     ai::AiPlayer,
-    game_settings::{GameMode, GameAutoquitMode}
+    game_settings::{GameMode, GameAutoquitMode, OrderRole},
+    ui::theme::Theme
 };
 use super::MenuOption;
 
 pub(super) struct GameModeMenuOption {
-    selected_game_mode: GameMode
+    selected_game_mode: GameMode,
+    allow_variants: bool
 }
 
 impl GameModeMenuOption {
-    
-    /// Creates and returns a new GameModeMenuOption for the specified player
-    pub fn new() -> Self
+    /// Creates and returns a new GameModeMenuOption
+    /// 
+    /// Modes that are not [classic](GameMode::is_classic) can only be selected if
+    /// `allow_variants` is true
+    pub fn new(allow_variants: bool) -> Self
+    {
+        Self{selected_game_mode: GameMode::Classic, allow_variants}
+    }
+
+    pub fn value(&self) -> &GameMode
     {
-        Self{selected_game_mode: GameMode::Classic}
+        &self.selected_game_mode
     }
 
-    pub fn value(self) -> GameMode
+    pub fn consume(self) -> GameMode
     {
         self.selected_game_mode
     }
+
+    /// Returns the game modes that can be selected
+    fn available_modes(&self) -> Vec<GameMode>
+    {
+        GameMode::ALL.into_iter()
+            .filter(|game_mode|{self.allow_variants || game_mode.is_classic()})
+            .collect()
+    }
+
+    /// Selects the mode `offset` places after the current one, wrapping around
+    fn step(&mut self, offset: usize)
+    {
+        let modes = self.available_modes();
+        let index = modes.iter().position(|game_mode|{*game_mode == self.selected_game_mode}).unwrap_or(0);
+        self.selected_game_mode = modes[(index + offset) % modes.len()];
+    }
 }
 
 impl MenuOption for GameModeMenuOption {
@@ -35,20 +60,29 @@ impl MenuOption for GameModeMenuOption {
     fn current_value_name(&self) -> String {
         match self.selected_game_mode {
             GameMode::Classic => "Classic".to_owned(),
-            GameMode::Reverse => "Reverse".to_owned()
+            GameMode::Reverse => "Reverse".to_owned(),
+            GameMode::Ultimate => "Ultimate".to_owned(),
+            GameMode::Qubic => "3D (4x4x4)".to_owned(),
+            GameMode::Rolling => "Three pieces".to_owned(),
+            GameMode::Morris => "Three Men's Morris".to_owned(),
+            GameMode::Wild => "Wild".to_owned(),
+            GameMode::Notakto => "Notakto".to_owned(),
+            GameMode::Gravity => "Gravity".to_owned(),
+            GameMode::Quantum => "Quantum".to_owned(),
+            GameMode::Numerical => "Numerical".to_owned(),
+            GameMode::OrderChaos => "Order and Chaos".to_owned(),
+            GameMode::Multiplayer => "3-4 players".to_owned()
         }
     }
 
     fn next_value(&mut self) -> Result<(),()> {
-        match self.selected_game_mode {
-            GameMode::Classic => self.selected_game_mode = GameMode::Reverse,
-            GameMode::Reverse => self.selected_game_mode = GameMode::Classic
-        }
+        self.step(1);
         Ok(())
     }
 
     fn prev_value(&mut self) -> Result<(),()> {
-        self.next_value()
+        self.step(self.available_modes().len() - 1);
+        Ok(())
     }
 
     fn at_maximum(&self) -> bool {
@@ -62,11 +96,432 @@ impl MenuOption for GameModeMenuOption {
     fn description(&self) -> Option<String> {
         Some(match self.selected_game_mode {
             GameMode::Classic => "Play to place three of your pieces in a row. ".to_owned(),
-            GameMode::Reverse => "Play to avoid placing three of your pieces in a row. ".to_owned()
+            GameMode::Reverse => "Play to avoid placing three of your pieces in a row. ".to_owned(),
+            GameMode::Ultimate => "Win three boards in a row. Your move picks your opponent's next board. ".to_owned(),
+            GameMode::Qubic => "Place four of your pieces in a row, on one layer or through all four. ".to_owned(),
+            GameMode::Rolling => "Keep three pieces at most; placing a fourth removes your oldest (dimmed) piece. ".to_owned(),
+            GameMode::Morris => "Place three pieces, then move them to adjacent spaces to get three in a row. ".to_owned(),
+            GameMode::Wild => "Place an X or an O each turn. Whoever completes a line of either wins. ".to_owned(),
+            GameMode::Notakto => "Both players place X's. Whoever completes a line on the last live board loses. ".to_owned(),
+            GameMode::Gravity => "Pieces drop to the bottom of the chosen column. Connect Four by default. ".to_owned(),
+            GameMode::Quantum => "Each move marks two squares. Cycles of marks collapse into ordinary pieces. ".to_owned(),
+            GameMode::Numerical => "Place odd or even numbers, each once. A line adding up to 15 wins. ".to_owned(),
+            GameMode::OrderChaos => "Place an X or an O each turn. Order wants five in a row; Chaos wants a full board without. ".to_owned(),
+            GameMode::Multiplayer => "Three or four players take turns. The first to complete a line of their own wins. ".to_owned()
         })
     }
 }
 
+pub(super) struct DiagonalMovesMenuOption {
+    diagonal_moves: bool
+}
+
+impl DiagonalMovesMenuOption {
+
+    /// Creates and returns a new DiagonalMovesMenuOption
+    pub fn new() -> Self
+    {
+        Self{diagonal_moves: false}
+    }
+
+    pub fn value(self) -> bool
+    {
+        self.diagonal_moves
+    }
+}
+
+impl MenuOption for DiagonalMovesMenuOption {
+
+    fn option_name(&self) -> String {
+        "Diagonal Moves".to_owned()
+    }
+
+    fn current_value_name(&self) -> String {
+        if self.diagonal_moves {
+            "Allowed".to_owned()
+        } else {
+            "Not allowed".to_owned()
+        }
+    }
+
+    fn next_value(&mut self) -> Result<(),()> {
+        self.diagonal_moves = !self.diagonal_moves;
+        Ok(())
+    }
+
+    fn prev_value(&mut self) -> Result<(),()> {
+        self.next_value()
+    }
+
+    fn at_maximum(&self) -> bool {
+        false
+    }
+
+    fn at_minimum(&self) -> bool {
+        false
+    }
+
+    fn description(&self) -> Option<String> {
+        None
+    }
+}
+
+pub(super) struct WrapAroundMenuOption {
+    wrap_around: bool
+}
+
+impl WrapAroundMenuOption {
+
+    /// Creates and returns a new WrapAroundMenuOption
+    pub fn new() -> Self
+    {
+        Self{wrap_around: false}
+    }
+
+    pub fn value(self) -> bool
+    {
+        self.wrap_around
+    }
+}
+
+impl MenuOption for WrapAroundMenuOption {
+
+    fn option_name(&self) -> String {
+        "Wrap Around".to_owned()
+    }
+
+    fn current_value_name(&self) -> String {
+        if self.wrap_around {
+            "On".to_owned()
+        } else {
+            "Off".to_owned()
+        }
+    }
+
+    fn next_value(&mut self) -> Result<(),()> {
+        self.wrap_around = !self.wrap_around;
+        Ok(())
+    }
+
+    fn prev_value(&mut self) -> Result<(),()> {
+        self.next_value()
+    }
+
+    fn at_maximum(&self) -> bool {
+        false
+    }
+
+    fn at_minimum(&self) -> bool {
+        false
+    }
+
+    fn description(&self) -> Option<String> {
+        None
+    }
+}
+
+/// Whether the second player may swap sides after the first move
+pub(super) struct SwapRuleMenuOption {
+    swap_rule: bool
+}
+
+impl SwapRuleMenuOption {
+
+    /// Creates and returns a new SwapRuleMenuOption
+    pub fn new() -> Self
+    {
+        Self{swap_rule: false}
+    }
+
+    pub fn value(self) -> bool
+    {
+        self.swap_rule
+    }
+}
+
+impl MenuOption for SwapRuleMenuOption {
+
+    fn option_name(&self) -> String {
+        "Swap Rule".to_owned()
+    }
+
+    fn current_value_name(&self) -> String {
+        if self.swap_rule {
+            "On".to_owned()
+        } else {
+            "Off".to_owned()
+        }
+    }
+
+    fn next_value(&mut self) -> Result<(),()> {
+        self.swap_rule = !self.swap_rule;
+        Ok(())
+    }
+
+    fn prev_value(&mut self) -> Result<(),()> {
+        self.next_value()
+    }
+
+    fn at_maximum(&self) -> bool {
+        false
+    }
+
+    fn at_minimum(&self) -> bool {
+        false
+    }
+
+    fn description(&self) -> Option<String> {
+        if self.swap_rule {
+            Some("After the first move, the second player may swap sides instead of replying. ".to_owned())
+        } else {
+            None
+        }
+    }
+}
+
+/// Which player plays Order in Order and Chaos
+pub(super) struct OrderRoleMenuOption {
+    order_role: OrderRole
+}
+
+impl OrderRoleMenuOption {
+
+    /// Creates and returns a new OrderRoleMenuOption
+    pub fn new() -> Self
+    {
+        Self{order_role: OrderRole::FirstPlayer}
+    }
+
+    pub fn value(&self) -> OrderRole
+    {
+        self.order_role
+    }
+}
+
+impl MenuOption for OrderRoleMenuOption {
+
+    fn option_name(&self) -> String {
+        "Order Player".to_owned()
+    }
+
+    fn current_value_name(&self) -> String {
+        match self.order_role {
+            OrderRole::FirstPlayer => "First to move".to_owned(),
+            OrderRole::PlayerX => "X".to_owned(),
+            OrderRole::PlayerO => "O".to_owned()
+        }
+    }
+
+    fn next_value(&mut self) -> Result<(),()> {
+        self.order_role = match self.order_role {
+            OrderRole::FirstPlayer => OrderRole::PlayerX,
+            OrderRole::PlayerX => OrderRole::PlayerO,
+            OrderRole::PlayerO => return Err(())
+        };
+        Ok(())
+    }
+
+    fn prev_value(&mut self) -> Result<(),()> {
+        self.order_role = match self.order_role {
+            OrderRole::FirstPlayer => return Err(()),
+            OrderRole::PlayerX => OrderRole::FirstPlayer,
+            OrderRole::PlayerO => OrderRole::PlayerX
+        };
+        Ok(())
+    }
+
+    fn at_maximum(&self) -> bool {
+        self.order_role == OrderRole::PlayerO
+    }
+
+    fn at_minimum(&self) -> bool {
+        self.order_role == OrderRole::FirstPlayer
+    }
+
+    fn description(&self) -> Option<String> {
+        None
+    }
+}
+
+/// Whether blocked spaces and handicap pieces are placed at random or chosen by the user
+pub(super) struct StartingPositionMenuOption {
+    chosen: bool
+}
+
+impl StartingPositionMenuOption {
+
+    /// Creates and returns a new StartingPositionMenuOption
+    pub fn new() -> Self
+    {
+        Self{chosen: false}
+    }
+
+    /// Returns true if the starting position is chosen by the user
+    pub fn value(self) -> bool
+    {
+        self.chosen
+    }
+}
+
+impl MenuOption for StartingPositionMenuOption {
+
+    fn option_name(&self) -> String {
+        "Starting Position".to_owned()
+    }
+
+    fn current_value_name(&self) -> String {
+        if self.chosen {
+            "Chosen".to_owned()
+        } else {
+            "Random".to_owned()
+        }
+    }
+
+    fn next_value(&mut self) -> Result<(),()> {
+        self.chosen = !self.chosen;
+        Ok(())
+    }
+
+    fn prev_value(&mut self) -> Result<(),()> {
+        self.next_value()
+    }
+
+    fn at_maximum(&self) -> bool {
+        false
+    }
+
+    fn at_minimum(&self) -> bool {
+        false
+    }
+
+    fn description(&self) -> Option<String> {
+        None
+    }
+}
+
+/// The colours and text styles boards are drawn in
+pub(super) struct ThemeMenuOption {
+    theme: Theme
+}
+
+impl ThemeMenuOption {
+
+    /// Creates and returns a new ThemeMenuOption, with the given theme selected
+    pub fn new(theme: Theme) -> Self
+    {
+        Self{theme}
+    }
+
+    pub fn value(&self) -> Theme
+    {
+        self.theme
+    }
+
+    /// Returns the index of the selected theme in [Theme::ALL]
+    fn index(&self) -> usize
+    {
+        Theme::ALL.iter().position(|theme|{*theme == self.theme}).unwrap_or(0)
+    }
+}
+
+impl MenuOption for ThemeMenuOption {
+
+    fn option_name(&self) -> String {
+        "Theme".to_owned()
+    }
+
+    fn current_value_name(&self) -> String {
+        self.theme.display_name().to_owned()
+    }
+
+    fn next_value(&mut self) -> Result<(),()> {
+        self.theme = *Theme::ALL.get(self.index() + 1).ok_or(())?;
+        Ok(())
+    }
+
+    fn prev_value(&mut self) -> Result<(),()> {
+        self.theme = Theme::ALL[self.index().checked_sub(1).ok_or(())?];
+        Ok(())
+    }
+
+    fn at_maximum(&self) -> bool {
+        self.index() == Theme::ALL.len() - 1
+    }
+
+    fn at_minimum(&self) -> bool {
+        self.index() == 0
+    }
+
+    fn description(&self) -> Option<String> {
+        None
+    }
+}
+
+/// A whole number setting that can be changed within a fixed range
+#[derive(Clone, Copy)]
+pub(super) struct NumberMenuOption {
+    name: &'static str,
+    value: usize,
+    min: usize,
+    max: usize
+}
+
+impl NumberMenuOption {
+
+    /// Creates and returns a new NumberMenuOption with the given display name,
+    /// starting at `value`, which can be changed from `min` to `max`
+    pub fn new(name: &'static str, value: usize, min: usize, max: usize) -> Self
+    {
+        Self{name, value: value.clamp(min, max), min, max}
+    }
+
+    pub fn value(self) -> usize
+    {
+        self.value
+    }
+}
+
+impl MenuOption for NumberMenuOption {
+
+    fn option_name(&self) -> String {
+        self.name.to_owned()
+    }
+
+    fn current_value_name(&self) -> String {
+        format!("{}", self.value)
+    }
+
+    fn next_value(&mut self) -> Result<(),()> {
+        if self.at_maximum() {
+            Err(())
+        } else {
+            self.value += 1;
+            Ok(())
+        }
+    }
+
+    fn prev_value(&mut self) -> Result<(),()> {
+        if self.at_minimum() {
+            Err(())
+        } else {
+            self.value -= 1;
+            Ok(())
+        }
+    }
+
+    fn at_maximum(&self) -> bool {
+        self.value >= self.max
+    }
+
+    fn at_minimum(&self) -> bool {
+        self.value <= self.min
+    }
+
+    fn description(&self) -> Option<String> {
+        None
+    }
+}
+
 pub(super) struct AutoquitValueMenuOption {
     selected_value: u32
 }
@@ -219,14 +674,14 @@ impl MenuOption for AutoquitModeMenuOption {
 
 pub(super) struct DifficultyMenuOption {
     selected_difficulty: i8,
-    player: ActivePlayer
+    player: Seat
 }
 
 impl DifficultyMenuOption {
     const DIFFICULTY_STEP: i8 = 5;
 
     /// Creates and returns a new DifficultyMenuOption for the specified player
-    pub fn new(player: ActivePlayer) -> Self
+    pub fn new(player: Seat) -> Self
     {
         Self{player, selected_difficulty: 85}
     }
@@ -283,20 +738,29 @@ impl MenuOption for DifficultyMenuOption {
 
 pub(super) struct PlayerTypeMenuOption{
     selected_player_type: PlayerType,
-    player: ActivePlayer
+    player: Seat,
+    allow_remote: bool
 }
 
 impl PlayerTypeMenuOption{
     /// Creates and returns a new PlayerTypeMenuOption for the specified player
-    pub fn new(player: ActivePlayer, default_type: PlayerType) -> Self
+    /// 
+    /// [PlayerType::Remote] can only be selected if `allow_remote` is true
+    pub fn new(player: Seat, default_type: PlayerType, allow_remote: bool) -> Self
     {
-        Self{player, selected_player_type: default_type}
+        Self{player, selected_player_type: default_type, allow_remote}
     }
     
     pub fn value(&self) -> &PlayerType
     {
         &self.selected_player_type
     }
+
+    /// Returns true if the selected player type is [PlayerType::AI]
+    pub fn is_ai(&self) -> bool
+    {
+        matches!(self.selected_player_type, PlayerType::AI(_))
+    }
 }
 
 impl MenuOption for PlayerTypeMenuOption{
@@ -304,7 +768,8 @@ impl MenuOption for PlayerTypeMenuOption{
     fn current_value_name(&self) -> String {
         match self.selected_player_type {
             PlayerType::Human => "Human".to_owned(),
-            PlayerType::AI(_) => "AI".to_owned()
+            PlayerType::AI(_) => "AI".to_owned(),
+            PlayerType::Remote => "Remote".to_owned()
         }
     }
 
@@ -315,13 +780,19 @@ impl MenuOption for PlayerTypeMenuOption{
     fn next_value(&mut self) -> Result<(),()> {
         match self.selected_player_type {
             PlayerType::Human => self.selected_player_type = PlayerType::AI(AiPlayer::default()),
-            PlayerType::AI(_) => self.selected_player_type = PlayerType::Human
+            PlayerType::AI(_) if self.allow_remote => self.selected_player_type = PlayerType::Remote,
+            PlayerType::AI(_) | PlayerType::Remote => self.selected_player_type = PlayerType::Human
         }
         Ok(())
     }
 
     fn prev_value(&mut self) -> Result<(),()> {
-        self.next_value()
+        match self.selected_player_type {
+            PlayerType::Human if self.allow_remote => self.selected_player_type = PlayerType::Remote,
+            PlayerType::Human | PlayerType::Remote => self.selected_player_type = PlayerType::AI(AiPlayer::default()),
+            PlayerType::AI(_) => self.selected_player_type = PlayerType::Human
+        }
+        Ok(())
     }
 
     fn at_maximum(&self) -> bool {
@@ -0,0 +1,135 @@
+//! UI implementations for watching a hosted game
+
+use std::io::Write;
+
+use crossterm::{
+    style::Print,
+    cursor::{MoveToColumn, MoveToRow, MoveToNextLine},
+    terminal::{Clear, ClearType},
+    event::Event,
+    QueueableCommand,
+    ExecutableCommand
+};
+
+use crate::{
+    gameboard::BoardSpace,
+    network::{
+        Connection,
+        NetworkError,
+        protocol::Message
+    },
+    multiplayer::Seat
+};
+
+use super::key_bindings::Action;
+
+impl super::UI {
+
+    /// Watches a game hosted by another `tactic` instance until the host stops or the user quits
+    ///
+    /// Draws the board as moves are made, along with the scores shown in the play again menu.
+    /// No input is accepted other than the quit key or Ctrl+C to stop watching.
+    /// 
+    /// Spectators never play, so afterwards [UI::game_loop] will return immediately
+    /// without clearing the last board watched.
+    pub fn spectate(&mut self, address: &str) -> crossterm::Result<()>
+    {
+        self.exit_flag = true;
+
+        self.draw_network_status(&format!("Connecting to {}...", address))?;
+
+        let connection = Connection::connect(address)?;
+        let settings = connection.spectator_handshake()?;
+        self.game_mode = settings.game_mode;
+        self.game_autoquit_mode = settings.autoquit_mode;
+        self.game_autoquit_value = settings.autoquit_value;
+
+        self.terminal.execute(Clear(ClearType::All))?;
+
+        loop {
+            self.draw_spectator_view()?;
+
+            let message = match self.wait_for_spectator_message(&connection)? {
+                Some(message) => message,
+                None => break
+            };
+
+            match message {
+                Message::Board(board, active_player) => {
+                    self.game_board = board;
+                    self.active_player = active_player;
+                    self.resigned_player = None;
+                    self.move_history.clear();
+                },
+                Message::Move(location) if self.game_board.space(location) == &BoardSpace::Empty => {
+                    *self.game_board.space_mut(location) = self.active_player.get_board_space();
+                    self.move_history.push(location);
+                    self.active_player.switch();
+                },
+                Message::Score{player_x, player_o, draws} => {
+                    // networked games are classic games, which are only won by whole points
+                    self.half_points[Seat::X.index()] = player_x * 2;
+                    self.half_points[Seat::O.index()] = player_o * 2;
+                    self.games_won = player_x + player_o;
+                    self.number_of_draws = draws;
+                },
+                Message::Resigned(side) => {
+                    self.resigned_player = Some(side);
+                },
+                Message::Quit => break,
+                other => return Err(NetworkError::UnexpectedMessage(other).into())
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Blocks until a message arrives from the host or the user quits
+    ///
+    /// Returns `None` if the user quits or the host disconnects.
+    /// Redraws the spectator view if the terminal is resized while waiting.
+    fn wait_for_spectator_message(&mut self, connection: &Connection) -> crossterm::Result<Option<Message>>
+    {
+        loop {
+            match connection.recv_timeout(Self::NETWORK_POLL_INTERVAL) {
+                Ok(Some(message)) => return Ok(Some(message)),
+                Ok(None) => {},
+                Err(NetworkError::Disconnected) => return Ok(None),
+                Err(error) => return Err(error.into())
+            }
+
+            if self.terminal.poll_event(Self::NETWORK_POLL_INTERVAL)? {
+                match self.terminal.read_event()? {
+                    event if Self::is_quit_event(&self.key_bindings, &event) => return Ok(None),
+                    //ignore size returned by resize event as it is currently (as of crossterm 0.25) wrong on Windows
+                    Event::Resize(_, _) => {
+                        (self.terminal_x_size, self.terminal_y_size) = self.terminal.size()?;
+                        self.terminal.execute(Clear(ClearType::All))?;
+                        self.draw_spectator_view()?;
+                    },
+                    _ => {
+                        //ignore other events; spectators can't play
+                    }
+                }
+            }
+        }
+    }
+
+    /// Draws the board, whose turn it is or the outcome of the game, and the scores
+    fn draw_spectator_view(&mut self) -> crossterm::Result<()>
+    {
+        self.terminal
+            .queue(MoveToColumn(0))?
+            .queue(MoveToRow(0))?
+            .flush()?;
+
+        let (_, summary_left) = self.draw_game_summary(&format!("{}'s turn", self.active_player.get_char()))?;
+
+        self.terminal
+            .queue(MoveToNextLine(2))?
+            .queue(MoveToColumn(summary_left))?
+            .queue(Print(format!("Spectating. Press {} to stop watching", self.key_bindings.key_name(Action::Quit))))?
+            .flush()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,224 @@
+//! UI implementations for setting up the blocked spaces and handicap pieces a game starts with
+
+use crossterm::{
+    terminal::{Clear, ClearType},
+    event::{Event, KeyEvent, KeyCode, KeyModifiers},
+    ExecutableCommand
+};
+use rand::seq::SliceRandom;
+
+use crate::{
+    gameboard::{GameBoard, BoardSpace, BoardSpaceLocation},
+    game_outcome::GameOutcome
+};
+
+use super::key_bindings::Action;
+
+impl super::UI {
+
+    /// The largest number of blocked spaces a game can start with
+    pub(super) const MAX_BLOCKED_SPACES: usize = 3;
+    /// The largest number of handicap pieces either player can start with
+    pub(super) const MAX_HANDICAP_PIECES: usize = 2;
+
+    /// Places the blocked spaces and handicap pieces chosen in the setup menu on the game board
+    ///
+    /// If the starting position is chosen by the user, they are asked to choose it before the
+    /// first game, and the same position is used for every game after that. Otherwise, a new
+    /// random position is used for each game.
+    ///
+    /// Sets the exit flag if the user quits while choosing the starting position.
+    pub(super) fn set_up_starting_position(&mut self) -> crossterm::Result<()>
+    {
+        let starting_position = if !self.variant_options.choose_starting_position {
+            random_starting_position(&self.game_board, &self.starting_pieces())
+        } else if let Some(starting_position) = &self.starting_position {
+            starting_position.clone()
+        } else {
+            match self.choose_starting_position()? {
+                Some(starting_position) => {
+                    self.starting_position = Some(starting_position.clone());
+                    starting_position
+                },
+                None => {
+                    self.exit_flag = true;
+                    return Ok(());
+                }
+            }
+        };
+
+        for (location, space) in starting_position {
+            *self.game_board.space_mut(location) = space;
+        }
+        Ok(())
+    }
+
+    /// Returns the blocked spaces and handicap pieces chosen in the setup menu,
+    /// in the order they are placed
+    fn starting_pieces(&self) -> Vec<BoardSpace>
+    {
+        let options = &self.variant_options;
+        [
+            (BoardSpace::Blocked, options.blocked_spaces),
+            (BoardSpace::X, options.handicap_x),
+            (BoardSpace::O, options.handicap_o)
+        ].into_iter()
+            .flat_map(|(space, count)|{std::iter::repeat_n(space, count)})
+            .collect()
+    }
+
+    /// Lets the user place each of the starting pieces on the game board
+    ///
+    /// The pieces are left on the game board. A piece can't be placed where it would
+    /// finish the game before it starts.
+    ///
+    /// Returns the chosen position, or [None] if the user quits.
+    fn choose_starting_position(&mut self) -> crossterm::Result<Option<Vec<(BoardSpaceLocation, BoardSpace)>>>
+    {
+        let pieces = self.starting_pieces();
+        let mut starting_position = Vec::new();
+
+        self.terminal.execute(Clear(ClearType::All))?;
+
+        for (index, piece) in pieces.iter().enumerate() {
+            // the number of pieces of this type, and which of them is being placed
+            let count = pieces.iter().filter(|other|{*other == piece}).count();
+            let number = pieces[..index].iter().filter(|other|{*other == piece}).count() + 1;
+            let prompt = match piece {
+                BoardSpace::Blocked => format!("Choose a space to block ({} of {})", number, count),
+                _ => format!("Choose a space for {}'s handicap piece ({} of {})", piece, number, count)
+            };
+
+            loop {
+                let cursor = BoardSpaceLocation::from_coordinates((self.cursor_x_pos, self.cursor_y_pos));
+                let help_text = self.selection_help("space", None, "place", "quit");
+                self.draw_classic_screen("Setting up.", &prompt, &help_text, Some(cursor))?;
+
+                // true if the piece should be placed in the selected space
+                let place_piece = match self.terminal.read_event()? {
+                    Event::Resize(_, _) => {
+                        (self.terminal_x_size, self.terminal_y_size) = self.terminal.size()?;
+                        self.terminal.execute(Clear(ClearType::All))?;
+                        false
+                    },
+                    Event::Key(KeyEvent{code: KeyCode::Char('c'), modifiers: KeyModifiers::CONTROL, ..}) => {
+                        return Ok(None);
+                    },
+                    Event::Key(key_event) => match self.key_bindings.action(&key_event) {
+                        Some(Action::Right) => {
+                            self.move_cursor_right();
+                            false
+                        },
+                        Some(Action::Left) => {
+                            self.move_cursor_left();
+                            false
+                        },
+                        Some(Action::Down) => {
+                            self.move_cursor_down();
+                            false
+                        },
+                        Some(Action::Up) => {
+                            self.move_cursor_up();
+                            false
+                        },
+                        Some(Action::Place) => true,
+                        Some(Action::PlaceAt(location)) => {
+                            (self.cursor_x_pos, self.cursor_y_pos) = location.as_coordinates();
+                            true
+                        },
+                        Some(Action::Quit) => {
+                            return Ok(None);
+                        },
+                        _ => false
+                    },
+                    Event::Mouse(mouse_event) => self.click_board_space(mouse_event),
+                    _ => false
+                };
+
+                let location = BoardSpaceLocation::from_coordinates((self.cursor_x_pos, self.cursor_y_pos));
+                if !place_piece || self.game_board.space(location) != &BoardSpace::Empty {
+                    continue;
+                }
+
+                *self.game_board.space_mut(location) = piece.clone();
+                if self.game_board.game_outcome() == GameOutcome::Incomplete {
+                    starting_position.push((location, piece.clone()));
+                    break;
+                }
+                // this piece would finish the game, so it can't go here
+                *self.game_board.space_mut(location) = BoardSpace::Empty;
+            }
+        }
+
+        self.reset_cursor_pos();
+        Ok(Some(starting_position))
+    }
+}
+
+/// How many random starting positions to try before giving up on finding one
+/// where the game isn't already over
+const RANDOM_POSITION_ATTEMPTS: usize = 100;
+
+/// Returns a random placement of the given pieces on empty spaces of the given board
+///
+/// Placements that would finish the game before it starts are avoided; if none can be found,
+/// the game starts without any starting pieces.
+fn random_starting_position(board: &GameBoard, pieces: &[BoardSpace]) -> Vec<(BoardSpaceLocation, BoardSpace)>
+{
+    let mut locations: Vec<BoardSpaceLocation> = board.all_spaces()
+        .filter(|(_, space)|{**space == BoardSpace::Empty})
+        .map(|(location, _)|{location})
+        .collect();
+    let mut rng = rand::thread_rng();
+
+    for _ in 0..RANDOM_POSITION_ATTEMPTS {
+        locations.shuffle(&mut rng);
+        let starting_position: Vec<(BoardSpaceLocation, BoardSpace)> =
+            locations.iter().copied().zip(pieces.iter().cloned()).collect();
+
+        let mut board = board.clone();
+        for (location, space) in &starting_position {
+            *board.space_mut(*location) = space.clone();
+        }
+        if board.game_outcome() == GameOutcome::Incomplete {
+            return starting_position;
+        }
+    }
+    Vec::new()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::rules::Rules;
+
+    #[test]
+    fn random_position_places_every_piece_without_finishing_the_game()
+    {
+        let pieces = [BoardSpace::Blocked, BoardSpace::Blocked, BoardSpace::Blocked,
+            BoardSpace::X, BoardSpace::X, BoardSpace::O, BoardSpace::O];
+        for _ in 0..200 {
+            let starting_position = random_starting_position(&GameBoard::new(), &pieces);
+            assert_eq!(starting_position.len(), pieces.len());
+
+            let mut board = GameBoard::new();
+            for (location, space) in &starting_position {
+                assert_eq!(*board.space(*location), BoardSpace::Empty, "each piece should have its own space");
+                *board.space_mut(*location) = space.clone();
+            }
+            assert_eq!(board.game_outcome(), GameOutcome::Incomplete);
+        }
+    }
+
+    #[test]
+    fn random_position_is_empty_if_every_placement_finishes_the_game()
+    {
+        // a single X anywhere wins
+        let rules = Rules::parse("lines\npattern dot anywhere\nX").expect("rules should be valid");
+        let board = GameBoard::with_rules(Arc::new(rules));
+        assert_eq!(random_starting_position(&board, &[BoardSpace::X]), Vec::new());
+        assert_eq!(random_starting_position(&board, &[BoardSpace::Blocked]).len(), 1);
+    }
+}
@@ -0,0 +1,110 @@
+//! Terminals that the UI can be drawn on and read input from
+//!
+//! The UI writes ANSI escape sequences (via crossterm commands) to a [Terminal] and reads
+//! [Event]s from it, so the same game can be played in the local terminal or over a socket.
+
+use std::{
+    io::{self, stdout, Stdout, Write},
+    time::Duration
+};
+
+use crossterm::{
+    event::{self, Event, EnableMouseCapture, DisableMouseCapture},
+    terminal::{self, EnterAlternateScreen, LeaveAlternateScreen},
+    QueueableCommand
+};
+
+// Declare socket module which contains
+// the terminal for sessions played over a TCP connection
+mod socket;
+pub use socket::SocketTerminal;
+
+/// Somewhere the UI can be drawn and read input from
+///
+/// Output is written through the [Write] implementation, normally using crossterm commands.
+pub trait Terminal: Write + Send {
+    /// Prepares the terminal for drawing the UI (raw mode, an alternate screen and mouse capture)
+    fn enter(&mut self) -> io::Result<()>;
+
+    /// Returns the terminal to how it was before [Terminal::enter] was called
+    fn leave(&mut self) -> io::Result<()>;
+
+    /// Returns the size of the terminal as `(columns, rows)`
+    fn size(&self) -> io::Result<(u16, u16)>;
+
+    /// Blocks until an [Event] is available, then returns it
+    fn read_event(&mut self) -> io::Result<Event>;
+
+    /// Returns `true` if an [Event] becomes available within `timeout`
+    ///
+    /// The event can then be read with [Terminal::read_event] without blocking
+    fn poll_event(&mut self, timeout: Duration) -> io::Result<bool>;
+}
+
+/// The terminal the program is running in
+pub struct LocalTerminal {
+    stdout: Stdout
+}
+
+impl LocalTerminal {
+    pub fn new() -> Self
+    {
+        Self{stdout: stdout()}
+    }
+}
+
+impl Default for LocalTerminal {
+    fn default() -> Self
+    {
+        Self::new()
+    }
+}
+
+impl Write for LocalTerminal {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize>
+    {
+        self.stdout.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()>
+    {
+        self.stdout.flush()
+    }
+}
+
+impl Terminal for LocalTerminal {
+    fn enter(&mut self) -> io::Result<()>
+    {
+        terminal::enable_raw_mode()?;
+        self.stdout
+            .queue(EnterAlternateScreen)?
+            .queue(EnableMouseCapture)?
+            .flush()?;
+        Ok(())
+    }
+
+    fn leave(&mut self) -> io::Result<()>
+    {
+        self.stdout
+            .queue(DisableMouseCapture)?
+            .queue(LeaveAlternateScreen)?
+            .flush()?;
+        terminal::disable_raw_mode()?;
+        Ok(())
+    }
+
+    fn size(&self) -> io::Result<(u16, u16)>
+    {
+        terminal::size()
+    }
+
+    fn read_event(&mut self) -> io::Result<Event>
+    {
+        event::read()
+    }
+
+    fn poll_event(&mut self, timeout: Duration) -> io::Result<bool>
+    {
+        event::poll(timeout)
+    }
+}
@@ -0,0 +1,371 @@
+//! A terminal on the other end of a TCP connection
+//!
+//! Output is sent as-is, so the client needs a terminal that understands ANSI escape
+//! sequences. Input is read as raw bytes and parsed into key presses and mouse events
+//! (reported in SGR format, which the client is asked for along with mouse capture).
+//!
+//! Telnet clients are asked to switch to character mode, to stop echoing input, and to
+//! report their window size. Other clients, like `nc`, must put the local terminal into
+//! raw mode themselves (e.g. `stty raw -echo`); the negotiation bytes they receive are
+//! cleared from the screen along with everything else when the UI is first drawn.
+
+use std::{
+    io::{self, BufWriter, ErrorKind, Read, Write},
+    net::{Shutdown, TcpStream},
+    sync::{
+        mpsc::{self, Receiver, RecvTimeoutError, Sender},
+        Arc, Mutex, PoisonError
+    },
+    thread,
+    time::Duration
+};
+
+use crossterm::{
+    cursor,
+    event::{
+        DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyModifiers,
+        MouseButton, MouseEvent, MouseEventKind
+    },
+    terminal::{Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen},
+    QueueableCommand
+};
+
+use super::Terminal;
+
+/// The size assumed for clients that don't report their window size
+const DEFAULT_SIZE: (u16, u16) = (80, 24);
+
+/// The most bytes of a telnet subnegotiation kept while waiting for its end
+///
+/// The only subnegotiation used, a window size report, is 9 bytes long (or up to 13 if its
+/// sizes contain escaped `IAC` bytes), so longer ones are dropped rather than buffered forever.
+const MAX_SUBNEGOTIATION_LENGTH: usize = 64;
+
+/// Telnet "interpret as command" byte, which starts every telnet command
+const IAC: u8 = 255;
+const WILL: u8 = 251;
+const DO: u8 = 253;
+/// Start of subnegotiation
+const SB: u8 = 250;
+/// End of subnegotiation
+const SE: u8 = 240;
+const ECHO: u8 = 1;
+const SUPPRESS_GO_AHEAD: u8 = 3;
+/// Negotiate About Window Size
+const NAWS: u8 = 31;
+
+/// A terminal on the other end of a [TcpStream]
+pub struct SocketTerminal {
+    stream: TcpStream,
+    output: BufWriter<TcpStream>,
+    events: Receiver<Event>,
+    /// An event returned by [Terminal::poll_event] but not yet read
+    pending: Option<Event>,
+    size: Arc<Mutex<(u16, u16)>>
+}
+
+impl SocketTerminal {
+
+    /// Wraps the given [TcpStream], spawning a thread to parse incoming key presses and mouse events
+    pub fn new(stream: TcpStream) -> io::Result<Self>
+    {
+        let output = BufWriter::new(stream.try_clone()?);
+        let reader = stream.try_clone()?;
+        let size = Arc::new(Mutex::new(DEFAULT_SIZE));
+        let (sender, events) = mpsc::channel();
+
+        let thread_size = Arc::clone(&size);
+        thread::spawn(move ||{
+            read_input(reader, &sender, &thread_size);
+        });
+
+        Ok(Self{stream, output, events, pending: None, size})
+    }
+}
+
+impl Write for SocketTerminal {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize>
+    {
+        self.output.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()>
+    {
+        self.output.flush()
+    }
+}
+
+impl Terminal for SocketTerminal {
+    fn enter(&mut self) -> io::Result<()>
+    {
+        self.output
+            .queue(EnterAlternateScreen)?
+            .queue(EnableMouseCapture)?;
+        self.output.write_all(&[
+            IAC, WILL, ECHO,
+            IAC, WILL, SUPPRESS_GO_AHEAD,
+            IAC, DO, NAWS
+        ])?;
+        self.output
+            .queue(Clear(ClearType::All))?
+            .flush()
+    }
+
+    fn leave(&mut self) -> io::Result<()>
+    {
+        // the client may already have disconnected, in which case there is nothing to restore
+        let _ = self.output
+            .queue(DisableMouseCapture)
+            .and_then(|output|{output.queue(LeaveAlternateScreen)})
+            .and_then(|output|{output.queue(cursor::Show)})
+            .and_then(|output|{output.flush()});
+        let _ = self.stream.shutdown(Shutdown::Both);
+        Ok(())
+    }
+
+    fn size(&self) -> io::Result<(u16, u16)>
+    {
+        Ok(*self.size.lock().unwrap_or_else(PoisonError::into_inner))
+    }
+
+    fn read_event(&mut self) -> io::Result<Event>
+    {
+        match self.pending.take() {
+            Some(event) => Ok(event),
+            None => self.events.recv().map_err(|_|{disconnected()})
+        }
+    }
+
+    fn poll_event(&mut self, timeout: Duration) -> io::Result<bool>
+    {
+        if self.pending.is_some() {
+            return Ok(true);
+        }
+        match self.events.recv_timeout(timeout) {
+            Ok(event) => {
+                self.pending = Some(event);
+                Ok(true)
+            },
+            Err(RecvTimeoutError::Timeout) => Ok(false),
+            Err(RecvTimeoutError::Disconnected) => Err(disconnected())
+        }
+    }
+}
+
+fn disconnected() -> io::Error
+{
+    io::Error::new(ErrorKind::UnexpectedEof, "the client disconnected")
+}
+
+/// Reads from the stream until it closes, sending each parsed [Event]
+///
+/// Window size reports also update `size`
+fn read_input(mut reader: TcpStream, sender: &Sender<Event>, size: &Mutex<(u16, u16)>)
+{
+    let mut input: Vec<u8> = Vec::new();
+    let mut buffer = [0; 256];
+
+    loop {
+        let bytes_read = match reader.read(&mut buffer) {
+            Ok(0) | Err(_) => return,
+            Ok(bytes_read) => bytes_read
+        };
+        input.extend_from_slice(&buffer[..bytes_read]);
+
+        let mut start = 0;
+        while let Some((event, length)) = parse_input(&input[start..]) {
+            start += length;
+            if let Some(event) = event {
+                if let Event::Resize(columns, rows) = event {
+                    *size.lock().unwrap_or_else(PoisonError::into_inner) = (columns, rows);
+                }
+                // stop reading once the terminal has been dropped
+                if sender.send(event).is_err() {
+                    return;
+                }
+            }
+        }
+        input.drain(..start);
+    }
+}
+
+/// Parses the first key press, mouse event or telnet command in `input`
+///
+/// Returns the resulting [Event] (if any) along with the number of bytes used,
+/// or `None` if more input is needed.
+fn parse_input(input: &[u8]) -> Option<(Option<Event>, usize)>
+{
+    let key = |code: KeyCode, modifiers: KeyModifiers|{Some(Event::Key(KeyEvent::new(code, modifiers)))};
+
+    match *input.first()? {
+        IAC => parse_telnet_command(input),
+        0x1B => Some(parse_escape_sequence(input)),
+        // telnet clients send Enter as "\r\n" or "\r\0"
+        b'\r' => match input.get(1) {
+            Some(b'\n' | b'\0') => Some((key(KeyCode::Enter, KeyModifiers::NONE), 2)),
+            _ => Some((key(KeyCode::Enter, KeyModifiers::NONE), 1))
+        },
+        b'\n' => Some((key(KeyCode::Enter, KeyModifiers::NONE), 1)),
+        b'\t' => Some((key(KeyCode::Tab, KeyModifiers::NONE), 1)),
+        0x08 | 0x7F => Some((key(KeyCode::Backspace, KeyModifiers::NONE), 1)),
+        // Ctrl+A to Ctrl+Z
+        control @ 0x01..=0x1A => {
+            Some((key(KeyCode::Char((b'a' + control - 1) as char), KeyModifiers::CONTROL), 1))
+        },
+        byte if byte.is_ascii_uppercase() => Some((key(KeyCode::Char(byte as char), KeyModifiers::SHIFT), 1)),
+        byte if byte.is_ascii_graphic() || byte == b' ' => {
+            Some((key(KeyCode::Char(byte as char), KeyModifiers::NONE), 1))
+        },
+        byte if byte >= 0x80 => {
+            let length = match byte {
+                0xC0..=0xDF => 2,
+                0xE0..=0xEF => 3,
+                0xF0..=0xF7 => 4,
+                // not the start of a UTF-8 character
+                _ => return Some((None, 1))
+            };
+            let bytes = input.get(..length)?;
+            match std::str::from_utf8(bytes).ok().and_then(|text|{text.chars().next()}) {
+                Some(character) => Some((key(KeyCode::Char(character), KeyModifiers::NONE), length)),
+                None => Some((None, 1))
+            }
+        },
+        // ignore other control characters
+        _ => Some((None, 1))
+    }
+}
+
+/// Parses a telnet command starting with [IAC]
+///
+/// Window size reports become [Event::Resize]; other commands are ignored, as are
+/// subnegotiations longer than [MAX_SUBNEGOTIATION_LENGTH].
+fn parse_telnet_command(input: &[u8]) -> Option<(Option<Event>, usize)>
+{
+    match *input.get(1)? {
+        // WILL, WONT, DO and DONT are followed by an option
+        251..=254 => {
+            input.get(2)?;
+            Some((None, 3))
+        },
+        SB => {
+            let end = match input.windows(2).position(|bytes|{bytes == [IAC, SE]}) {
+                Some(end) => end,
+                // drop what has arrived of a subnegotiation that is too long to be useful
+                None if input.len() > MAX_SUBNEGOTIATION_LENGTH => return Some((None, input.len())),
+                None => return None
+            };
+            let event = match &input[2..end] {
+                &[NAWS, columns_high, columns_low, rows_high, rows_low] => {
+                    let columns = u16::from_be_bytes([columns_high, columns_low]);
+                    let rows = u16::from_be_bytes([rows_high, rows_low]);
+                    // a size of 0 means the client doesn't know
+                    (columns > 0 && rows > 0).then_some(Event::Resize(columns, rows))
+                },
+                _ => None
+            };
+            Some((event, end + 2))
+        },
+        _ => Some((None, 2))
+    }
+}
+
+/// Parses a key press or mouse event starting with an escape byte
+///
+/// Escape sequences are assumed to arrive whole, so an escape byte
+/// on its own is treated as the Esc key.
+fn parse_escape_sequence(input: &[u8]) -> (Option<Event>, usize)
+{
+    let key = |code: KeyCode|{Some(Event::Key(KeyEvent::new(code, KeyModifiers::NONE)))};
+
+    match input.get(1) {
+        // CSI sequences, e.g. "\x1b[A" or "\x1b[3~"
+        Some(b'[') => {
+            let end = match input[2..].iter().position(|byte|{(0x40..=0x7E).contains(byte)}) {
+                Some(offset) => offset + 2,
+                None => return (None, input.len())
+            };
+            if input[2] == b'<' {
+                return (parse_mouse_event(&input[3..end], input[end]), end + 1);
+            }
+            let code = match (&input[2..end], input[end]) {
+                (_, b'A') => KeyCode::Up,
+                (_, b'B') => KeyCode::Down,
+                (_, b'C') => KeyCode::Right,
+                (_, b'D') => KeyCode::Left,
+                (_, b'H') | (b"1" | b"7", b'~') => KeyCode::Home,
+                (_, b'F') | (b"4" | b"8", b'~') => KeyCode::End,
+                (b"2", b'~') => KeyCode::Insert,
+                (b"3", b'~') => KeyCode::Delete,
+                (b"5", b'~') => KeyCode::PageUp,
+                (b"6", b'~') => KeyCode::PageDown,
+                _ => return (None, end + 1)
+            };
+            (key(code), end + 1)
+        },
+        // SS3 sequences, sent for arrow keys in application cursor mode, e.g. "\x1bOA"
+        Some(b'O') => {
+            let code = match input.get(2) {
+                Some(b'A') => KeyCode::Up,
+                Some(b'B') => KeyCode::Down,
+                Some(b'C') => KeyCode::Right,
+                Some(b'D') => KeyCode::Left,
+                Some(b'H') => KeyCode::Home,
+                Some(b'F') => KeyCode::End,
+                Some(_) => return (None, 3),
+                None => return (key(KeyCode::Esc), 1)
+            };
+            (key(code), 3)
+        },
+        _ => (key(KeyCode::Esc), 1)
+    }
+}
+
+/// Parses the parameters and final byte of an SGR mouse report, e.g. `"0;12;5"` and `b'M'`
+/// from `"\x1b[<0;12;5M"`
+///
+/// The parameters are the button (with flags for modifiers, dragging and the scroll wheel),
+/// then the column and row counting from 1. The final byte is `M` for a press and `m` for
+/// a release.
+fn parse_mouse_event(parameters: &[u8], final_byte: u8) -> Option<Event>
+{
+    let parameters: Vec<u16> = std::str::from_utf8(parameters).ok()?
+        .split(';')
+        .map(|parameter|{parameter.parse().ok()})
+        .collect::<Option<_>>()?;
+    let &[code, column, row] = &parameters[..] else {
+        return None;
+    };
+
+    let button = match code & 0b11 {
+        0 => MouseButton::Left,
+        1 => MouseButton::Middle,
+        _ => MouseButton::Right
+    };
+    let kind = match (code & 0b110_0000, final_byte) {
+        (0b100_0000, _) if code & 1 == 0 => MouseEventKind::ScrollUp,
+        (0b100_0000, _) => MouseEventKind::ScrollDown,
+        // no button is held while the mouse is moved
+        (0b010_0000, _) if code & 0b11 == 3 => MouseEventKind::Moved,
+        (0b010_0000, _) => MouseEventKind::Drag(button),
+        (_, b'm') => MouseEventKind::Up(button),
+        _ => MouseEventKind::Down(button)
+    };
+
+    let mut modifiers = KeyModifiers::NONE;
+    if code & 0b0_0100 != 0 {
+        modifiers |= KeyModifiers::SHIFT;
+    }
+    if code & 0b0_1000 != 0 {
+        modifiers |= KeyModifiers::ALT;
+    }
+    if code & 0b1_0000 != 0 {
+        modifiers |= KeyModifiers::CONTROL;
+    }
+
+    Some(Event::Mouse(MouseEvent{
+        kind,
+        column: column.saturating_sub(1),
+        row: row.saturating_sub(1),
+        modifiers
+    }))
+}
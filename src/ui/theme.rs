@@ -0,0 +1,153 @@
+//! Themes, which decide the colours and text styles boards are drawn in
+
+use crossterm::style::{Color, ContentStyle, Stylize};
+
+use crate::multiplayer::Seat;
+
+/// The colours and text styles boards are drawn in
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum Theme {
+    /// X in cyan and O in yellow, with the winning line shown in reverse video
+    #[default]
+    Classic,
+    /// Bold pieces and strong background colours for the cursor and the winning line
+    HighContrast,
+    /// Blue and orange pieces, which can be told apart with the common forms of colour blindness
+    ColourBlindSafe,
+    /// No colours, only bold, underlined, dim and reverse video text
+    Monochrome
+}
+
+impl Theme {
+
+    /// Every theme, in the order they are listed in the setup menu
+    pub const ALL: [Theme; 4] = [Theme::Classic, Theme::HighContrast, Theme::ColourBlindSafe, Theme::Monochrome];
+
+    /// Returns the theme to use for the local terminal
+    ///
+    /// This is [Theme::Monochrome] if the `NO_COLOR` environment variable is set (to anything
+    /// other than an empty string), or [Theme::Classic] otherwise. See <https://no-color.org>.
+    pub fn from_environment() -> Self
+    {
+        match std::env::var_os("NO_COLOR") {
+            Some(value) if !value.is_empty() => Theme::Monochrome,
+            _ => Theme::Classic
+        }
+    }
+
+    /// Returns the theme with the given name on the command line, if any
+    pub fn from_name(name: &str) -> Option<Self>
+    {
+        Self::ALL.into_iter().find(|theme|{theme.command_line_name() == name})
+    }
+
+    /// Returns the name used to choose this theme on the command line
+    pub fn command_line_name(&self) -> &'static str
+    {
+        match self {
+            Theme::Classic => "classic",
+            Theme::HighContrast => "high-contrast",
+            Theme::ColourBlindSafe => "colour-blind",
+            Theme::Monochrome => "monochrome"
+        }
+    }
+
+    /// Returns the name of this theme as shown in the setup menu
+    pub fn display_name(&self) -> &'static str
+    {
+        match self {
+            Theme::Classic => "Classic",
+            Theme::HighContrast => "High contrast",
+            Theme::ColourBlindSafe => "Colour-blind safe",
+            Theme::Monochrome => "Monochrome"
+        }
+    }
+
+    /// Returns the style of the pieces of the player in the given seat
+    pub fn seat(&self, seat: Seat) -> ContentStyle
+    {
+        let style = ContentStyle::new();
+        match (self, seat) {
+            (Theme::Monochrome, _) => style,
+            (Theme::Classic, Seat::X) => style.cyan(),
+            (Theme::Classic, Seat::O) => style.yellow(),
+            (Theme::HighContrast, Seat::X) => style.white().bold(),
+            (Theme::HighContrast, Seat::O) => style.yellow().bold(),
+            (Theme::ColourBlindSafe, Seat::X) => style.with(Color::AnsiValue(33)),
+            (Theme::ColourBlindSafe, Seat::O) => style.with(Color::AnsiValue(208)),
+            (Theme::ColourBlindSafe, Seat::Triangle) => style.with(Color::AnsiValue(36)),
+            (Theme::ColourBlindSafe, Seat::Square) => style.with(Color::AnsiValue(169)),
+            (_, Seat::Triangle) => style.green(),
+            (_, Seat::Square) => style.magenta()
+        }
+    }
+
+    /// Returns the style of the given symbol when it is drawn in a space
+    ///
+    /// Pieces are styled like the [seat](Self::seat) they belong to; other symbols are unstyled.
+    pub fn symbol(&self, symbol: char) -> ContentStyle
+    {
+        match Seat::first(Seat::COUNT).find(|seat|{seat.get_char() == symbol}) {
+            Some(seat) => self.seat(seat),
+            None => ContentStyle::new()
+        }
+    }
+
+    /// Returns the style added to the space the cursor is in
+    pub fn cursor(&self) -> ContentStyle
+    {
+        let style = ContentStyle::new();
+        match self {
+            Theme::Classic => style.on_dark_grey(),
+            Theme::HighContrast => style.on_blue(),
+            Theme::ColourBlindSafe => style.on(Color::AnsiValue(238)),
+            Theme::Monochrome => style.underlined()
+        }
+    }
+
+    /// Returns the style added to the space of the last move
+    pub fn last_move(&self) -> ContentStyle
+    {
+        let style = ContentStyle::new();
+        match self {
+            Theme::Classic | Theme::ColourBlindSafe => style.underlined(),
+            Theme::HighContrast => style.underlined().bold(),
+            Theme::Monochrome => style.bold()
+        }
+    }
+
+    /// Returns the style of the spaces of a winning line, which replaces any other style
+    pub fn win_line(&self) -> ContentStyle
+    {
+        let style = ContentStyle::new();
+        match self {
+            Theme::Classic | Theme::Monochrome => style.negative(),
+            Theme::HighContrast => style.black().on_yellow().bold(),
+            Theme::ColourBlindSafe => style.negative().bold()
+        }
+    }
+
+    /// Returns the style of spaces and boards that can't be played in, such as blocked spaces
+    pub fn inactive(&self) -> ContentStyle
+    {
+        let style = ContentStyle::new();
+        match self {
+            Theme::Classic | Theme::ColourBlindSafe => style.dark_grey(),
+            Theme::HighContrast => style.grey(),
+            Theme::Monochrome => style.dim()
+        }
+    }
+}
+
+/// Returns `style` with the colours and attributes of `overlay` added to it
+///
+/// The colours of `overlay` replace those of `style`, where it has them.
+pub fn combine(style: ContentStyle, overlay: ContentStyle) -> ContentStyle
+{
+    ContentStyle{
+        foreground_color: overlay.foreground_color.or(style.foreground_color),
+        background_color: overlay.background_color.or(style.background_color),
+        underline_color: overlay.underline_color.or(style.underline_color),
+        attributes: style.attributes | overlay.attributes
+    }
+}
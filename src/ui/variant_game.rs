@@ -0,0 +1,843 @@
+//! UI implementations for playing [Variant]s
+//!
+//! Variants are drawn from the [BoardView]s they describe, so the same game loop, rendering and
+//! cursor navigation work for every variant.
+
+use std::{
+    io::{self, Write},
+    sync::mpsc,
+    thread,
+    time::Duration
+};
+
+use crossterm::{
+    terminal::{Clear, ClearType},
+    style::{Print, PrintStyledContent, ContentStyle, Stylize},
+    cursor::{self, MoveTo},
+    event::{Event, KeyEvent, KeyCode, KeyModifiers, MouseEvent, MouseEventKind, MouseButton},
+    QueueableCommand,
+    ExecutableCommand
+};
+
+use crate::{
+    gameboard::{BoardSpace, BoardSpaceLocation},
+    player_type::PlayerType,
+    active_player::ActivePlayer,
+    ai::{AiPlayer, AiError},
+    variants::{self, Variant, Move, Outcome, BoardView, CellStyle, Layout}
+};
+
+use super::{key_bindings::Action, theme, board_geometry::BoardGeometry};
+
+/// The state of a variant game before a move, which undoing the move returns to
+pub(super) struct VariantSnapshot {
+    variant: Box<dyn Variant>,
+    /// the number of moves played before the move
+    moves: usize,
+    /// the cell of the move before it, if any
+    last_move: Option<usize>,
+    swap_available: bool,
+    sides_swapped: bool
+}
+
+/// A direction the cursor can be moved in
+#[derive(Debug, Clone, Copy)]
+enum Direction {
+    Up,
+    Down,
+    Left,
+    Right
+}
+
+impl super::UI {
+
+    /// How often to check for input while an AI player is choosing a move
+    pub(super) const AI_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+    /// How long a dropped piece is shown in each cell it falls past
+    const DROP_FRAME_DURATION: Duration = Duration::from_millis(40);
+
+    /// The game loop for game modes played on a [Variant]
+    ///
+    /// Starts a new game of the variant for the current game mode, then alternates between players
+    /// until either the game is finished or the user quits the game.
+    pub fn variant_game_loop(&mut self) -> crossterm::Result<Outcome>
+    {
+        // don't start (or clear the board for) a new game if the user has already quit
+        if self.exit_flag {
+            return Ok(self.variant.as_ref().map_or(Outcome::Incomplete, |variant|{variant.outcome()}));
+        }
+
+        (self.terminal_x_size, self.terminal_y_size) = self.terminal.size()?;
+
+        let variant = variants::new_variant(self.game_mode, &self.variant_options, self.active_player.clone())
+            .expect("variant game loop started for a classic game mode");
+        self.variant_cursor = Self::centre_cell(&variant.boards());
+        // some variants decide who moves first themselves, such as Order and Chaos
+        self.active_player = variant.active_player();
+        self.variant = Some(variant);
+        self.variant_selection = None;
+        self.variant_symbol = BoardSpace::X;
+        self.resigned_player = None;
+        self.variant_moves = 0;
+        self.swap_available = false;
+        self.sides_swapped = false;
+        self.variant_history.clear();
+        self.variant_last_move = None;
+
+        self.terminal.execute(Clear(ClearType::All))?;
+
+        let mut outcome = self.variant().outcome();
+        while !(outcome.game_finished() || self.exit_flag) {
+            self.select_legal_cell();
+            self.select_legal_number();
+            self.draw_variant_game()?;
+
+            match self.active_player_type() {
+                // only classic games are played over the network
+                PlayerType::Remote => {
+                    return Err(io::Error::new(io::ErrorKind::Unsupported, "this game mode can't be played over the network"));
+                },
+                PlayerType::Human => {
+                    let event = self.terminal.read_event()?;
+                    self.handle_variant_event(event)?;
+                },
+                PlayerType::AI(ai_player) if self.swap_available => {
+                    let ai_player = AiPlayer::new(ai_player.difficulty());
+                    match self.wait_for_ai(move |variant|{ai_player.choose_swap(variant)})? {
+                        Some(true) => self.swap_sides(),
+                        // reply to the first move instead
+                        Some(false) => self.swap_available = false,
+                        None => {}
+                    }
+                },
+                PlayerType::AI(ai_player) => {
+                    let ai_player = AiPlayer::new(ai_player.difficulty());
+                    if let Some(game_move) = self.wait_for_ai(move |variant|{ai_player.choose_variant_move(variant)})? {
+                        self.play_variant_move(&game_move)?;
+                    }
+                }
+            }
+
+            outcome = self.variant().outcome();
+        }
+
+        match &outcome {
+            Outcome::Win(winner) => self.add_win(self.player_identity(winner.clone())),
+            Outcome::Draw => self.add_draw(),
+            Outcome::Incomplete => {}
+        }
+        if let Some(player) = self.variant().half_point() {
+            self.add_half_point(self.player_identity(player));
+        }
+
+        Ok(outcome)
+    }
+
+    /// Returns the variant currently being played
+    ///
+    ///# Panics
+    ///
+    /// This method panics if no variant game has been started
+    fn variant(&self) -> &dyn Variant
+    {
+        self.variant.as_deref().expect("no variant game has been started")
+    }
+
+    /// Plays the given move and updates the active player to match the variant
+    ///
+    /// If the variant [drops pieces](Variant::drops_pieces), the piece is first shown falling
+    /// down its column.
+    fn play_variant_move(&mut self, game_move: &Move) -> crossterm::Result<()>
+    {
+        if self.variant().drops_pieces() {
+            self.animate_drop(game_move.cell())?;
+        }
+        self.save_variant_snapshot();
+        let previous_player = self.active_player.clone();
+        if let Some(variant) = self.variant.as_mut() {
+            variant.play_move(game_move);
+            self.active_player = variant.active_player();
+        }
+        self.variant_selection = None;
+        self.variant_last_move = Some(game_move.cell());
+        self.variant_moves += 1;
+        // under the swap rule, the second player may swap sides instead of replying to the first move
+        self.swap_available = self.variant_options.swap_rule && self.variant_moves == 1 &&
+            self.active_player != previous_player;
+        Ok(())
+    }
+
+    /// Swaps the sides of the two players, so that the active player takes over the side that
+    /// played the first move, and their opponent replies to it
+    fn swap_sides(&mut self)
+    {
+        self.save_variant_snapshot();
+        self.sides_swapped = !self.sides_swapped;
+        self.swap_available = false;
+    }
+
+    /// Saves the current state of the game, so that the next move (or swap) can be undone
+    fn save_variant_snapshot(&mut self)
+    {
+        let snapshot = VariantSnapshot{
+            variant: self.variant().clone_variant(),
+            moves: self.variant_moves,
+            last_move: self.variant_last_move,
+            swap_available: self.swap_available,
+            sides_swapped: self.sides_swapped
+        };
+        self.variant_history.push(snapshot);
+    }
+
+    /// Takes back the last move (or swap), and the moves of any AI players since, so that it is
+    /// a human player's turn again
+    fn undo_variant_move(&mut self)
+    {
+        while let Some(snapshot) = self.variant_history.pop() {
+            self.active_player = snapshot.variant.active_player();
+            self.variant = Some(snapshot.variant);
+            self.variant_moves = snapshot.moves;
+            self.variant_last_move = snapshot.last_move;
+            self.swap_available = snapshot.swap_available;
+            self.sides_swapped = snapshot.sides_swapped;
+            self.variant_selection = None;
+            if self.active_player_type() == &PlayerType::Human {
+                break;
+            }
+        }
+    }
+
+    /// Selects the move the strongest AI player would play for the active player
+    ///
+    /// The cursor is moved to the cell of the move (or the piece it moves), and the symbol or
+    /// number it places is chosen.
+    fn show_variant_hint(&mut self)
+    {
+        let Ok(game_move) = AiPlayer::new(1.0).choose_variant_move(self.variant()) else {
+            return;
+        };
+        self.variant_selection = None;
+        self.variant_cursor = game_move.source().unwrap_or(game_move.cell());
+        match game_move {
+            Move::PlaceSymbol(_, symbol) => self.variant_symbol = symbol,
+            Move::PlaceNumber(_, number) => self.variant_number = number,
+            _ => {}
+        }
+    }
+
+    /// Shows the active player's piece falling from the top of the column of the given cell
+    /// down to the cell above it
+    fn animate_drop(&mut self, cell: usize) -> crossterm::Result<()>
+    {
+        let boards = self.variant().boards();
+        let layout = Layout::new(&boards);
+        if self.terminal_x_size < layout.width || self.terminal_y_size < layout.height + 3 {
+            return Ok(());
+        }
+        let Some(board) = boards.iter().find(|board|{board.cells.iter().any(|view|{view.cell == cell})}) else {
+            return Ok(());
+        };
+        let index = board.cells.iter().position(|view|{view.cell == cell}).unwrap_or(0);
+        let columns = board.columns as usize;
+
+        let symbol = self.active_player.get_char();
+        self.terminal.queue(cursor::Hide)?;
+        for above in (index % columns..index).step_by(columns) {
+            let (x, y) = layout.cell_position(board, above);
+            self.terminal
+                .queue(MoveTo(x, y))?
+                .queue(PrintStyledContent(self.theme.symbol(symbol).apply(symbol)))?
+                .flush()?;
+            thread::sleep(Self::DROP_FRAME_DURATION);
+            self.terminal
+                .queue(MoveTo(x, y))?
+                .queue(Print(board.cells[above].symbol))?;
+        }
+        self.terminal.flush()?;
+        Ok(())
+    }
+
+    /// Makes a choice for the active AI player on another thread, handling input while waiting
+    ///
+    /// `choose` is given a copy of the variant, and returns the choice (such as a move to play).
+    /// Returns `None` if the user quit before a choice was made.
+    fn wait_for_ai<T: Send + 'static>(
+        &mut self,
+        choose: impl FnOnce(&dyn Variant) -> Result<T, AiError> + Send + 'static
+    ) -> crossterm::Result<Option<T>>
+    {
+        let variant = self.variant().clone_variant();
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move ||{
+            // the receiver is gone if the user quit while the choice was being made
+            let _ = sender.send(choose(variant.as_ref()));
+        });
+
+        loop {
+            match receiver.try_recv() {
+                Ok(Ok(choice)) => return Ok(Some(choice)),
+                Ok(Err(AiError::NoMovesFound)) => panic!("No moves found despite game not being finished"),
+                Ok(Err(AiError::GameFinished)) | Err(mpsc::TryRecvError::Disconnected) => return Ok(None),
+                Err(mpsc::TryRecvError::Empty) => {}
+            }
+
+            if self.terminal.poll_event(Self::AI_POLL_INTERVAL)? {
+                let event = self.terminal.read_event()?;
+                self.handle_variant_event(event)?;
+                if self.exit_flag {
+                    return Ok(None);
+                }
+                self.draw_variant_game()?;
+            }
+        }
+    }
+
+    /// Handles the given [Event] during a variant game
+    fn handle_variant_event(&mut self, event: Event) -> crossterm::Result<()>
+    {
+        let human_turn = self.active_player_type() == &PlayerType::Human;
+        match event {
+            Event::Key(KeyEvent{code:KeyCode::Char('c'), modifiers:KeyModifiers::CONTROL, ..}) => {
+                self.exit_flag = true;
+            },
+            Event::Key(key_event) => {
+                match (self.key_bindings.action(&key_event), key_event.code) {
+                    (Some(Action::PlaceAt(_)) | None, KeyCode::Char(digit @ '1'..='9'))
+                        if human_turn && self.choosing_numbers() => {
+                        self.select_number(digit as u8 - b'0');
+                    },
+                    (Some(Action::PlaceAt(location)), _) if human_turn => {
+                        if let Some(cell) = self.variant_cell_at(location) {
+                            self.variant_cursor = cell;
+                            self.play_selected_cell()?;
+                        }
+                    },
+                    (Some(Action::Right), _) if human_turn => {
+                        self.move_variant_cursor(Direction::Right);
+                    },
+                    (Some(Action::Left), _) if human_turn => {
+                        self.move_variant_cursor(Direction::Left);
+                    },
+                    (Some(Action::Down), _) if human_turn => {
+                        self.move_variant_cursor(Direction::Down);
+                    },
+                    (Some(Action::Up), _) if human_turn => {
+                        self.move_variant_cursor(Direction::Up);
+                    },
+                    (Some(Action::NextBoard), _) if human_turn => {
+                        self.move_variant_cursor_to_board(true);
+                    },
+                    (Some(Action::PreviousBoard), _) if human_turn => {
+                        self.move_variant_cursor_to_board(false);
+                    },
+                    (Some(Action::Cancel), _) => {
+                        self.variant_selection = None;
+                    },
+                    (Some(Action::Swap), _) if human_turn && self.swap_available => {
+                        self.swap_sides();
+                    },
+                    (Some(Action::ToggleSymbol), _) if human_turn && self.choosing_symbols() => {
+                        self.variant_symbol = match self.variant_symbol {
+                            BoardSpace::X => BoardSpace::O,
+                            _ => BoardSpace::X
+                        };
+                    },
+                    (Some(Action::Place), _) if human_turn => {
+                        self.play_selected_cell()?;
+                    },
+                    (Some(Action::Undo), _) if human_turn => {
+                        self.undo_variant_move();
+                    },
+                    (Some(Action::Hint), _) if human_turn => {
+                        self.show_variant_hint();
+                    },
+                    (Some(Action::Quit), _) => {
+                        self.exit_flag = true;
+                    },
+                    // while they aren't bound to anything, x and o choose a symbol directly
+                    (None, KeyCode::Char('x')) if human_turn && self.choosing_symbols() => {
+                        self.variant_symbol = BoardSpace::X;
+                    },
+                    (None, KeyCode::Char('o')) if human_turn && self.choosing_symbols() => {
+                        self.variant_symbol = BoardSpace::O;
+                    },
+                    (None, KeyCode::Char('x'))
+                        if human_turn && self.active_player == ActivePlayer::PlayerX => {
+                        self.play_selected_cell()?;
+                    },
+                    (None, KeyCode::Char('o'))
+                        if human_turn && self.active_player == ActivePlayer::PlayerO => {
+                        self.play_selected_cell()?;
+                    },
+                    _ => {
+                        //ignore other KeyEvents
+                    }
+                }
+            },
+            //clicking a cell selects it and plays it, as if Enter was pressed
+            Event::Mouse(MouseEvent{kind: MouseEventKind::Down(MouseButton::Left), column, row, ..}) if human_turn => {
+                if let Some(cell) = self.clicked_variant_cell(column, row) {
+                    self.variant_cursor = cell;
+                    self.play_selected_cell()?;
+                }
+            },
+            //ignore size returned by resize event as it is currently (as of crossterm 0.25) wrong on Windows
+            Event::Resize(_,_) => {
+                (self.terminal_x_size, self.terminal_y_size) = self.terminal.size()?;
+                self.terminal.execute(Clear(ClearType::All))?;
+            },
+            _ => {
+                //ignore other Events
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the selectable cell drawn at the given position of the terminal, if any
+    ///
+    /// If the variant [drops pieces](Variant::drops_pieces), clicking anywhere in a column
+    /// selects the cell the piece would fall to.
+    fn clicked_variant_cell(&self, column: u16, row: u16) -> Option<usize>
+    {
+        let cells = self.selectable_cells();
+        if self.variant().drops_pieces() {
+            return cells.iter()
+                .find(|(_, (x, _))|{column + 1 >= *x && column <= x + 1})
+                .map(|(cell, _)|{*cell});
+        }
+
+        let boards = self.variant().boards();
+        Layout::new(&boards).cell_at(&boards, (column, row))
+            .filter(|cell|{cells.iter().any(|(selectable, _)|{selectable == cell})})
+    }
+
+    /// Returns the cell in the given space of a variant played on a single 3x3 board, if the
+    /// current variant is one
+    ///
+    /// Variants that [drop pieces](Variant::drops_pieces) are excluded, since their cells
+    /// can't be chosen directly.
+    fn variant_cell_at(&self, location: BoardSpaceLocation) -> Option<usize>
+    {
+        if self.variant().drops_pieces() {
+            return None;
+        }
+        match &self.variant().boards()[..] {
+            [board] if board.columns == 3 && board.cells.len() == 9 => {
+                let (x, y) = location.as_coordinates();
+                board.cells.get(y as usize * 3 + x as usize).map(|cell|{cell.cell})
+            },
+            _ => None
+        }
+    }
+
+    /// Plays the move for the selected cell, if it is legal
+    ///
+    /// For moves that move a piece, the piece is selected first, then the cell to move it to
+    /// (likewise for the two cells of an entangled move). Selecting the selected piece again
+    /// deselects it.
+    fn play_selected_cell(&mut self) -> crossterm::Result<()>
+    {
+        let cursor = self.variant_cursor;
+        let legal_moves = self.variant().legal_moves();
+        match self.variant_selection {
+            Some(source) if source == cursor => {
+                self.variant_selection = None;
+            },
+            Some(source) => {
+                let game_move = legal_moves.iter()
+                    .find(|game_move|{game_move.cell_after(source) == Some(cursor)})
+                    .cloned();
+                if let Some(game_move) = game_move {
+                    self.play_variant_move(&game_move)?;
+                }
+            },
+            None => {
+                let game_move = if self.choosing_symbols() {
+                    Move::PlaceSymbol(cursor, self.variant_symbol.clone())
+                } else if self.choosing_numbers() {
+                    Move::PlaceNumber(cursor, self.variant_number)
+                } else {
+                    Move::Place(cursor)
+                };
+                if legal_moves.contains(&game_move) {
+                    self.play_variant_move(&game_move)?;
+                } else if legal_moves.iter().any(|game_move|{game_move.cell_after(cursor).is_some()}) {
+                    self.variant_selection = Some(cursor);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns true if the active player chooses which symbol to place
+    fn choosing_symbols(&self) -> bool
+    {
+        self.variant().legal_moves().iter().any(|game_move|{matches!(game_move, Move::PlaceSymbol(..))})
+    }
+
+    /// Returns true if the active player chooses which number to place
+    fn choosing_numbers(&self) -> bool
+    {
+        self.variant().legal_moves().iter().any(|game_move|{matches!(game_move, Move::PlaceNumber(..))})
+    }
+
+    /// Returns every number the active player can place, from lowest to highest
+    fn legal_numbers(&self) -> Vec<u8>
+    {
+        let mut numbers: Vec<u8> = self.variant().legal_moves().iter()
+            .filter_map(|game_move|{
+                match game_move {
+                    Move::PlaceNumber(_, number) => Some(*number),
+                    _ => None
+                }
+            })
+            .collect();
+        numbers.sort_unstable();
+        numbers.dedup();
+        numbers
+    }
+
+    /// Chooses the given number to place, if the active player can place it
+    fn select_number(&mut self, number: u8)
+    {
+        if self.legal_numbers().contains(&number) {
+            self.variant_number = number;
+        }
+    }
+
+    /// Chooses the lowest number the active player can place if the chosen number can't be placed
+    fn select_legal_number(&mut self)
+    {
+        let numbers = self.legal_numbers();
+        if !numbers.contains(&self.variant_number) {
+            if let Some(number) = numbers.first() {
+                self.variant_number = *number;
+            }
+        }
+    }
+
+    /// Returns every cell the active player can select next
+    /// 
+    /// These are the cells that can be claimed or whose piece can be moved, or once a piece has
+    /// been selected, the cells it can move to (and the piece itself, to deselect it).
+    fn selectable_cell_indices(&self) -> Vec<usize>
+    {
+        let legal_moves = self.variant().legal_moves();
+        match self.variant_selection {
+            Some(source) => legal_moves.iter()
+                .filter_map(|game_move|{game_move.cell_after(source)})
+                .chain(std::iter::once(source))
+                .collect(),
+            None => legal_moves.iter()
+                .flat_map(Move::first_cells)
+                .collect()
+        }
+    }
+
+    /// Returns the screen position of every cell the active player can select next
+    fn selectable_cells(&self) -> Vec<(usize, (u16, u16))>
+    {
+        let boards = self.variant().boards();
+        let selectable = self.selectable_cell_indices();
+        Layout::new(&boards).cell_positions(&boards).into_iter()
+            .filter(|(cell, _)|{selectable.contains(cell)})
+            .collect()
+    }
+
+    /// Moves the cursor to the nearest selectable cell in the given direction, if there is one
+    ///
+    /// If the variant [drops pieces](Variant::drops_pieces), the cursor only moves left and right,
+    /// to the next column that can be played.
+    fn move_variant_cursor(&mut self, direction: Direction)
+    {
+        let cells = self.selectable_cells();
+        let Some(&(_, (x, y))) = cells.iter().find(|(cell, _)|{*cell == self.variant_cursor}) else {
+            return;
+        };
+
+        if self.variant().drops_pieces() {
+            // there is at most one selectable cell in each column
+            let nearest = cells.iter().filter_map(|(cell, (cell_x, _))|{
+                let dx = *cell_x as i32 - x as i32;
+                let along = match direction {
+                    Direction::Left => -dx,
+                    Direction::Right => dx,
+                    Direction::Up | Direction::Down => return None
+                };
+                (along > 0).then_some((along, *cell))
+            }).min();
+
+            if let Some((_, cell)) = nearest {
+                self.variant_cursor = cell;
+            }
+            return;
+        }
+
+        // prefer cells directly in line with the cursor over closer cells off to the side
+        let nearest = cells.iter().filter_map(|(cell, (cell_x, cell_y))|{
+            let (dx, dy) = (*cell_x as i32 - x as i32, *cell_y as i32 - y as i32);
+            let (along, across) = match direction {
+                Direction::Up => (-dy, dx),
+                Direction::Down => (dy, dx),
+                Direction::Left => (-dx, dy),
+                Direction::Right => (dx, dy)
+            };
+            (along > 0).then_some((along + 2 * across.abs(), *cell))
+        }).min();
+
+        if let Some((_, cell)) = nearest {
+            self.variant_cursor = cell;
+        }
+    }
+
+    /// Moves the cursor to the same space on the next board with a selectable cell
+    /// (or the previous board if `forward` is false), wrapping around
+    /// 
+    /// If that space cannot be played, the nearest selectable cell on that board is chosen instead.
+    fn move_variant_cursor_to_board(&mut self, forward: bool)
+    {
+        let boards = self.variant().boards();
+        let selectable = self.selectable_cell_indices();
+        let Some(current_board) = boards.iter().position(|board|{
+            board.cells.iter().any(|cell|{cell.cell == self.variant_cursor})
+        }) else {
+            return;
+        };
+        let index = boards[current_board].cells.iter()
+            .position(|cell|{cell.cell == self.variant_cursor})
+            .unwrap_or(0);
+
+        let count = boards.len();
+        let next_board = (1..count)
+            .map(|offset|{
+                if forward {(current_board + offset) % count} else {(current_board + count - offset) % count}
+            })
+            .map(|board|{&boards[board]})
+            .find(|board|{board.cells.iter().any(|cell|{selectable.contains(&cell.cell)})});
+
+        if let Some(board) = next_board {
+            // select the same space even if it cannot be played, so the nearest one is found below
+            self.variant_cursor = board.cells.get(index).unwrap_or(&board.cells[0]).cell;
+            self.select_legal_cell();
+        }
+    }
+
+    /// Moves the cursor to the nearest selectable cell if the selected cell cannot be played
+    fn select_legal_cell(&mut self)
+    {
+        let cells = self.selectable_cells();
+        if cells.iter().any(|(cell, _)|{*cell == self.variant_cursor}) {
+            return;
+        }
+
+        let boards = self.variant().boards();
+        let Some((_, (x, y))) = Layout::new(&boards).cell_positions(&boards).into_iter()
+            .find(|(cell, _)|{*cell == self.variant_cursor}) else {
+            return;
+        };
+
+        let nearest = cells.iter().min_by_key(|(_, (cell_x, cell_y))|{
+            // rows are twice as far apart on screen as they look
+            (*cell_x as i32 - x as i32).abs() + 2 * (*cell_y as i32 - y as i32).abs()
+        });
+        if let Some((cell, _)) = nearest {
+            self.variant_cursor = *cell;
+        }
+    }
+
+    /// Returns the cell closest to the centre of the given boards
+    pub(super) fn centre_cell(boards: &[BoardView]) -> usize
+    {
+        let layout = Layout::new(boards);
+        let (centre_x, centre_y) = (layout.width as i32 / 2, layout.height as i32 / 2);
+        layout.cell_positions(boards).into_iter()
+            .min_by_key(|(_, (x, y))|{(*x as i32 - centre_x).abs() + (*y as i32 - centre_y).abs()})
+            .map_or(0, |(cell, _)|{cell})
+    }
+
+    /// Draws the variant game with the turn and help text below it, and positions the cursor
+    /// in the selected cell if a local human player is choosing a move
+    fn draw_variant_game(&mut self) -> crossterm::Result<()>
+    {
+        let boards = self.variant().boards();
+        let layout = Layout::new(&boards);
+
+        self.terminal.queue(cursor::Hide)?;
+
+        if self.terminal_x_size < layout.width || self.terminal_y_size < layout.height + 3 {
+            // print error message instead of game board if terminal is too small
+            self.terminal
+                .queue(MoveTo(0, 0))?
+                .queue(Clear(ClearType::CurrentLine))?
+                .queue(Print(format!("Terminal too small ({} x {})! Please enlarge terminal", self.terminal_x_size, self.terminal_y_size)))?
+                .flush()?;
+            return Ok(());
+        }
+
+        let human_turn = self.active_player_type() == &PlayerType::Human;
+        let cursor = human_turn.then_some(self.variant_cursor);
+        self.draw_variant_boards(&boards, &layout, cursor)?;
+
+        let player = self.active_player.get_char();
+        self.draw_status_panel_beside(&layout, &format!("{}'s turn.", player))?;
+        let moving_pieces = self.variant().legal_moves().iter().any(|game_move|{game_move.source().is_some()});
+        let entangling = self.variant().legal_moves().iter().any(|game_move|{matches!(game_move, Move::Entangle(..))});
+        let choosing_symbols = self.choosing_symbols();
+        let choosing_numbers = self.choosing_numbers();
+        let drops_pieces = self.variant().drops_pieces();
+        let key_bindings = &self.key_bindings;
+        let turn_text = if self.swap_available {
+            format!(
+                "{}'s turn. Press {} to swap sides and play {} instead.",
+                player, key_bindings.key_name(Action::Swap), self.active_player.opposite().get_char()
+            )
+        } else if choosing_symbols {
+            format!(
+                "{}'s turn. Placing {}; press {} to change.",
+                player, self.variant_symbol.get_char(), key_bindings.key_name(Action::ToggleSymbol)
+            )
+        } else if choosing_numbers {
+            format!("{}'s turn. Placing {}; press a number key to change.", player, self.variant_number)
+        } else if self.variant_selection.is_some() && entangling {
+            format!(
+                "{}'s turn. Select the second square to mark, or press {} to choose another.",
+                player, key_bindings.key_name(Action::Cancel)
+            )
+        } else if self.variant_selection.is_some() {
+            format!(
+                "{}'s turn. Select where to move the piece, or press {} to choose another.",
+                player, key_bindings.key_name(Action::Cancel)
+            )
+        } else if entangling {
+            format!("{}'s turn. Select two squares to mark.", player)
+        } else if moving_pieces {
+            format!("{}'s turn. Select a piece to move.", player)
+        } else if drops_pieces {
+            format!("{}'s turn. Select a column to drop a piece in.", player)
+        } else if boards.len() > 1 {
+            format!("{}'s turn. Press {} to move to the next board.", player, key_bindings.key_name(Action::NextBoard))
+        } else {
+            format!("{}'s turn", player)
+        };
+        let help_text = if choosing_symbols || choosing_numbers {
+            self.selection_help("space", None, "place", "quit")
+        } else if drops_pieces {
+            self.selection_help("column", Some(player), "drop", "quit")
+        } else {
+            self.selection_help("space", Some(player), if moving_pieces {"select"} else {"place"}, "quit")
+        };
+        self.terminal
+            .queue(MoveTo(0, layout.height + 1))?
+            .queue(Clear(ClearType::CurrentLine))?
+            .queue(Print(turn_text))?
+            .queue(MoveTo(0, layout.height + 2))?
+            .queue(Clear(ClearType::CurrentLine))?
+            .queue(Print(help_text))?;
+
+        // highlight the piece being moved
+        if let Some(source) = self.variant_selection {
+            let symbol = boards.iter().flat_map(|board|{board.cells.iter()})
+                .find(|cell|{cell.cell == source})
+                .map(|cell|{cell.symbol});
+            let position = layout.cell_positions(&boards).into_iter()
+                .find(|(cell, _)|{*cell == source})
+                .map(|(_, position)|{position});
+            if let (Some(symbol), Some((x, y))) = (symbol, position) {
+                self.terminal
+                    .queue(MoveTo(x, y))?
+                    .queue(PrintStyledContent(symbol.bold().underlined()))?;
+            }
+        }
+
+        if human_turn {
+            let position = layout.cell_positions(&boards).into_iter()
+                .find(|(cell, _)|{*cell == self.variant_cursor})
+                .map(|(_, position)|{position});
+            if let Some((x, y)) = position {
+                self.terminal
+                    .queue(MoveTo(x, y))?
+                    .queue(cursor::Show)?;
+            }
+        }
+        self.terminal.flush()?;
+        Ok(())
+    }
+
+    /// Draws the [status panel](Self::status_panel_lines) to the right of boards with the
+    /// given [Layout], if the terminal is large enough for it
+    pub(super) fn draw_status_panel_beside(&mut self, layout: &Layout, status: &str) -> crossterm::Result<()>
+    {
+        let lines = self.status_panel_lines(status);
+        let column = layout.width + BoardGeometry::PANEL_GAP;
+        if self.terminal_x_size >= column + Self::SIDE_PANEL_WIDTH && self.terminal_y_size >= lines.len() as u16 {
+            self.draw_side_panel(lines, column, 0)?;
+        }
+        Ok(())
+    }
+
+    /// Draws the given boards, styling cells and lines as each [BoardView] describes
+    ///
+    /// The cell of the last move and the `cursor` cell (if any) are highlighted as well, in the
+    /// colours of the chosen [Theme](super::theme::Theme). Does not flush the terminal.
+    pub(super) fn draw_variant_boards(
+        &mut self,
+        boards: &[BoardView],
+        layout: &Layout,
+        cursor: Option<usize>
+    ) -> crossterm::Result<()>
+    {
+        for board in boards {
+            let (origin_x, origin_y) = layout.board_origin(board);
+            let line_style = self.cell_style(' ', board.style);
+
+            for (row, cells) in board.cells.chunks(board.columns as usize).enumerate() {
+                let row = row as u16;
+                self.terminal.queue(MoveTo(origin_x, origin_y + row * 2))?;
+                for (column, cell) in cells.iter().enumerate() {
+                    if column > 0 {
+                        self.terminal.queue(PrintStyledContent(line_style.apply("|")))?;
+                    }
+                    let mut style = self.cell_style(cell.symbol, cell.style);
+                    if cell.style != CellStyle::Winning {
+                        if self.variant_last_move == Some(cell.cell) {
+                            style = theme::combine(style, self.theme.last_move());
+                        }
+                        if cursor == Some(cell.cell) {
+                            style = theme::combine(style, self.theme.cursor());
+                        }
+                    }
+                    let text = format!(" {}{}", cell.symbol, cell.subscript.unwrap_or(' '));
+                    self.terminal.queue(PrintStyledContent(style.apply(text)))?;
+                }
+
+                if row + 1 < board.rows() {
+                    self.terminal
+                        .queue(MoveTo(origin_x, origin_y + row * 2 + 1))?
+                        .queue(PrintStyledContent(line_style.apply(variants::separator_text(board.columns))))?;
+                }
+            }
+
+            if let Some(label) = &board.label {
+                let width = (board.columns * 4).saturating_sub(1) as usize;
+                let (label_x, label_y) = layout.label_position(board);
+                self.terminal
+                    .queue(MoveTo(label_x, label_y))?
+                    .queue(Print(format!("{:<width$}", label, width = width)))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the style of a cell showing the given symbol, or of a line of a board, with the
+    /// given [CellStyle]
+    fn cell_style(&self, symbol: char, style: CellStyle) -> ContentStyle
+    {
+        match style {
+            CellStyle::Normal => self.theme.symbol(symbol),
+            CellStyle::Winning => self.theme.win_line(),
+            CellStyle::Inactive => self.theme.inactive(),
+            CellStyle::Seat(seat) => self.theme.seat(seat)
+        }
+    }
+}
@@ -0,0 +1,448 @@
+//! Game variants that are not played on a single [GameBoard](crate::gameboard::GameBoard)
+//!
+//! Each variant implements [Variant], which provides everything the UI and the AI need
+//! to play it: the rules (whose turn it is, the legal moves, the outcome) and a description
+//! of how the boards should be drawn.
+//!
+//! Cells are identified by index. How cells are numbered is up to each variant; the UI only
+//! needs to know where each cell is drawn, which is described by [BoardView]s.
+
+use crate::{
+    active_player::ActivePlayer,
+    game_settings::{GameMode, VariantOptions, OrderRole},
+    gameboard::{BoardSpace, BoardSpaceLocation},
+    multiplayer::Seat
+};
+
+//declare ultimate module which contains
+//the Ultimate tic-tac-toe variant
+pub mod ultimate;
+
+//declare qubic module which contains
+//the 3D (4x4x4) tic-tac-toe variant
+pub mod qubic;
+
+//declare rolling module which contains
+//the variant where each player may only have three pieces
+pub mod rolling;
+
+//declare morris module which contains
+//the Three Men's Morris variant
+pub mod morris;
+
+//declare wild module which contains
+//the variant where players may place either symbol
+pub mod wild;
+
+//declare notakto module which contains
+//the Notakto variant
+pub mod notakto;
+
+//declare gravity module which contains
+//the variant where pieces fall to the bottom of their column
+pub mod gravity;
+
+//declare quantum module which contains
+//the Quantum tic-tac-toe variant
+pub mod quantum;
+
+//declare numerical module which contains
+//the Numerical tic-tac-toe variant
+pub mod numerical;
+
+//declare order_chaos module which contains
+//the Order and Chaos variant
+pub mod order_chaos;
+
+/// A move in a [Variant]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Move {
+    /// Claim the cell with the given index for the active player
+    Place(usize),
+    /// Move the active player's piece from the first cell to the second
+    Step(usize, usize),
+    /// Place the given symbol in the cell with the given index, whichever player is active
+    PlaceSymbol(usize, BoardSpace),
+    /// Place a mark of the active player in both cells at once, to be decided between later
+    Entangle(usize, usize),
+    /// Place the given number in the cell with the given index
+    PlaceNumber(usize, u8)
+}
+
+impl Move {
+    /// Returns the cell this move claims
+    ///
+    /// This is the cell a user selects to make the move (after selecting the
+    /// [source](Move::source), if any).
+    pub fn cell(&self) -> usize
+    {
+        match self {
+            Move::Place(cell) | Move::Step(_, cell) | Move::PlaceSymbol(cell, _) |
+                Move::Entangle(_, cell) | Move::PlaceNumber(cell, _) => *cell
+        }
+    }
+
+    /// Returns the cell of the piece this move moves (or the first cell of an
+    /// [entangled](Move::Entangle) move), if any
+    /// 
+    /// This cell is selected before the move's [cell](Move::cell).
+    pub fn source(&self) -> Option<usize>
+    {
+        match self {
+            Move::Place(_) | Move::PlaceSymbol(..) | Move::PlaceNumber(..) => None,
+            Move::Step(source, _) | Move::Entangle(source, _) => Some(*source)
+        }
+    }
+
+    /// Returns the cells a user can select first to make this move
+    ///
+    /// This is the [source](Move::source) if there is one, or else the move's [cell](Move::cell),
+    /// except that either cell of an [entangled](Move::Entangle) move can be selected first.
+    pub fn first_cells(&self) -> Vec<usize>
+    {
+        match self {
+            Move::Entangle(first, second) => vec![*first, *second],
+            _ => vec![self.source().unwrap_or(self.cell())]
+        }
+    }
+
+    /// Returns the cell a user selects to make this move after selecting `first`, if this move
+    /// can be made by selecting `first` and then another cell
+    pub fn cell_after(&self, first: usize) -> Option<usize>
+    {
+        match self {
+            Move::Step(source, cell) if *source == first => Some(*cell),
+            Move::Entangle(a, b) if *a == first => Some(*b),
+            Move::Entangle(a, b) if *b == first => Some(*a),
+            _ => None
+        }
+    }
+}
+
+/// The outcome of a game of a [Variant], if any
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    /// The game was won by the indicated player
+    Win(ActivePlayer),
+    /// No more moves can be played, and there was no winner
+    Draw,
+    /// Neither player has won, and there are still moves that can be played
+    Incomplete
+}
+
+impl Outcome {
+    /// Returns `true` if the game is finished
+    pub fn game_finished(&self) -> bool
+    {
+        !matches!(self, Self::Incomplete)
+    }
+}
+
+/// The rules and state of a game variant
+pub trait Variant: Send {
+
+    /// Returns the player whose turn it is
+    fn active_player(&self) -> ActivePlayer;
+
+    /// Returns every move the active player may play
+    ///
+    /// Returns an empty list if the game is finished
+    fn legal_moves(&self) -> Vec<Move>;
+
+    /// Plays the given move for the active player
+    ///
+    /// The move must be one of those returned by [Variant::legal_moves]
+    fn play_move(&mut self, game_move: &Move);
+
+    /// Returns the outcome of the game so far
+    fn outcome(&self) -> Outcome;
+
+    /// Returns the player who won half a point without winning the finished game, if any
+    ///
+    /// In Quantum mode, a collapse that completes lines for both players wins the game for
+    /// one of them and half a point for the other. Other variants never award half points.
+    fn half_point(&self) -> Option<ActivePlayer>
+    {
+        None
+    }
+
+    /// Estimates how likely `player` is to win from the current (unfinished) position
+    ///
+    /// Returns a value from `-1.0` (`player` is sure to lose) to `1.0` (`player` is sure to win)
+    fn heuristic(&self, player: &ActivePlayer) -> f64;
+
+    /// Describes how to draw each board of this variant
+    fn boards(&self) -> Vec<BoardView>;
+
+    /// Returns true if pieces fall to the lowest empty cell of the column they are played in
+    ///
+    /// Every legal move must then be to the lowest empty cell of its column. The UI moves the
+    /// cursor between columns rather than cells, and shows pieces falling into place.
+    fn drops_pieces(&self) -> bool
+    {
+        false
+    }
+
+    /// Returns a copy of this variant, for exploring moves without changing the game
+    fn clone_variant(&self) -> Box<dyn Variant>;
+}
+
+/// Creates a new game of the variant played in the given mode, with `first_player` to move first
+///
+/// Returns `None` for modes played on a single [GameBoard](crate::gameboard::GameBoard)
+/// (classic and reverse), and for games with more than two players, which are played on a
+/// [MultiplayerGame](crate::multiplayer::MultiplayerGame)
+pub fn new_variant(
+    game_mode: GameMode,
+    options: &VariantOptions,
+    first_player: ActivePlayer
+) -> Option<Box<dyn Variant>>
+{
+    match game_mode {
+        GameMode::Classic | GameMode::Reverse | GameMode::Multiplayer => None,
+        GameMode::Ultimate => Some(Box::new(ultimate::Ultimate::new(first_player))),
+        GameMode::Qubic => Some(Box::new(qubic::Qubic::new(first_player))),
+        GameMode::Rolling => Some(Box::new(rolling::Rolling::new(first_player))),
+        GameMode::Morris => Some(Box::new(morris::Morris::new(first_player, options.diagonal_moves))),
+        GameMode::Wild => Some(Box::new(wild::Wild::new(first_player))),
+        GameMode::Notakto => Some(Box::new(notakto::Notakto::new(first_player, options.board_count))),
+        GameMode::Gravity => Some(Box::new(gravity::Gravity::new(
+            first_player,
+            options.board_columns,
+            options.board_rows,
+            options.line_length
+        ))),
+        GameMode::Quantum => Some(Box::new(quantum::Quantum::new(first_player))),
+        GameMode::Numerical => Some(Box::new(numerical::Numerical::new(first_player))),
+        GameMode::OrderChaos => Some(Box::new(order_chaos::OrderChaos::new(match options.order_role {
+            OrderRole::FirstPlayer => first_player,
+            OrderRole::PlayerX => ActivePlayer::PlayerX,
+            OrderRole::PlayerO => ActivePlayer::PlayerO
+        })))
+    }
+}
+
+/// Returns the index (0 to 8, row by row) of the given location on a 3x3 board
+fn space_index(location: BoardSpaceLocation) -> usize
+{
+    let (x, y) = location.as_coordinates();
+    (y * 3 + x) as usize
+}
+
+/// Returns the location with the given index (0 to 8, row by row) on a 3x3 board
+fn space_location(index: usize) -> BoardSpaceLocation
+{
+    BoardSpaceLocation::from_coordinates(((index % 3) as u8, (index / 3) as u8))
+}
+
+/// Returns every straight line of `line_length` cells on a board of the given size
+///
+/// Cells are numbered row by row from the top left, so the cell in column `x` of row `y`
+/// has index `y * columns + x`.
+pub(crate) fn winning_lines(columns: usize, rows: usize, line_length: usize) -> Vec<Vec<usize>>
+{
+    let (columns, rows, length) = (columns as i32, rows as i32, line_length as i32);
+    let directions = [(1, 0), (0, 1), (1, 1), (1, -1)];
+
+    let mut lines = Vec::new();
+    for y in 0..rows {
+        for x in 0..columns {
+            for (dx, dy) in directions {
+                let (end_x, end_y) = (x + dx * (length - 1), y + dy * (length - 1));
+                if (0..columns).contains(&end_x) && (0..rows).contains(&end_y) {
+                    lines.push((0..length).map(|step|{
+                        ((y + dy * step) * columns + x + dx * step) as usize
+                    }).collect());
+                }
+            }
+        }
+    }
+    lines
+}
+
+/// How a cell should be drawn
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CellStyle {
+    Normal,
+    /// Part of a winning line
+    Winning,
+    /// Cannot be played this turn
+    Inactive,
+    /// Claimed by the player in the given seat, in a game with more than two players
+    Seat(Seat)
+}
+
+/// How a single cell should be drawn
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CellView {
+    /// The index of the cell
+    pub cell: usize,
+    /// The character shown in the cell (a space if empty)
+    pub symbol: char,
+    /// A character shown just after the symbol, such as the number of the move that placed it
+    pub subscript: Option<char>,
+    pub style: CellStyle
+}
+
+/// How one board of a [Variant] should be drawn
+///
+/// Boards are drawn like the classic board, as a grid of cells separated by lines
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BoardView {
+    /// The position of this board among the other boards, as (column, row)
+    pub position: (u16, u16),
+    /// The number of cells in each row
+    pub columns: u16,
+    /// The cells of the board, row by row from the top left
+    pub cells: Vec<CellView>,
+    /// The style of the lines between cells
+    pub style: CellStyle,
+    /// A short description shown below the board
+    pub label: Option<String>
+}
+
+impl BoardView {
+    /// Returns the number of rows of cells in this board
+    pub fn rows(&self) -> u16
+    {
+        (self.cells.len() as u16).div_ceil(self.columns)
+    }
+}
+
+/// The positions of boards and cells when a list of [BoardView]s is drawn
+///
+/// Every board is given the same amount of space, determined by the largest board.
+/// Each cell is three characters wide, with the symbol in the middle; cells are separated by
+/// `|` horizontally and by a line of `-` vertically.
+pub struct Layout {
+    board_width: u16,
+    board_height: u16,
+    /// The total width of all boards
+    pub width: u16,
+    /// The total height of all boards, including labels
+    pub height: u16
+}
+
+impl Layout {
+
+    /// Columns between boards
+    const BOARD_GAP_X: u16 = 4;
+    /// Rows between boards
+    const BOARD_GAP_Y: u16 = 1;
+
+    /// Calculates the layout of the given boards
+    pub fn new(boards: &[BoardView]) -> Self
+    {
+        let max_columns = boards.iter().map(|board|{board.columns}).max().unwrap_or(0);
+        let max_rows = boards.iter().map(BoardView::rows).max().unwrap_or(0);
+        let labelled = boards.iter().any(|board|{board.label.is_some()});
+
+        let board_width = (max_columns * 4).saturating_sub(1);
+        let board_height = (max_rows * 2).saturating_sub(1) + if labelled {1} else {0};
+
+        let board_columns = boards.iter().map(|board|{board.position.0 + 1}).max().unwrap_or(0);
+        let board_rows = boards.iter().map(|board|{board.position.1 + 1}).max().unwrap_or(0);
+
+        Self{
+            board_width,
+            board_height,
+            width: (board_columns * (board_width + Self::BOARD_GAP_X)).saturating_sub(Self::BOARD_GAP_X),
+            height: (board_rows * (board_height + Self::BOARD_GAP_Y)).saturating_sub(Self::BOARD_GAP_Y)
+        }
+    }
+
+    /// Returns the top left corner of the given board, as (column, row)
+    pub fn board_origin(&self, board: &BoardView) -> (u16, u16)
+    {
+        (
+            board.position.0 * (self.board_width + Self::BOARD_GAP_X),
+            board.position.1 * (self.board_height + Self::BOARD_GAP_Y)
+        )
+    }
+
+    /// Returns the position of the symbol of the cell at the given index within the given board
+    pub fn cell_position(&self, board: &BoardView, index: usize) -> (u16, u16)
+    {
+        let (origin_x, origin_y) = self.board_origin(board);
+        let column = index as u16 % board.columns;
+        let row = index as u16 / board.columns;
+        (origin_x + column * 4 + 1, origin_y + row * 2)
+    }
+
+    /// Returns the index and symbol position of every cell in the given boards
+    pub fn cell_positions(&self, boards: &[BoardView]) -> Vec<(usize, (u16, u16))>
+    {
+        boards.iter().flat_map(|board|{
+            board.cells.iter().enumerate().map(move |(index, cell)|{
+                (cell.cell, self.cell_position(board, index))
+            })
+        }).collect()
+    }
+
+    /// Returns the index of the cell drawn at the given position, if any
+    ///
+    /// A cell covers the symbol and the space on either side of it, but not the lines
+    /// between cells.
+    pub fn cell_at(&self, boards: &[BoardView], (column, row): (u16, u16)) -> Option<usize>
+    {
+        self.cell_positions(boards).into_iter()
+            .find(|(_, (x, y))|{*y == row && column + 1 >= *x && column <= x + 1})
+            .map(|(cell, _)|{cell})
+    }
+
+    /// Returns the position of the label of the given board
+    pub fn label_position(&self, board: &BoardView) -> (u16, u16)
+    {
+        let (origin_x, origin_y) = self.board_origin(board);
+        (origin_x, origin_y + self.board_height - 1)
+    }
+}
+
+/// Returns the text for one row of cells of a board, e.g. `" X | O |   "`
+pub fn row_text(cells: &[CellView]) -> String
+{
+    cells.iter()
+        .map(|cell|{format!(" {}{}", cell.symbol, cell.subscript.unwrap_or(' '))})
+        .collect::<Vec<_>>()
+        .join("|")
+}
+
+/// Returns the line separating rows of cells of a board with the given number of columns
+pub fn separator_text(columns: u16) -> String
+{
+    vec!["---"; columns as usize].join("+")
+}
+
+/// Returns the given boards drawn as plain text, without styles
+pub fn boards_to_string(boards: &[BoardView]) -> String
+{
+    let layout = Layout::new(boards);
+    let mut lines = vec![vec![' '; layout.width as usize]; layout.height as usize];
+
+    let mut write = |(x, y): (u16, u16), text: &str|{
+        for (offset, character) in text.chars().enumerate() {
+            if let Some(space) = lines[y as usize].get_mut(x as usize + offset) {
+                *space = character;
+            }
+        }
+    };
+
+    for board in boards {
+        let (origin_x, origin_y) = layout.board_origin(board);
+        for (row, cells) in board.cells.chunks(board.columns as usize).enumerate() {
+            let row = row as u16;
+            write((origin_x, origin_y + row * 2), &row_text(cells));
+            if row + 1 < board.rows() {
+                write((origin_x, origin_y + row * 2 + 1), &separator_text(board.columns));
+            }
+        }
+        if let Some(label) = &board.label {
+            write(layout.label_position(board), label);
+        }
+    }
+
+    lines.into_iter()
+        .map(|line|{line.into_iter().collect::<String>().trim_end().to_owned()})
+        .collect::<Vec<_>>()
+        .join("\n")
+}
@@ -0,0 +1,203 @@
+//! Gravity tic-tac-toe, where pieces fall to the bottom of the column they are played in
+//!
+//! The board size and the number of pieces needed in a row are configurable; with 7 columns,
+//! 6 rows and lines of four, this is Connect Four.
+
+use std::sync::Arc;
+
+use crate::{
+    active_player::ActivePlayer,
+    gameboard::BoardSpace
+};
+
+use super::{Variant, Move, Outcome, BoardView, CellView, CellStyle, winning_lines};
+
+/// A game of Gravity tic-tac-toe
+///
+/// Cells are numbered row by row from the top left, so the cell in column `x` of row `y`
+/// (counting from the top) has index `y * columns + x`.
+#[derive(Clone)]
+pub struct Gravity {
+    columns: usize,
+    rows: usize,
+    cells: Vec<BoardSpace>,
+    active_player: ActivePlayer,
+    /// The player who moved first, who benefits from threats on odd rows
+    first_player: ActivePlayer,
+    /// The cell of the last piece played, if any
+    last_move: Option<usize>,
+    /// Every line of cells that wins the game, shared between copies of the game
+    lines: Arc<Vec<Vec<usize>>>
+}
+
+impl Gravity {
+
+    /// The smallest number of columns a board can have
+    pub const MIN_COLUMNS: usize = 3;
+    /// The largest number of columns a board can have
+    pub const MAX_COLUMNS: usize = 10;
+    /// The smallest number of rows a board can have
+    pub const MIN_ROWS: usize = 3;
+    /// The largest number of rows a board can have
+    pub const MAX_ROWS: usize = 8;
+    /// The shortest winning line
+    pub const MIN_LINE_LENGTH: usize = 3;
+    /// The longest winning line
+    pub const MAX_LINE_LENGTH: usize = 6;
+
+    /// Creates a new game on a board with the given number of `columns` and `rows`,
+    /// where `line_length` pieces in a row are needed to win, with `first_player` to move first
+    ///
+    /// Each setting is clamped to the range allowed by the constants above, and `line_length`
+    /// is also limited to the larger of `columns` and `rows`.
+    pub fn new(first_player: ActivePlayer, columns: usize, rows: usize, line_length: usize) -> Self
+    {
+        let columns = columns.clamp(Self::MIN_COLUMNS, Self::MAX_COLUMNS);
+        let rows = rows.clamp(Self::MIN_ROWS, Self::MAX_ROWS);
+        let line_length = line_length.clamp(Self::MIN_LINE_LENGTH, Self::MAX_LINE_LENGTH).min(columns.max(rows));
+
+        Self{
+            columns,
+            rows,
+            cells: vec![BoardSpace::Empty; columns * rows],
+            active_player: first_player.clone(),
+            first_player,
+            last_move: None,
+            lines: Arc::new(winning_lines(columns, rows, line_length))
+        }
+    }
+
+    /// Returns the line completed by the last piece played, if any
+    fn winning_line(&self) -> Option<&Vec<usize>>
+    {
+        let last_move = self.last_move?;
+        let space = &self.cells[last_move];
+        self.lines.iter()
+            .filter(|line|{line.contains(&last_move)})
+            .find(|line|{line.iter().all(|cell|{self.cells[*cell] == *space})})
+    }
+
+    /// Returns the row of the given cell counting up from the bottom, starting at 1
+    fn height(&self, cell: usize) -> usize
+    {
+        self.rows - cell / self.columns
+    }
+
+    /// Returns true if a piece played in the column of the given empty cell would land there
+    fn playable(&self, cell: usize) -> bool
+    {
+        let below = cell + self.columns;
+        below >= self.cells.len() || self.cells[below] != BoardSpace::Empty
+    }
+}
+
+impl Variant for Gravity {
+
+    fn active_player(&self) -> ActivePlayer
+    {
+        self.active_player.clone()
+    }
+
+    fn legal_moves(&self) -> Vec<Move>
+    {
+        if self.outcome().game_finished() {
+            return Vec::new();
+        }
+        // the lowest empty cell of each column that isn't full
+        (0..self.columns).filter_map(|column|{
+            (0..self.rows).rev()
+                .map(|row|{row * self.columns + column})
+                .find(|cell|{self.cells[*cell] == BoardSpace::Empty})
+                .map(Move::Place)
+        }).collect()
+    }
+
+    fn play_move(&mut self, game_move: &Move)
+    {
+        let cell = game_move.cell();
+        self.cells[cell] = self.active_player.get_board_space();
+        self.last_move = Some(cell);
+        self.active_player.switch();
+    }
+
+    fn outcome(&self) -> Outcome
+    {
+        if self.winning_line().is_some() {
+            // only the player who moved last can have completed a line
+            Outcome::Win(self.active_player.opposite())
+        } else if self.cells.iter().all(|space|{*space != BoardSpace::Empty}) {
+            Outcome::Draw
+        } else {
+            Outcome::Incomplete
+        }
+    }
+
+    fn heuristic(&self, player: &ActivePlayer) -> f64
+    {
+        let own_space = player.get_board_space();
+        let line_length = self.lines.first().map_or(0, Vec::len);
+
+        let score: f64 = self.lines.iter().map(|line|{
+            let ours = line.iter().filter(|cell|{self.cells[**cell] == own_space}).count();
+            let empty = line.iter().filter(|cell|{self.cells[**cell] == BoardSpace::Empty}).count();
+            let theirs = line_length - ours - empty;
+            let (pieces, sign, owner) = match (ours, theirs) {
+                (0, 0) => return 0.0,
+                (ours, 0) => (ours, 1.0, player.clone()),
+                (0, theirs) => (theirs, -1.0, player.opposite()),
+                _ => return 0.0
+            };
+
+            let mut value = (pieces * pieces) as f64;
+            if empty == 1 {
+                // a line that only needs one more piece is a threat. Threats that can't be
+                // completed yet are worth more on the rows their owner will usually get to
+                // play in first: odd rows for the first player and even rows for the second
+                let cell = *line.iter().find(|cell|{self.cells[**cell] == BoardSpace::Empty}).unwrap();
+                let odd_row = self.height(cell) % 2 == 1;
+                if self.playable(cell) || odd_row == (owner == self.first_player) {
+                    value *= 2.0;
+                }
+            }
+            sign * value
+        }).sum();
+
+        (score / (4 * line_length * line_length) as f64).tanh()
+    }
+
+    fn boards(&self) -> Vec<BoardView>
+    {
+        let winning_line = self.winning_line();
+
+        let cells = self.cells.iter().enumerate().map(|(cell, space)|{
+            CellView{
+                cell,
+                symbol: space.get_char(),
+                subscript: None,
+                style: if winning_line.is_some_and(|line|{line.contains(&cell)}) {
+                    CellStyle::Winning
+                } else {
+                    CellStyle::Normal
+                }
+            }
+        }).collect();
+
+        vec![BoardView{
+            position: (0, 0),
+            columns: self.columns as u16,
+            cells,
+            style: CellStyle::Normal,
+            label: None
+        }]
+    }
+
+    fn drops_pieces(&self) -> bool
+    {
+        true
+    }
+
+    fn clone_variant(&self) -> Box<dyn Variant>
+    {
+        Box::new(self.clone())
+    }
+}
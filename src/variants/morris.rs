@@ -0,0 +1,316 @@
+//! Three Men's Morris
+//!
+//! Each player starts by placing three pieces, one per turn. Once a player has placed all three,
+//! their turns are spent moving one of their pieces to an adjacent empty space instead. Pieces
+//! move orthogonally, or also diagonally if diagonal moves are enabled.
+//!
+//! The first player to get three pieces in a row wins. A player who cannot move loses, and if
+//! the same position occurs for the third time, the game is drawn.
+
+use crate::{
+    active_player::ActivePlayer,
+    gameboard::{GameBoard, BoardSpace, BoardSpaceLocation},
+    game_outcome::{GameOutcome, WinPosition}
+};
+
+use super::{Variant, Move, Outcome, BoardView, CellView, CellStyle, space_index, space_location};
+
+/// The number of pieces each player places
+const PIECES: usize = 3;
+
+/// The number of times a position must occur for the game to be drawn
+const REPETITION_LIMIT: usize = 3;
+
+/// A game of Three Men's Morris
+///
+/// Spaces are numbered from 0 to 8, row by row from the top left.
+#[derive(Clone)]
+pub struct Morris {
+    board: GameBoard,
+    active_player: ActivePlayer,
+    /// true if pieces may move diagonally as well as orthogonally
+    diagonal_moves: bool,
+    /// Every position so far, as returned by [Morris::position_key]
+    positions: Vec<u32>
+}
+
+impl Morris {
+
+    /// Creates a new game, with `first_player` to move first
+    ///
+    /// If `diagonal_moves` is true, pieces may move to diagonally adjacent spaces.
+    pub fn new(first_player: ActivePlayer, diagonal_moves: bool) -> Self
+    {
+        let mut new_instance = Self{
+            board: GameBoard::new(),
+            active_player: first_player,
+            diagonal_moves,
+            positions: Vec::new()
+        };
+        new_instance.positions.push(new_instance.position_key());
+        new_instance
+    }
+
+    /// Returns the number of pieces the given player has placed
+    fn pieces_placed(&self, player: &ActivePlayer) -> usize
+    {
+        let space = player.get_board_space();
+        self.board.all_spaces().filter(|(_, board_space)|{**board_space == space}).count()
+    }
+
+    /// Returns true if the spaces are next to each other, taking diagonal moves into account
+    fn adjacent(&self, from: BoardSpaceLocation, to: BoardSpaceLocation) -> bool
+    {
+        let (from_x, from_y) = from.as_coordinates();
+        let (to_x, to_y) = to.as_coordinates();
+        let (dx, dy) = (from_x.abs_diff(to_x), from_y.abs_diff(to_y));
+        if self.diagonal_moves {
+            dx.max(dy) == 1
+        } else {
+            dx + dy == 1
+        }
+    }
+
+    /// Returns every move the given player could play if it were their turn,
+    /// ignoring whether the game is finished
+    fn available_moves(&self, player: &ActivePlayer) -> Vec<Move>
+    {
+        let empty_spaces = self.board.all_spaces()
+            .filter(|(_, space)|{**space == BoardSpace::Empty})
+            .map(|(location, _)|{location});
+
+        if self.pieces_placed(player) < PIECES {
+            return empty_spaces.map(|location|{Move::Place(space_index(location))}).collect();
+        }
+
+        let own_space = player.get_board_space();
+        let empty_spaces: Vec<BoardSpaceLocation> = empty_spaces.collect();
+        self.board.all_spaces()
+            .filter(|(_, space)|{**space == own_space})
+            .flat_map(|(from, _)|{
+                empty_spaces.iter()
+                    .filter(move |to|{self.adjacent(from, **to)})
+                    .map(move |to|{Move::Step(space_index(from), space_index(*to))})
+            })
+            .collect()
+    }
+
+    /// Returns a number identifying the current position
+    fn position_key(&self) -> u32
+    {
+        // 2 bits per space, then 1 bit for the active player
+        let spaces = self.board.all_spaces().fold(0, |key, (_, space)|{
+            (key << 2) | match space {
+                BoardSpace::Empty => 0,
+                BoardSpace::X => 1,
+                BoardSpace::O => 2,
+                BoardSpace::Blocked => 3
+            }
+        });
+        let active = match self.active_player {
+            ActivePlayer::PlayerX => 0,
+            ActivePlayer::PlayerO => 1
+        };
+        (spaces << 1) | active
+    }
+}
+
+impl Variant for Morris {
+
+    fn active_player(&self) -> ActivePlayer
+    {
+        self.active_player.clone()
+    }
+
+    fn legal_moves(&self) -> Vec<Move>
+    {
+        if self.outcome().game_finished() {
+            return Vec::new();
+        }
+        self.available_moves(&self.active_player)
+    }
+
+    fn play_move(&mut self, game_move: &Move)
+    {
+        if let Some(source) = game_move.source() {
+            *self.board.space_mut(space_location(source)) = BoardSpace::Empty;
+        }
+        *self.board.space_mut(space_location(game_move.cell())) = self.active_player.get_board_space();
+
+        self.active_player.switch();
+        self.positions.push(self.position_key());
+    }
+
+    fn outcome(&self) -> Outcome
+    {
+        match self.board.game_outcome() {
+            GameOutcome::PlayerX(_) => return Outcome::Win(ActivePlayer::PlayerX),
+            GameOutcome::PlayerO(_) => return Outcome::Win(ActivePlayer::PlayerO),
+            GameOutcome::Draw | GameOutcome::Incomplete => {}
+        }
+
+        // a player who cannot move loses
+        if self.available_moves(&self.active_player).is_empty() {
+            return Outcome::Win(self.active_player.opposite());
+        }
+
+        let position = self.position_key();
+        let repetitions = self.positions.iter().filter(|key|{**key == position}).count();
+        if repetitions >= REPETITION_LIMIT {
+            Outcome::Draw
+        } else {
+            Outcome::Incomplete
+        }
+    }
+
+    fn heuristic(&self, player: &ActivePlayer) -> f64
+    {
+        let own_space = player.get_board_space();
+        let lines: f64 = WinPosition::all().map(|win_position|{
+            let spaces = win_position.as_board_spaces().map(|location|{self.board.space(location)});
+            let ours = spaces.iter().filter(|space|{***space == own_space}).count();
+            let empty = spaces.iter().filter(|space|{***space == BoardSpace::Empty}).count();
+            match (ours, 3 - ours - empty) {
+                (ours, 0) => [0.0, 1.0, 4.0, 0.0][ours],
+                (0, theirs) => -[0.0, 1.0, 4.0, 0.0][theirs],
+                _ => 0.0
+            }
+        }).sum();
+
+        // having more ways to move is an advantage once pieces start moving
+        let mobility = self.available_moves(player).len() as f64
+            - self.available_moves(&player.opposite()).len() as f64;
+
+        ((lines + 0.25 * mobility) / 8.0).tanh()
+    }
+
+    fn boards(&self) -> Vec<BoardView>
+    {
+        let win_position = match self.board.game_outcome() {
+            GameOutcome::PlayerX(win_position) | GameOutcome::PlayerO(win_position) => Some(win_position),
+            GameOutcome::Draw | GameOutcome::Incomplete => None
+        };
+
+        let cells = BoardSpaceLocation::all().map(|location|{
+            CellView{
+                cell: space_index(location),
+                symbol: self.board.space(location).get_char(),
+                subscript: None,
+                style: if win_position.is_some_and(|win_position|{win_position.as_board_spaces().contains(&location)}) {
+                    CellStyle::Winning
+                } else {
+                    CellStyle::Normal
+                }
+            }
+        }).collect();
+
+        vec![BoardView{
+            position: (0, 0),
+            columns: 3,
+            cells,
+            style: CellStyle::Normal,
+            label: None
+        }]
+    }
+
+    fn clone_variant(&self) -> Box<dyn Variant>
+    {
+        Box::new(self.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Plays the given moves in order from a new game with X to move first
+    fn play(diagonal_moves: bool, moves: &[Move]) -> Morris
+    {
+        let mut game = Morris::new(ActivePlayer::PlayerX, diagonal_moves);
+        for game_move in moves {
+            assert!(game.legal_moves().contains(game_move), "{:?} should be legal", game_move);
+            game.play_move(game_move);
+        }
+        game
+    }
+
+    /// Places X in 0, 3 and 8 and O in 1, 4 and 6, leaving X to move
+    fn placed(diagonal_moves: bool) -> Morris
+    {
+        play(diagonal_moves, &[0, 1, 3, 4, 8, 6].map(Move::Place))
+    }
+
+    /// Returns the legal moves of the game, sorted
+    fn sorted_moves(game: &Morris) -> Vec<(Option<usize>, usize)>
+    {
+        let mut moves: Vec<_> = game.legal_moves().iter().map(|game_move|{(game_move.source(), game_move.cell())}).collect();
+        moves.sort();
+        moves
+    }
+
+    #[test]
+    fn pieces_are_placed_before_they_move()
+    {
+        let game = play(false, &[Move::Place(0), Move::Place(1)]);
+        assert_eq!(game.legal_moves().len(), 7);
+        assert!(game.legal_moves().iter().all(|game_move|{matches!(game_move, Move::Place(_))}));
+    }
+
+    #[test]
+    fn pieces_move_orthogonally()
+    {
+        let game = placed(false);
+        assert_eq!(sorted_moves(&game), vec![(Some(8), 5), (Some(8), 7)]);
+    }
+
+    #[test]
+    fn pieces_move_diagonally_if_enabled()
+    {
+        let game = placed(true);
+        assert_eq!(sorted_moves(&game), vec![(Some(3), 7), (Some(8), 5), (Some(8), 7)]);
+    }
+
+    #[test]
+    fn moving_a_piece_empties_its_space()
+    {
+        let mut game = placed(false);
+        game.play_move(&Move::Step(8, 5));
+        assert_eq!(*game.board.space(BoardSpaceLocation::BottomRight), BoardSpace::Empty);
+        assert_eq!(*game.board.space(BoardSpaceLocation::MiddleRight), BoardSpace::X);
+        assert_eq!(game.active_player(), ActivePlayer::PlayerO);
+    }
+
+    #[test]
+    fn third_repetition_is_a_draw()
+    {
+        let placement = [0, 1, 2, 3, 4, 5].map(Move::Place);
+        let shuffle = [Move::Step(4, 7), Move::Step(3, 6), Move::Step(7, 4), Move::Step(6, 3)];
+        let moves: Vec<Move> = placement.into_iter().chain(shuffle.iter().cloned().cycle().take(8)).collect();
+
+        let game = play(false, &moves[..13]);
+        assert_eq!(game.outcome(), Outcome::Incomplete);
+
+        // the position after placing every piece has now occurred three times
+        let game = play(false, &moves);
+        assert_eq!(game.outcome(), Outcome::Draw);
+        assert!(game.legal_moves().is_empty());
+    }
+
+    #[test]
+    fn player_who_cannot_move_loses()
+    {
+        // three pieces each can never block every move without completing a line, so O
+        // is given a fourth piece
+        let mut game = Morris::new(ActivePlayer::PlayerX, false);
+        for (space, board_space) in [(0, BoardSpace::X), (1, BoardSpace::X), (4, BoardSpace::X),
+            (2, BoardSpace::O), (3, BoardSpace::O), (5, BoardSpace::O), (7, BoardSpace::O)] {
+            *game.board.space_mut(space_location(space)) = board_space;
+        }
+        assert_eq!(game.outcome(), Outcome::Win(ActivePlayer::PlayerO));
+        assert!(game.legal_moves().is_empty());
+
+        // with diagonal moves, X can move from 4 to 6 or 8
+        game.diagonal_moves = true;
+        assert_eq!(game.outcome(), Outcome::Incomplete);
+    }
+}
@@ -0,0 +1,285 @@
+//! Notakto, where both players place X's on one or more boards
+//!
+//! A board is dead once it contains three X's in a row, and no more moves can be played on it.
+//! Whoever kills the last board loses.
+//!
+//! The AI plays perfectly using the misère quotient of Notakto, found by Plambeck and Whitehead
+//! in "The Secrets of Notakto". Every board position is assigned an element of a small
+//! commutative monoid, and the player to move loses exactly when the product of the values of
+//! all the boards is one of the four P-positions.
+
+use std::ops::Mul;
+
+use crate::{
+    active_player::ActivePlayer,
+    gameboard::{GameBoard, BoardSpace, BoardSpaceLocation},
+    game_outcome::{GameOutcome, WinPosition}
+};
+
+use super::{Variant, Move, Outcome, BoardView, CellView, CellStyle, space_index, space_location};
+
+/// The number of cells in each board
+const BOARD_CELLS: usize = 9;
+
+/// The number of boards drawn in each row
+const BOARDS_PER_ROW: usize = 3;
+
+/// A game of Notakto
+///
+/// Cells are numbered board by board, with the cells of each board numbered from 0 to 8,
+/// row by row from the top left. Cell `i` of board `b` has index `b * 9 + i`.
+#[derive(Clone)]
+pub struct Notakto {
+    boards: Vec<GameBoard>,
+    active_player: ActivePlayer
+}
+
+impl Notakto {
+
+    /// The smallest number of boards a game can be played on
+    pub const MIN_BOARDS: usize = 1;
+    /// The largest number of boards a game can be played on
+    pub const MAX_BOARDS: usize = 6;
+
+    /// Creates a new game on `board_count` boards, with `first_player` to move first
+    ///
+    /// `board_count` is clamped to between [Notakto::MIN_BOARDS] and [Notakto::MAX_BOARDS].
+    pub fn new(first_player: ActivePlayer, board_count: usize) -> Self
+    {
+        let board_count = board_count.clamp(Self::MIN_BOARDS, Self::MAX_BOARDS);
+        Self{
+            boards: (0..board_count).map(|_|{GameBoard::new()}).collect(),
+            active_player: first_player
+        }
+    }
+
+    /// Returns the line of three that killed the given board, if it is dead
+    fn dead_line(board: &GameBoard) -> Option<WinPosition>
+    {
+        match board.game_outcome() {
+            GameOutcome::PlayerX(win_position) => Some(win_position),
+            _ => None
+        }
+    }
+
+    /// Returns the value of the whole position in the misère quotient
+    fn position_value(&self) -> QuotientElement
+    {
+        self.boards.iter()
+            .map(board_value)
+            .fold(QuotientElement::ONE, Mul::mul)
+    }
+}
+
+impl Variant for Notakto {
+
+    fn active_player(&self) -> ActivePlayer
+    {
+        self.active_player.clone()
+    }
+
+    fn legal_moves(&self) -> Vec<Move>
+    {
+        self.boards.iter().enumerate()
+            .filter(|(_, board)|{Self::dead_line(board).is_none()})
+            .flat_map(|(board_index, board)|{
+                board.all_spaces()
+                    .filter(|(_, space)|{**space == BoardSpace::Empty})
+                    .map(move |(location, _)|{Move::Place(board_index * BOARD_CELLS + space_index(location))})
+            })
+            .collect()
+    }
+
+    fn play_move(&mut self, game_move: &Move)
+    {
+        let cell = game_move.cell();
+        let board = &mut self.boards[cell / BOARD_CELLS];
+        *board.space_mut(space_location(cell % BOARD_CELLS)) = BoardSpace::X;
+        self.active_player.switch();
+    }
+
+    fn outcome(&self) -> Outcome
+    {
+        // the player who killed the last board has just moved, so the player to move wins
+        if self.boards.iter().all(|board|{Self::dead_line(board).is_some()}) {
+            Outcome::Win(self.active_player.clone())
+        } else {
+            Outcome::Incomplete
+        }
+    }
+
+    fn heuristic(&self, player: &ActivePlayer) -> f64
+    {
+        // the quotient decides the position exactly, so this is never just an estimate
+        let active_player_loses = self.position_value().is_p_position();
+        if active_player_loses == (*player == self.active_player) {
+            -1.0
+        } else {
+            1.0
+        }
+    }
+
+    fn boards(&self) -> Vec<BoardView>
+    {
+        self.boards.iter().enumerate().map(|(board_index, board)|{
+            let dead_line = Self::dead_line(board);
+
+            let cells = BoardSpaceLocation::all().map(|location|{
+                CellView{
+                    cell: board_index * BOARD_CELLS + space_index(location),
+                    symbol: board.space(location).get_char(),
+                    subscript: None,
+                    style: match dead_line {
+                        Some(win_position) if win_position.as_board_spaces().contains(&location) => CellStyle::Winning,
+                        Some(_) => CellStyle::Inactive,
+                        None => CellStyle::Normal
+                    }
+                }
+            }).collect();
+
+            BoardView{
+                position: ((board_index % BOARDS_PER_ROW) as u16, (board_index / BOARDS_PER_ROW) as u16),
+                columns: 3,
+                cells,
+                style: if dead_line.is_some() {CellStyle::Inactive} else {CellStyle::Normal},
+                // live boards get an empty label, so the layout doesn't change once a board dies
+                label: Some(if dead_line.is_some() {"dead"} else {""}.to_owned())
+            }
+        }).collect()
+    }
+
+    fn clone_variant(&self) -> Box<dyn Variant>
+    {
+        Box::new(self.clone())
+    }
+}
+
+/// An element of the misère quotient of Notakto
+///
+/// The quotient is generated by `a`, `b`, `c` and `d`, subject to
+/// `a² = 1, b³ = b, b²c = c, c³ = ac², b²d = d, cd = ad, d² = c²`.
+/// Elements are kept in the normal form `a^a b^b c^c d^d`, of which there are 18.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct QuotientElement {
+    a: u8,
+    b: u8,
+    c: u8,
+    d: u8
+}
+
+impl QuotientElement {
+
+    const ONE: Self = Self::new(0, 0, 0, 0);
+    const A: Self = Self::new(1, 0, 0, 0);
+    const B: Self = Self::new(0, 1, 0, 0);
+    const C: Self = Self::new(0, 0, 1, 0);
+    const D: Self = Self::new(0, 0, 0, 1);
+
+    /// The elements whose positions are lost by the player to move
+    const P_POSITIONS: [Self; 4] = [
+        Self::new(1, 0, 0, 0),
+        Self::new(0, 2, 0, 0),
+        Self::new(0, 1, 1, 0),
+        Self::new(0, 0, 2, 0)
+    ];
+
+    const fn new(a: u8, b: u8, c: u8, d: u8) -> Self
+    {
+        Self{a, b, c, d}
+    }
+
+    /// Returns the normal form of `a^a b^b c^c d^d`
+    fn normalised(self) -> Self
+    {
+        let Self{mut a, mut b, mut c, mut d} = self;
+        // d² = c²
+        c += d / 2 * 2;
+        d %= 2;
+        // cd = ad
+        if d == 1 {
+            a += c;
+            c = 0;
+        }
+        // c³ = ac²
+        if c > 2 {
+            a += c - 2;
+            c = 2;
+        }
+        // b³ = b
+        if b > 2 {
+            b = 2 - b % 2;
+        }
+        // b²c = c and b²d = d
+        if b == 2 && (c > 0 || d > 0) {
+            b = 0;
+        }
+        // a² = 1
+        a %= 2;
+        Self{a, b, c, d}
+    }
+
+    /// Returns true if the player to move loses a position with this value
+    fn is_p_position(&self) -> bool
+    {
+        Self::P_POSITIONS.contains(self)
+    }
+}
+
+impl Mul for QuotientElement {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self
+    {
+        Self{
+            a: self.a + other.a,
+            b: self.b + other.b,
+            c: self.c + other.c,
+            d: self.d + other.d
+        }.normalised()
+    }
+}
+
+/// Returns the value of a board in the misère quotient
+fn board_value(board: &GameBoard) -> QuotientElement
+{
+    use QuotientElement as Q;
+
+    let mask = board.all_spaces()
+        .filter(|(_, space)|{**space == BoardSpace::X})
+        .fold(0, |mask, (location, _)|{mask | 1 << space_index(location)});
+
+    // values of every live board, up to rotation and reflection, as listed by Plambeck and Whitehead
+    match canonical_mask(mask) {
+        0 => Q::C,
+        16 => Q::C * Q::C,
+        1 | 2 | 98 => Q::ONE,
+        3 => Q::D,
+        14 | 41 | 70 => Q::A * Q::D,
+        5 | 11 | 12 | 17 | 18 | 29 | 30 | 42 | 45 | 99 | 101 | 110 | 113 | 114 | 171 => Q::B,
+        19 | 26 | 69 | 78 | 106 => Q::A * Q::B,
+        10 | 13 | 21 | 27 | 28 | 40 | 43 | 68 | 97 | 102 | 108 | 115 | 170 | 173 | 229 | 238 | 325 => Q::A,
+        // a dead board is the same as no board at all
+        _ => Q::ONE
+    }
+}
+
+/// Returns the smallest mask equivalent to the given mask of X's (bit `i` set for an X
+/// in space `i`) under rotation and reflection of the board
+fn canonical_mask(mask: u16) -> u16
+{
+    // every symmetry of the board is a rotation, optionally preceded by a reflection
+    (0..8).map(|symmetry|{
+        (0..BOARD_CELLS as u16)
+            .filter(|space|{mask & (1 << space) != 0})
+            .fold(0, |transformed, space|{
+                let (mut x, mut y) = (space % 3, space / 3);
+                if symmetry >= 4 {
+                    x = 2 - x;
+                }
+                for _ in 0..symmetry % 4 {
+                    (x, y) = (2 - y, x);
+                }
+                transformed | 1 << (y * 3 + x)
+            })
+    }).min().unwrap_or(mask)
+}
@@ -0,0 +1,196 @@
+//! Numerical tic-tac-toe, where players place numbers and a line must add up to 15
+//!
+//! The player who moves first places the odd numbers (1, 3, 5, 7 and 9) and the other player the
+//! even numbers (2, 4, 6 and 8), each number once. Whoever completes a line of three numbers
+//! adding up to 15 wins, no matter whose numbers are in it.
+
+use std::sync::Arc;
+
+use crate::active_player::ActivePlayer;
+
+use super::{Variant, Move, Outcome, BoardView, CellView, CellStyle, winning_lines};
+
+/// A game of Numerical tic-tac-toe
+///
+/// Spaces are numbered from 0 to 8, row by row from the top left. The numbers still to be
+/// placed are shown on a second board next to the first, which can't be played on.
+#[derive(Clone)]
+pub struct Numerical {
+    /// The number in each space, if any
+    spaces: [Option<u8>; 9],
+    active_player: ActivePlayer,
+    /// The player placing the odd numbers
+    odd_player: ActivePlayer,
+    /// Every line of spaces, shared between copies of the game
+    lines: Arc<Vec<Vec<usize>>>
+}
+
+impl Numerical {
+
+    /// The total of a winning line
+    const TARGET: u8 = 15;
+    /// The index of the first cell of the board of remaining numbers
+    const FIRST_PANEL_CELL: usize = 9;
+
+    /// Creates a new game, with `first_player` to move first (placing the odd numbers)
+    pub fn new(first_player: ActivePlayer) -> Self
+    {
+        Self{
+            spaces: [None; 9],
+            active_player: first_player.clone(),
+            odd_player: first_player,
+            lines: Arc::new(winning_lines(3, 3, 3))
+        }
+    }
+
+    /// Returns the numbers the given player has not placed yet
+    fn remaining_numbers(&self, player: &ActivePlayer) -> Vec<u8>
+    {
+        let first = if *player == self.odd_player {1} else {2};
+        (first..=9).step_by(2)
+            .filter(|number|{!self.spaces.contains(&Some(*number))})
+            .collect()
+    }
+
+    /// Returns the completed line adding up to 15, if any
+    fn winning_line(&self) -> Option<&Vec<usize>>
+    {
+        self.lines.iter().find(|line|{
+            let numbers: Option<Vec<u8>> = line.iter().map(|space|{self.spaces[*space]}).collect();
+            numbers.is_some_and(|numbers|{numbers.iter().sum::<u8>() == Self::TARGET})
+        })
+    }
+
+    /// Returns the empty spaces where the given player could complete a line on their next move
+    fn threats(&self, player: &ActivePlayer) -> Vec<usize>
+    {
+        let remaining = self.remaining_numbers(player);
+        self.lines.iter().filter_map(|line|{
+            let empty: Vec<&usize> = line.iter().filter(|space|{self.spaces[**space].is_none()}).collect();
+            let [space] = empty[..] else {
+                return None;
+            };
+            let total: u8 = line.iter().filter_map(|space|{self.spaces[*space]}).sum();
+            let needed = Self::TARGET.checked_sub(total)?;
+            remaining.contains(&needed).then_some(*space)
+        }).collect()
+    }
+}
+
+impl Variant for Numerical {
+
+    fn active_player(&self) -> ActivePlayer
+    {
+        self.active_player.clone()
+    }
+
+    fn legal_moves(&self) -> Vec<Move>
+    {
+        if self.outcome().game_finished() {
+            return Vec::new();
+        }
+        let numbers = self.remaining_numbers(&self.active_player);
+        (0..9)
+            .filter(|space|{self.spaces[*space].is_none()})
+            .flat_map(|space|{
+                numbers.iter().map(move |number|{Move::PlaceNumber(space, *number)})
+            })
+            .collect()
+    }
+
+    fn play_move(&mut self, game_move: &Move)
+    {
+        let Move::PlaceNumber(space, number) = game_move else {
+            return;
+        };
+        self.spaces[*space] = Some(*number);
+        self.active_player.switch();
+    }
+
+    fn outcome(&self) -> Outcome
+    {
+        // the line was completed by the player who moved last, whoever placed its numbers
+        if self.winning_line().is_some() {
+            Outcome::Win(self.active_player.opposite())
+        } else if self.spaces.iter().all(Option::is_some) {
+            Outcome::Draw
+        } else {
+            Outcome::Incomplete
+        }
+    }
+
+    fn heuristic(&self, player: &ActivePlayer) -> f64
+    {
+        // the player to move wins if they can complete a line; otherwise, they can only block
+        // one of their opponent's spaces
+        let mut opponent_threats = self.threats(&self.active_player.opposite());
+        opponent_threats.sort_unstable();
+        opponent_threats.dedup();
+
+        let score = if !self.threats(&self.active_player).is_empty() {
+            1.0
+        } else {
+            match opponent_threats.len() {
+                0 => 0.0,
+                1 => -0.25,
+                _ => -1.0
+            }
+        };
+
+        if *player == self.active_player {score} else {-score}
+    }
+
+    fn boards(&self) -> Vec<BoardView>
+    {
+        let winning_line = self.winning_line();
+
+        let cells = (0..9).map(|space|{
+            CellView{
+                cell: space,
+                symbol: self.spaces[space].map_or(' ', |number|{char::from(b'0' + number)}),
+                subscript: None,
+                style: if winning_line.is_some_and(|line|{line.contains(&space)}) {
+                    CellStyle::Winning
+                } else {
+                    CellStyle::Normal
+                }
+            }
+        }).collect();
+
+        // the numbers of the player not moving are dimmed
+        let odd_turn = self.active_player == self.odd_player;
+        let remaining: Vec<u8> = [ActivePlayer::PlayerX, ActivePlayer::PlayerO].iter()
+            .flat_map(|player|{self.remaining_numbers(player)})
+            .collect();
+        let panel_cells = (1..=9).map(|number: u8|{
+            CellView{
+                cell: Self::FIRST_PANEL_CELL + number as usize - 1,
+                symbol: if remaining.contains(&number) {char::from(b'0' + number)} else {' '},
+                subscript: None,
+                style: if (number % 2 == 1) == odd_turn {CellStyle::Normal} else {CellStyle::Inactive}
+            }
+        }).collect();
+
+        vec![
+            BoardView{
+                position: (0, 0),
+                columns: 3,
+                cells,
+                style: CellStyle::Normal,
+                label: None
+            },
+            BoardView{
+                position: (1, 0),
+                columns: 3,
+                cells: panel_cells,
+                style: CellStyle::Inactive,
+                label: Some("remaining".to_owned())
+            }
+        ]
+    }
+
+    fn clone_variant(&self) -> Box<dyn Variant>
+    {
+        Box::new(self.clone())
+    }
+}
@@ -0,0 +1,274 @@
+//! Order and Chaos, where one player tries to make a line and the other tries to prevent it
+//!
+//! Both players may place an X or an O in any empty space of a 6x6 board. Order wins as soon
+//! as five matching symbols are in a row, whoever placed them; Chaos wins if the board fills
+//! up without one. Order moves first.
+
+use std::sync::Arc;
+
+use crate::{
+    active_player::ActivePlayer,
+    gameboard::BoardSpace
+};
+
+use super::{Variant, Move, Outcome, BoardView, CellView, CellStyle, winning_lines};
+
+/// A game of Order and Chaos
+///
+/// Cells are numbered row by row from the top left, so the cell in column `x` of row `y`
+/// has index `y * 6 + x`.
+#[derive(Clone)]
+pub struct OrderChaos {
+    cells: Vec<BoardSpace>,
+    active_player: ActivePlayer,
+    /// The player playing Order; their opponent plays Chaos
+    order: ActivePlayer,
+    /// Every line of cells that wins the game for Order, shared between copies of the game
+    lines: Arc<Vec<Vec<usize>>>
+}
+
+impl OrderChaos {
+
+    /// The number of cells in each row and column
+    const SIZE: usize = 6;
+    /// The number of matching symbols in a row that Order needs
+    const LINE_LENGTH: usize = 5;
+
+    /// Creates a new game with `order` playing Order, who moves first
+    pub fn new(order: ActivePlayer) -> Self
+    {
+        Self{
+            cells: vec![BoardSpace::Empty; Self::SIZE * Self::SIZE],
+            active_player: order.clone(),
+            order,
+            lines: Arc::new(winning_lines(Self::SIZE, Self::SIZE, Self::LINE_LENGTH))
+        }
+    }
+
+    /// Returns the line of five matching symbols, if any
+    fn winning_line(&self) -> Option<&Vec<usize>>
+    {
+        self.lines.iter().find(|line|{
+            let first = &self.cells[line[0]];
+            *first != BoardSpace::Empty && line.iter().all(|cell|{self.cells[*cell] == *first})
+        })
+    }
+
+    /// Returns the number of symbols in each line that can still be completed
+    ///
+    /// A line can be completed while it holds only one of the two symbols.
+    fn open_lines(&self) -> Vec<usize>
+    {
+        self.lines.iter().filter_map(|line|{
+            let symbols: Vec<&BoardSpace> = line.iter()
+                .map(|cell|{&self.cells[*cell]})
+                .filter(|space|{**space != BoardSpace::Empty})
+                .collect();
+            symbols.iter().all(|symbol|{*symbol == symbols[0]}).then_some(symbols.len())
+        }).collect()
+    }
+
+    /// Returns every empty cell that would complete a line, along with the symbol that would
+    /// complete it
+    fn threats(&self) -> Vec<(usize, BoardSpace)>
+    {
+        let mut threats: Vec<(usize, BoardSpace)> = self.lines.iter().filter_map(|line|{
+            let empty: Vec<usize> = line.iter().copied().filter(|cell|{self.cells[*cell] == BoardSpace::Empty}).collect();
+            let [cell] = empty[..] else {
+                return None;
+            };
+            let symbol = line.iter().map(|other|{&self.cells[*other]}).find(|space|{**space != BoardSpace::Empty})?;
+            line.iter()
+                .all(|other|{*other == cell || self.cells[*other] == *symbol})
+                .then(||{(cell, symbol.clone())})
+        }).collect();
+        threats.sort_by_key(|(cell, symbol)|{(*cell, *symbol == BoardSpace::X)});
+        threats.dedup();
+        threats
+    }
+}
+
+impl Variant for OrderChaos {
+
+    fn active_player(&self) -> ActivePlayer
+    {
+        self.active_player.clone()
+    }
+
+    fn legal_moves(&self) -> Vec<Move>
+    {
+        if self.outcome().game_finished() {
+            return Vec::new();
+        }
+        self.cells.iter().enumerate()
+            .filter(|(_, space)|{**space == BoardSpace::Empty})
+            .flat_map(|(cell, _)|{
+                [BoardSpace::X, BoardSpace::O].map(|symbol|{Move::PlaceSymbol(cell, symbol)})
+            })
+            .collect()
+    }
+
+    fn play_move(&mut self, game_move: &Move)
+    {
+        let Move::PlaceSymbol(cell, symbol) = game_move else {
+            return;
+        };
+        self.cells[*cell] = symbol.clone();
+        self.active_player.switch();
+    }
+
+    fn outcome(&self) -> Outcome
+    {
+        if self.winning_line().is_some() {
+            Outcome::Win(self.order.clone())
+        } else if self.open_lines().is_empty() {
+            // every line holds both symbols, so the board can only fill up without five in a row
+            Outcome::Win(self.order.opposite())
+        } else {
+            Outcome::Incomplete
+        }
+    }
+
+    fn heuristic(&self, player: &ActivePlayer) -> f64
+    {
+        let threats = self.threats();
+        let threatened_cells = {
+            let mut cells: Vec<usize> = threats.iter().map(|(cell, _)|{*cell}).collect();
+            cells.dedup();
+            cells.len()
+        };
+
+        // Chaos can only block a threat by placing the other symbol in its cell, which fails
+        // if the cell is threatened by both symbols
+        let order_to_move = self.active_player == self.order;
+        let order_score = if (order_to_move && !threats.is_empty()) ||
+            threatened_cells > 1 || threats.len() > threatened_cells {
+            1.0
+        } else {
+            // lines closer to completion count for much more; lines that can no longer be
+            // completed count against Order
+            let open_lines = self.open_lines();
+            let potential: f64 = open_lines.iter().map(|count|{(*count as f64).exp2() - 1.0}).sum();
+            let closed = 1.0 - open_lines.len() as f64 / self.lines.len() as f64;
+            (potential / 32.0).tanh() - closed
+        };
+
+        if *player == self.order {order_score} else {-order_score}
+    }
+
+    fn boards(&self) -> Vec<BoardView>
+    {
+        let winning_line = self.winning_line();
+
+        let cells = self.cells.iter().enumerate().map(|(cell, space)|{
+            CellView{
+                cell,
+                symbol: space.get_char(),
+                subscript: None,
+                style: if winning_line.is_some_and(|line|{line.contains(&cell)}) {
+                    CellStyle::Winning
+                } else {
+                    CellStyle::Normal
+                }
+            }
+        }).collect();
+
+        vec![BoardView{
+            position: (0, 0),
+            columns: Self::SIZE as u16,
+            cells,
+            style: CellStyle::Normal,
+            label: Some(format!("Order: {}  Chaos: {}", self.order.get_char(), self.order.opposite().get_char()))
+        }]
+    }
+
+    fn clone_variant(&self) -> Box<dyn Variant>
+    {
+        Box::new(self.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Creates a game with X playing Order and the given rows of symbols (`X`, `O` or `-`)
+    fn from_rows(rows: [&str; 6]) -> OrderChaos
+    {
+        let mut game = OrderChaos::new(ActivePlayer::PlayerX);
+        for (cell, symbol) in rows.concat().chars().enumerate() {
+            game.cells[cell] = match symbol {
+                'X' => BoardSpace::X,
+                'O' => BoardSpace::O,
+                _ => BoardSpace::Empty
+            };
+        }
+        game
+    }
+
+    #[test]
+    fn five_in_a_row_wins_for_order()
+    {
+        let mut game = from_rows([
+            "XXXX--",
+            "------",
+            "------",
+            "------",
+            "------",
+            "OOOO--"
+        ]);
+        assert_eq!(game.outcome(), Outcome::Incomplete);
+        game.play_move(&Move::PlaceSymbol(4, BoardSpace::X));
+        assert_eq!(game.outcome(), Outcome::Win(ActivePlayer::PlayerX));
+        assert_eq!(game.winning_line(), Some(&vec![0, 1, 2, 3, 4]));
+
+        // Order's symbols don't matter, so a line of O wins for Order too
+        let mut game = from_rows([
+            "------",
+            "------",
+            "------",
+            "------",
+            "------",
+            "OOOO--"
+        ]);
+        game.play_move(&Move::PlaceSymbol(34, BoardSpace::O));
+        assert_eq!(game.outcome(), Outcome::Win(ActivePlayer::PlayerX));
+    }
+
+    #[test]
+    fn six_in_a_row_wins_for_order()
+    {
+        let mut game = from_rows([
+            "O-----",
+            "O-----",
+            "O-----",
+            "------",
+            "O-----",
+            "O-----"
+        ]);
+        assert_eq!(game.outcome(), Outcome::Incomplete);
+        game.play_move(&Move::PlaceSymbol(18, BoardSpace::O));
+        assert_eq!(game.outcome(), Outcome::Win(ActivePlayer::PlayerX));
+    }
+
+    #[test]
+    fn chaos_wins_once_no_line_can_be_completed()
+    {
+        // every line holds both symbols except the two lines of the first row, which have no O yet
+        let mut game = from_rows([
+            "-X----",
+            "-O-OX-",
+            "--X-O-",
+            "X-O--X",
+            "O--X-O",
+            "-X--O-"
+        ]);
+        assert_eq!(game.open_lines(), vec![1, 1]);
+        assert_eq!(game.outcome(), Outcome::Incomplete);
+
+        game.play_move(&Move::PlaceSymbol(4, BoardSpace::O));
+        assert!(game.cells.contains(&BoardSpace::Empty));
+        assert_eq!(game.outcome(), Outcome::Win(ActivePlayer::PlayerO));
+        assert!(game.legal_moves().is_empty());
+    }
+}
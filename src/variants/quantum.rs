@@ -0,0 +1,462 @@
+//! Quantum tic-tac-toe, where each move places a "spooky" mark in two squares at once
+//!
+//! A spooky mark is in superposition: it will end up in exactly one of its two squares, but
+//! which one isn't decided yet. Two spooky marks that share a square are entangled. When a move
+//! closes a cycle of entanglement, the cycle must collapse: the next player chooses which of its
+//! two squares the mark that closed the cycle ends up in, and every mark entangled with it is
+//! forced into its other square, and so on. Collapsed marks are classical, and only classical
+//! marks count towards a line.
+//!
+//! A collapse can complete lines for both players at once. The player whose line was finished
+//! first (whose line's highest move number is lowest) wins, and their opponent gets half a point,
+//! which is shown on the board and reported by [Variant::half_point].
+
+use std::sync::Arc;
+
+use crate::active_player::ActivePlayer;
+
+use super::{Variant, Move, Outcome, BoardView, CellView, CellStyle, winning_lines};
+
+/// A mark of one player, placed by the move with the given number (counting from 1)
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Mark {
+    player: ActivePlayer,
+    number: u8
+}
+
+impl Mark {
+    /// Returns the subscript character for the number of this mark
+    fn subscript(&self) -> char
+    {
+        // subscript digits are consecutive characters, and a game never has more than nine moves
+        char::from_u32('₀' as u32 + self.number as u32).unwrap_or(' ')
+    }
+}
+
+/// A mark in superposition between two squares
+#[derive(Debug, Clone)]
+struct SpookyMark {
+    mark: Mark,
+    squares: [usize; 2]
+}
+
+/// A line of classical marks of one player, along with the highest move number in the line
+type CompletedLine<'a> = (&'a Vec<usize>, ActivePlayer, u8);
+
+/// A game of Quantum tic-tac-toe
+///
+/// Squares are numbered from 0 to 8, row by row from the top left. Each square is drawn as a
+/// board of its own, with a slot for the mark of each move; the middle slot of each square
+/// has the index of the square, and is the one selected to play there.
+#[derive(Clone)]
+pub struct Quantum {
+    /// The collapsed mark in each square, if any
+    classical: [Option<Mark>; 9],
+    /// Every mark still in superposition
+    spooky: Vec<SpookyMark>,
+    active_player: ActivePlayer,
+    /// The number of the next mark to be placed
+    next_number: u8,
+    /// The number of the spooky mark that closed a cycle, if the cycle hasn't been collapsed yet
+    pending_collapse: Option<u8>,
+    /// Every line of squares that wins the game, shared between copies of the game
+    lines: Arc<Vec<Vec<usize>>>
+}
+
+impl Quantum {
+
+    /// The index of the first slot (other than the middle slots) of the squares' boards
+    const FIRST_SLOT: usize = 9;
+    /// The slot of each square's board that is selected to play there
+    const MIDDLE_SLOT: usize = 4;
+
+    /// Creates a new game, with `first_player` to move first
+    pub fn new(first_player: ActivePlayer) -> Self
+    {
+        Self{
+            classical: Default::default(),
+            spooky: Vec::new(),
+            active_player: first_player,
+            next_number: 1,
+            pending_collapse: None,
+            lines: Arc::new(winning_lines(3, 3, 3))
+        }
+    }
+
+    /// Returns every square without a classical mark
+    fn open_squares(&self) -> Vec<usize>
+    {
+        (0..9).filter(|square|{self.classical[*square].is_none()}).collect()
+    }
+
+    /// Returns the spooky mark with the given number
+    fn spooky_mark(&self, number: u8) -> Option<&SpookyMark>
+    {
+        self.spooky.iter().find(|spooky|{spooky.mark.number == number})
+    }
+
+    /// Returns true if the two squares are connected by a chain of entangled spooky marks
+    fn entangled(&self, from: usize, to: usize) -> bool
+    {
+        let mut reached = vec![from];
+        let mut index = 0;
+        while let Some(&square) = reached.get(index) {
+            if square == to {
+                return true;
+            }
+            for spooky in &self.spooky {
+                if let Some(position) = spooky.squares.iter().position(|other|{*other == square}) {
+                    let other = spooky.squares[1 - position];
+                    if !reached.contains(&other) {
+                        reached.push(other);
+                    }
+                }
+            }
+            index += 1;
+        }
+        false
+    }
+
+    /// Collapses the spooky mark with the given number into the given square
+    ///
+    /// Every spooky mark that shares the square is forced into its other square, which forces
+    /// the marks sharing that square, and so on.
+    fn collapse(&mut self, number: u8, square: usize)
+    {
+        let mut forced = vec![(number, square)];
+        while let Some((number, square)) = forced.pop() {
+            let Some(index) = self.spooky.iter().position(|spooky|{spooky.mark.number == number}) else {
+                continue;
+            };
+            let spooky = self.spooky.remove(index);
+            if self.classical[square].is_some() {
+                continue;
+            }
+            self.classical[square] = Some(spooky.mark);
+
+            forced.extend(self.spooky.iter()
+                .filter_map(|other|{
+                    let position = other.squares.iter().position(|other_square|{*other_square == square})?;
+                    Some((other.mark.number, other.squares[1 - position]))
+                }));
+        }
+    }
+
+    /// Returns every line of classical marks of one player
+    fn completed_lines(&self) -> Vec<CompletedLine<'_>>
+    {
+        self.lines.iter().filter_map(|line|{
+            let marks: Vec<&Mark> = line.iter().filter_map(|square|{self.classical[*square].as_ref()}).collect();
+            let player = &marks.first()?.player;
+            if marks.len() == line.len() && marks.iter().all(|mark|{mark.player == *player}) {
+                let highest = marks.iter().map(|mark|{mark.number}).max().unwrap_or(0);
+                Some((line, player.clone(), highest))
+            } else {
+                None
+            }
+        }).collect()
+    }
+
+    /// Returns the line that won the game, if any, and the line of the opponent that was
+    /// completed by the same collapse, if any
+    fn deciding_lines(&self) -> Option<(CompletedLine<'_>, Option<CompletedLine<'_>>)>
+    {
+        let lines = self.completed_lines();
+        let winning = lines.iter().min_by_key(|(_, _, highest)|{*highest})?.clone();
+        let opposing = lines.into_iter()
+            .filter(|(_, player, _)|{*player != winning.1})
+            .min_by_key(|(_, _, highest)|{*highest});
+        Some((winning, opposing))
+    }
+}
+
+impl Variant for Quantum {
+
+    fn active_player(&self) -> ActivePlayer
+    {
+        self.active_player.clone()
+    }
+
+    fn legal_moves(&self) -> Vec<Move>
+    {
+        if self.outcome().game_finished() {
+            return Vec::new();
+        }
+
+        if let Some(spooky) = self.pending_collapse.and_then(|number|{self.spooky_mark(number)}) {
+            return spooky.squares.iter().map(|square|{Move::Place(*square)}).collect();
+        }
+
+        let open = self.open_squares();
+        if let [square] = open[..] {
+            // the last square can only take a classical mark
+            return vec![Move::Place(square)];
+        }
+
+        // each pair is listed once, with the lower square first; the squares can still be
+        // selected in either order (see Move::cell_after)
+        open.iter()
+            .flat_map(|first|{
+                open.iter().filter(move |second|{*second > first}).map(|second|{Move::Entangle(*first, *second)})
+            })
+            .collect()
+    }
+
+    fn play_move(&mut self, game_move: &Move)
+    {
+        match game_move {
+            Move::Entangle(first, second) => {
+                let cycle = self.entangled(*first, *second);
+                let mark = Mark{player: self.active_player.clone(), number: self.next_number};
+                self.spooky.push(SpookyMark{mark, squares: [*first, *second]});
+                if cycle {
+                    // the next player chooses how the cycle collapses
+                    self.pending_collapse = Some(self.next_number);
+                }
+                self.next_number += 1;
+                self.active_player.switch();
+            },
+            Move::Place(square) => {
+                if let Some(number) = self.pending_collapse.take() {
+                    // the player who chose the collapse still makes their own move
+                    self.collapse(number, *square);
+                } else {
+                    self.classical[*square] = Some(Mark{player: self.active_player.clone(), number: self.next_number});
+                    self.next_number += 1;
+                    self.active_player.switch();
+                }
+            },
+            Move::Step(..) | Move::PlaceSymbol(..) | Move::PlaceNumber(..) => {}
+        }
+    }
+
+    fn outcome(&self) -> Outcome
+    {
+        if self.pending_collapse.is_some() {
+            return Outcome::Incomplete;
+        }
+        match self.deciding_lines() {
+            Some(((_, winner, _), _)) => Outcome::Win(winner),
+            None if self.open_squares().is_empty() => Outcome::Draw,
+            None => Outcome::Incomplete
+        }
+    }
+
+    fn half_point(&self) -> Option<ActivePlayer>
+    {
+        if !self.outcome().game_finished() {
+            return None;
+        }
+        let (_, opposing) = self.deciding_lines()?;
+        opposing.map(|(_, player, _)|{player})
+    }
+
+    fn heuristic(&self, player: &ActivePlayer) -> f64
+    {
+        // classical marks count fully and spooky marks partly, in lines the opponent
+        // hasn't blocked with a classical mark
+        let score: f64 = self.lines.iter().map(|line|{
+            let line_score = |owner: &ActivePlayer|{
+                let mut value = 0.0;
+                for square in line {
+                    match &self.classical[*square] {
+                        Some(mark) if mark.player == *owner => value += 2.0,
+                        Some(_) => return 0.0,
+                        None => {
+                            if self.spooky.iter().any(|spooky|{
+                                spooky.mark.player == *owner && spooky.squares.contains(square)
+                            }) {
+                                value += 1.0;
+                            }
+                        }
+                    }
+                }
+                value * value
+            };
+            line_score(player) - line_score(&player.opposite())
+        }).sum();
+
+        (score / 50.0).tanh()
+    }
+
+    fn boards(&self) -> Vec<BoardView>
+    {
+        let finished = self.outcome().game_finished();
+        let deciding_lines = self.deciding_lines();
+        let collapse = self.pending_collapse.and_then(|number|{self.spooky_mark(number)});
+        let playable: Vec<usize> = self.legal_moves().iter().flat_map(|game_move|{
+            [Some(game_move.cell()), game_move.source()]
+        }).flatten().collect();
+
+        (0..9).map(|square|{
+            let winning = deciding_lines.as_ref().is_some_and(|((line, _, _), _)|{line.contains(&square)});
+
+            let cells = (0..9).map(|slot|{
+                let cell = if slot == Self::MIDDLE_SLOT {square} else {Self::FIRST_SLOT + square * 9 + slot};
+                // the classical mark is shown in the middle, spooky marks in the slot of their move
+                let mark = match &self.classical[square] {
+                    Some(mark) if slot == Self::MIDDLE_SLOT => Some((mark, CellStyle::Normal)),
+                    Some(_) => None,
+                    None => self.spooky.iter()
+                        .find(|spooky|{spooky.mark.number as usize == slot + 1 && spooky.squares.contains(&square)})
+                        .map(|spooky|{
+                            let closing_cycle = collapse.is_some_and(|collapse|{collapse.mark == spooky.mark});
+                            (&spooky.mark, if closing_cycle {CellStyle::Winning} else {CellStyle::Inactive})
+                        })
+                };
+                CellView{
+                    cell,
+                    symbol: mark.map_or(' ', |(mark, _)|{mark.player.get_char()}),
+                    subscript: mark.map(|(mark, _)|{mark.subscript()}),
+                    style: match mark {
+                        _ if winning && slot == Self::MIDDLE_SLOT => CellStyle::Winning,
+                        Some((_, style)) => style,
+                        None => CellStyle::Normal
+                    }
+                }
+            }).collect();
+
+            let label = match (collapse, &deciding_lines) {
+                (Some(collapse), _) if collapse.squares.contains(&square) => {
+                    format!("put {}{} here", collapse.mark.player.get_char(), collapse.mark.subscript())
+                },
+                // the opponent's line completed by the same collapse is worth half a point
+                (_, Some((_, Some((line, player, highest))))) if line.iter().any(|square_in_line|{
+                    *square_in_line == square &&
+                        self.classical[square].as_ref().is_some_and(|mark|{mark.number == *highest})
+                }) => format!("{}: ½ point", player.get_char()),
+                _ => String::new()
+            };
+
+            BoardView{
+                position: ((square % 3) as u16, (square / 3) as u16),
+                columns: 3,
+                cells,
+                style: if winning {
+                    CellStyle::Winning
+                } else if !finished && !playable.contains(&square) {
+                    CellStyle::Inactive
+                } else {
+                    CellStyle::Normal
+                },
+                label: Some(label)
+            }
+        }).collect()
+    }
+
+    fn clone_variant(&self) -> Box<dyn Variant>
+    {
+        Box::new(self.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Plays the given moves in order from a new game with X to move first
+    fn play(moves: &[Move]) -> Quantum
+    {
+        let mut game = Quantum::new(ActivePlayer::PlayerX);
+        for game_move in moves {
+            assert!(game.legal_moves().contains(game_move), "{:?} should be legal", game_move);
+            game.play_move(game_move);
+        }
+        game
+    }
+
+    /// Returns the player and number of the classical mark in each square
+    fn classical(game: &Quantum) -> Vec<Option<(ActivePlayer, u8)>>
+    {
+        game.classical.iter().map(|mark|{mark.as_ref().map(|mark|{(mark.player.clone(), mark.number)})}).collect()
+    }
+
+    #[test]
+    fn each_entangle_move_is_listed_once()
+    {
+        let moves = Quantum::new(ActivePlayer::PlayerX).legal_moves();
+        assert_eq!(moves.len(), 36);
+        for game_move in &moves {
+            let Move::Entangle(first, second) = game_move else {
+                panic!("{:?} should be an entangle move", game_move);
+            };
+            assert!(first < second);
+        }
+    }
+
+    #[test]
+    fn closing_a_cycle_lets_the_next_player_collapse_it()
+    {
+        let mut game = play(&[Move::Entangle(0, 1), Move::Entangle(0, 1)]);
+        assert_eq!(game.pending_collapse, Some(2));
+        assert_eq!(game.active_player(), ActivePlayer::PlayerX);
+        assert_eq!(game.legal_moves(), vec![Move::Place(0), Move::Place(1)]);
+        assert_eq!(game.outcome(), Outcome::Incomplete);
+
+        // O's mark goes where X chooses, and X's mark is forced into the other square
+        game.play_move(&Move::Place(0));
+        assert!(game.spooky.is_empty());
+        assert_eq!(game.classical[0].as_ref().map(|mark|{mark.number}), Some(2));
+        assert_eq!(game.classical[1].as_ref().map(|mark|{mark.number}), Some(1));
+        // collapsing isn't X's move, so X still moves next
+        assert_eq!(game.active_player(), ActivePlayer::PlayerX);
+        assert_eq!(game.legal_moves().len(), 21);
+    }
+
+    #[test]
+    fn collapse_follows_the_whole_cycle()
+    {
+        let mut game = play(&[
+            Move::Entangle(0, 3),
+            Move::Entangle(1, 3),
+            Move::Entangle(1, 4),
+            Move::Entangle(2, 4),
+            Move::Entangle(2, 5),
+            Move::Entangle(0, 5)
+        ]);
+        assert_eq!(game.pending_collapse, Some(6));
+        game.play_move(&Move::Place(5));
+
+        let x = |number|{Some((ActivePlayer::PlayerX, number))};
+        let o = |number|{Some((ActivePlayer::PlayerO, number))};
+        assert_eq!(classical(&game), vec![x(1), x(3), x(5), o(2), o(4), o(6), None, None, None]);
+    }
+
+    #[test]
+    fn simultaneous_lines_give_the_later_line_half_a_point()
+    {
+        // the collapse completes X's top row (by move 5) and O's middle row (by move 6)
+        let mut game = play(&[
+            Move::Entangle(0, 3),
+            Move::Entangle(1, 3),
+            Move::Entangle(1, 4),
+            Move::Entangle(2, 4),
+            Move::Entangle(2, 5),
+            Move::Entangle(0, 5)
+        ]);
+        assert_eq!(game.half_point(), None);
+        game.play_move(&Move::Place(5));
+
+        assert_eq!(game.outcome(), Outcome::Win(ActivePlayer::PlayerX));
+        assert_eq!(game.half_point(), Some(ActivePlayer::PlayerO));
+        assert!(game.legal_moves().is_empty());
+    }
+
+    #[test]
+    fn single_line_wins_without_half_point()
+    {
+        // whichever way it collapses, the cycle puts all three of X's marks in the top row
+        let mut game = play(&[
+            Move::Entangle(0, 1),
+            Move::Entangle(3, 6),
+            Move::Entangle(1, 2),
+            Move::Entangle(4, 7),
+            Move::Entangle(0, 2)
+        ]);
+        assert_eq!(game.outcome(), Outcome::Incomplete);
+        game.play_move(&Move::Place(0));
+
+        assert_eq!(game.outcome(), Outcome::Win(ActivePlayer::PlayerX));
+        assert_eq!(game.half_point(), None);
+    }
+}
@@ -0,0 +1,225 @@
+//! 3D tic-tac-toe (Qubic)
+//!
+//! The game is played in a 4×4×4 cube, and the first player to claim four spaces in a line wins.
+//! Lines can run along any row, column or pillar, along the diagonals of any slice of the cube,
+//! or along the four space diagonals through its centre, for 76 lines in total.
+
+use std::sync::OnceLock;
+
+use crate::{
+    active_player::ActivePlayer,
+    gameboard::BoardSpace
+};
+
+use super::{Variant, Move, Outcome, BoardView, CellView, CellStyle};
+
+/// The number of spaces along each edge of the cube
+const SIZE: usize = 4;
+
+/// A game of Qubic
+///
+/// The cube is drawn as four layers side by side. Spaces are numbered layer by layer, then
+/// row by row within each layer from the top left: the space at column `x`, row `y` of layer
+/// `z` is cell `z * 16 + y * 4 + x`.
+#[derive(Clone)]
+pub struct Qubic {
+    spaces: [BoardSpace; SIZE * SIZE * SIZE],
+    active_player: ActivePlayer
+}
+
+impl Qubic {
+
+    /// Creates a new game, with `first_player` to move first
+    pub fn new(first_player: ActivePlayer) -> Self
+    {
+        Self{
+            spaces: std::array::from_fn(|_|{BoardSpace::Empty}),
+            active_player: first_player
+        }
+    }
+
+    /// Returns the first line claimed entirely by one player, and that player
+    fn winning_line(&self) -> Option<(&'static [usize; SIZE], ActivePlayer)>
+    {
+        winning_lines().iter().find_map(|line|{
+            let first = &self.spaces[line[0]];
+            let player = match first {
+                BoardSpace::X => ActivePlayer::PlayerX,
+                BoardSpace::O => ActivePlayer::PlayerO,
+                BoardSpace::Empty | BoardSpace::Blocked => return None
+            };
+            line.iter().all(|cell|{&self.spaces[*cell] == first}).then_some((line, player))
+        })
+    }
+}
+
+impl Variant for Qubic {
+
+    fn active_player(&self) -> ActivePlayer
+    {
+        self.active_player.clone()
+    }
+
+    fn legal_moves(&self) -> Vec<Move>
+    {
+        if self.outcome().game_finished() {
+            return Vec::new();
+        }
+        (0..self.spaces.len())
+            .filter(|cell|{self.spaces[*cell] == BoardSpace::Empty})
+            .map(Move::Place)
+            .collect()
+    }
+
+    fn play_move(&mut self, game_move: &Move)
+    {
+        let cell = game_move.cell();
+        self.spaces[cell] = self.active_player.get_board_space();
+        self.active_player.switch();
+    }
+
+    fn outcome(&self) -> Outcome
+    {
+        if let Some((_, winner)) = self.winning_line() {
+            Outcome::Win(winner)
+        } else if self.spaces.iter().all(|space|{*space != BoardSpace::Empty}) {
+            Outcome::Draw
+        } else {
+            Outcome::Incomplete
+        }
+    }
+
+    fn heuristic(&self, player: &ActivePlayer) -> f64
+    {
+        // lines only one player can still complete are worth more the closer they are to complete
+        const LINE_WEIGHTS: [f64; SIZE] = [0.0, 1.0, 4.0, 16.0];
+
+        let own_space = player.get_board_space();
+        let score: f64 = winning_lines().iter().map(|line|{
+            let ours = line.iter().filter(|cell|{self.spaces[**cell] == own_space}).count();
+            let empty = line.iter().filter(|cell|{self.spaces[**cell] == BoardSpace::Empty}).count();
+            let theirs = SIZE - ours - empty;
+            match (ours, theirs) {
+                (ours, 0) if ours < SIZE => LINE_WEIGHTS[ours],
+                (0, theirs) if theirs < SIZE => -LINE_WEIGHTS[theirs],
+                _ => 0.0
+            }
+        }).sum();
+
+        (score / 20.0).tanh()
+    }
+
+    fn boards(&self) -> Vec<BoardView>
+    {
+        let winning_line = self.winning_line().map(|(line, _)|{line});
+
+        (0..SIZE).map(|layer|{
+            let cells = (0..SIZE * SIZE).map(|index|{
+                let cell = layer * SIZE * SIZE + index;
+                CellView{
+                    cell,
+                    symbol: self.spaces[cell].get_char(),
+                    subscript: None,
+                    style: if winning_line.is_some_and(|line|{line.contains(&cell)}) {
+                        CellStyle::Winning
+                    } else {
+                        CellStyle::Normal
+                    }
+                }
+            }).collect();
+
+            BoardView{
+                position: (layer as u16, 0),
+                columns: SIZE as u16,
+                cells,
+                style: CellStyle::Normal,
+                label: Some(format!("layer {}", layer + 1))
+            }
+        }).collect()
+    }
+
+    fn clone_variant(&self) -> Box<dyn Variant>
+    {
+        Box::new(self.clone())
+    }
+}
+
+/// Returns every line of four cells in the cube
+///
+/// Lines are found by stepping from every cell in each of the 13 directions that are not the
+/// reverse of another, keeping the lines that stay within the cube.
+fn winning_lines() -> &'static [[usize; SIZE]]
+{
+    static LINES: OnceLock<Vec<[usize; SIZE]>> = OnceLock::new();
+    LINES.get_or_init(||{
+        let size = SIZE as i32;
+        let directions = (-1..=1).flat_map(|dz|{
+            (-1..=1).flat_map(move |dy|{
+                (-1..=1).map(move |dx|{(dx, dy, dz)})
+            })
+        }).filter(|direction|{
+            // keep one of each pair of opposite directions
+            *direction > (0, 0, 0)
+        });
+
+        let mut lines = Vec::new();
+        for (dx, dy, dz) in directions {
+            for start in 0..size * size * size {
+                let (x, y, z) = (start % size, start / size % size, start / (size * size));
+                let line: Option<Vec<usize>> = (0..size).map(|step|{
+                    let position = (x + dx * step, y + dy * step, z + dz * step);
+                    in_cube(position).then(||{
+                        (position.2 * size * size + position.1 * size + position.0) as usize
+                    })
+                }).collect();
+                if let Some(line) = line {
+                    lines.push(line.try_into().expect("line has SIZE cells"));
+                }
+            }
+        }
+        lines
+    })
+}
+
+/// Returns true if the given (x, y, z) position is within the cube
+fn in_cube((x, y, z): (i32, i32, i32)) -> bool
+{
+    let range = 0..SIZE as i32;
+    range.contains(&x) && range.contains(&y) && range.contains(&z)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn there_are_76_distinct_lines()
+    {
+        let lines = winning_lines();
+        assert_eq!(lines.len(), 76);
+
+        let mut sorted: Vec<[usize; SIZE]> = lines.iter().map(|line|{
+            let mut line = *line;
+            line.sort();
+            line
+        }).collect();
+        sorted.sort();
+        sorted.dedup();
+        assert_eq!(sorted.len(), 76);
+    }
+
+    #[test]
+    fn space_diagonal_wins()
+    {
+        let mut game = Qubic::new(ActivePlayer::PlayerX);
+        // X climbs the space diagonal from the top left of layer 1, while O plays elsewhere
+        for (x_cell, o_cell) in [(0, 1), (21, 2), (42, 3)] {
+            game.play_move(&Move::Place(x_cell));
+            game.play_move(&Move::Place(o_cell));
+        }
+        assert_eq!(game.outcome(), Outcome::Incomplete);
+        game.play_move(&Move::Place(63));
+        assert_eq!(game.outcome(), Outcome::Win(ActivePlayer::PlayerX));
+        assert!(game.legal_moves().is_empty());
+    }
+}
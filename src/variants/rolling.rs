@@ -0,0 +1,315 @@
+//! Rolling tic-tac-toe, where each player may only have three pieces on the board
+//!
+//! Once a player has three pieces on the board, placing another removes their oldest piece.
+//! The board can never fill up, so the game can only end in a draw by repetition: if the same
+//! position (including the order the pieces were placed in) occurs for the third time,
+//! the game is drawn.
+
+use std::collections::VecDeque;
+
+use crate::{
+    active_player::ActivePlayer,
+    gameboard::{GameBoard, BoardSpace, BoardSpaceLocation},
+    game_outcome::{GameOutcome, WinPosition}
+};
+
+use super::{Variant, Move, Outcome, BoardView, CellView, CellStyle, space_index, space_location};
+
+/// The number of pieces each player may have on the board
+const MAX_PIECES: usize = 3;
+
+/// The number of times a position must occur for the game to be drawn
+const REPETITION_LIMIT: usize = 3;
+
+/// A game of Rolling tic-tac-toe
+///
+/// Spaces are numbered from 0 to 8, row by row from the top left.
+#[derive(Clone)]
+pub struct Rolling {
+    board: GameBoard,
+    active_player: ActivePlayer,
+    /// The pieces of player X, oldest first
+    x_pieces: VecDeque<BoardSpaceLocation>,
+    /// The pieces of player O, oldest first
+    o_pieces: VecDeque<BoardSpaceLocation>,
+    /// Every position so far, as returned by [Rolling::position_key]
+    positions: Vec<u32>
+}
+
+impl Rolling {
+
+    /// Creates a new game, with `first_player` to move first
+    pub fn new(first_player: ActivePlayer) -> Self
+    {
+        let mut new_instance = Self{
+            board: GameBoard::new(),
+            active_player: first_player,
+            x_pieces: VecDeque::new(),
+            o_pieces: VecDeque::new(),
+            positions: Vec::new()
+        };
+        new_instance.positions.push(new_instance.position_key());
+        new_instance
+    }
+
+    /// Returns the pieces of the given player, oldest first
+    fn pieces(&self, player: &ActivePlayer) -> &VecDeque<BoardSpaceLocation>
+    {
+        match player {
+            ActivePlayer::PlayerX => &self.x_pieces,
+            ActivePlayer::PlayerO => &self.o_pieces
+        }
+    }
+
+    /// Returns the piece the given player will lose when they next place a piece, if any
+    fn vanishing_piece(&self, player: &ActivePlayer) -> Option<BoardSpaceLocation>
+    {
+        let pieces = self.pieces(player);
+        if pieces.len() == MAX_PIECES {
+            pieces.front().copied()
+        } else {
+            None
+        }
+    }
+
+    /// Returns a number identifying the current position
+    ///
+    /// Two positions are the same if the same player is to move and both players have the same
+    /// pieces, placed in the same order (so the same pieces will vanish next).
+    fn position_key(&self) -> u32
+    {
+        // each player's pieces are packed into 12 bits, 4 bits per piece
+        let pack = |pieces: &VecDeque<BoardSpaceLocation>|{
+            pieces.iter().fold(0, |key, location|{(key << 4) | (space_index(*location) as u32 + 1)})
+        };
+        let active = match self.active_player {
+            ActivePlayer::PlayerX => 0,
+            ActivePlayer::PlayerO => 1
+        };
+        (active << 24) | (pack(&self.x_pieces) << 12) | pack(&self.o_pieces)
+    }
+
+    /// Returns the winning line, if any
+    fn win_position(&self) -> Option<WinPosition>
+    {
+        match self.board.game_outcome() {
+            GameOutcome::PlayerX(win_position) | GameOutcome::PlayerO(win_position) => Some(win_position),
+            GameOutcome::Draw | GameOutcome::Incomplete => None
+        }
+    }
+}
+
+impl Variant for Rolling {
+
+    fn active_player(&self) -> ActivePlayer
+    {
+        self.active_player.clone()
+    }
+
+    fn legal_moves(&self) -> Vec<Move>
+    {
+        if self.outcome().game_finished() {
+            return Vec::new();
+        }
+        // the vanishing piece is only removed once the new piece is placed, so its space
+        // cannot be played
+        self.board.all_spaces()
+            .filter(|(_, space)|{**space == BoardSpace::Empty})
+            .map(|(location, _)|{Move::Place(space_index(location))})
+            .collect()
+    }
+
+    fn play_move(&mut self, game_move: &Move)
+    {
+        let cell = game_move.cell();
+        let location = space_location(cell);
+        let vanishing_piece = self.vanishing_piece(&self.active_player);
+
+        *self.board.space_mut(location) = self.active_player.get_board_space();
+        let pieces = match self.active_player {
+            ActivePlayer::PlayerX => &mut self.x_pieces,
+            ActivePlayer::PlayerO => &mut self.o_pieces
+        };
+        pieces.push_back(location);
+        if let Some(vanishing_piece) = vanishing_piece {
+            pieces.pop_front();
+            *self.board.space_mut(vanishing_piece) = BoardSpace::Empty;
+        }
+
+        self.active_player.switch();
+        self.positions.push(self.position_key());
+    }
+
+    fn outcome(&self) -> Outcome
+    {
+        match self.board.game_outcome() {
+            GameOutcome::PlayerX(_) => return Outcome::Win(ActivePlayer::PlayerX),
+            GameOutcome::PlayerO(_) => return Outcome::Win(ActivePlayer::PlayerO),
+            GameOutcome::Draw | GameOutcome::Incomplete => {}
+        }
+
+        let position = self.position_key();
+        let repetitions = self.positions.iter().filter(|key|{**key == position}).count();
+        if repetitions >= REPETITION_LIMIT {
+            Outcome::Draw
+        } else {
+            Outcome::Incomplete
+        }
+    }
+
+    fn heuristic(&self, player: &ActivePlayer) -> f64
+    {
+        // pieces that are about to vanish cannot help complete a line
+        let x_vanishing = self.vanishing_piece(&ActivePlayer::PlayerX);
+        let o_vanishing = self.vanishing_piece(&ActivePlayer::PlayerO);
+
+        let score: f64 = WinPosition::all().map(|win_position|{
+            let mut ours = 0;
+            let mut theirs = 0;
+            for location in win_position.as_board_spaces() {
+                let owner = match self.board.space(*location) {
+                    BoardSpace::X if x_vanishing != Some(*location) => ActivePlayer::PlayerX,
+                    BoardSpace::O if o_vanishing != Some(*location) => ActivePlayer::PlayerO,
+                    BoardSpace::Empty => continue,
+                    // a vanishing piece still blocks the line for now
+                    _ => return 0.0
+                };
+                if owner == *player {ours += 1} else {theirs += 1}
+            }
+            match (ours, theirs) {
+                (ours, 0) => [0.0, 1.0, 4.0, 0.0][ours],
+                (0, theirs) => -[0.0, 1.0, 4.0, 0.0][theirs],
+                _ => 0.0
+            }
+        }).sum();
+
+        (score / 8.0).tanh()
+    }
+
+    fn boards(&self) -> Vec<BoardView>
+    {
+        let win_position = self.win_position();
+        // only the active player's oldest piece is about to vanish
+        let vanishing_piece = if win_position.is_none() {
+            self.vanishing_piece(&self.active_player)
+        } else {
+            None
+        };
+
+        let cells = BoardSpaceLocation::all().map(|location|{
+            CellView{
+                cell: space_index(location),
+                symbol: self.board.space(location).get_char(),
+                subscript: None,
+                style: if win_position.is_some_and(|win_position|{win_position.as_board_spaces().contains(&location)}) {
+                    CellStyle::Winning
+                } else if vanishing_piece == Some(location) {
+                    CellStyle::Inactive
+                } else {
+                    CellStyle::Normal
+                }
+            }
+        }).collect();
+
+        vec![BoardView{
+            position: (0, 0),
+            columns: 3,
+            cells,
+            style: CellStyle::Normal,
+            label: None
+        }]
+    }
+
+    fn clone_variant(&self) -> Box<dyn Variant>
+    {
+        Box::new(self.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Plays a piece in each of the given spaces in order from a new game with X to move first
+    fn play(spaces: &[usize]) -> Rolling
+    {
+        let mut game = Rolling::new(ActivePlayer::PlayerX);
+        for space in spaces {
+            assert!(game.legal_moves().contains(&Move::Place(*space)), "{} should be legal", space);
+            game.play_move(&Move::Place(*space));
+        }
+        game
+    }
+
+    /// Returns the style of the cell of the given space
+    fn style(game: &Rolling, space: usize) -> CellStyle
+    {
+        game.boards()[0].cells.iter()
+            .find(|cell|{cell.cell == space})
+            .map(|cell|{cell.style})
+            .expect("every space should have a cell")
+    }
+
+    #[test]
+    fn fourth_piece_removes_the_oldest()
+    {
+        let mut game = play(&[0, 3, 1, 7, 5, 8]);
+        assert_eq!(game.vanishing_piece(&ActivePlayer::PlayerX), Some(BoardSpaceLocation::TopLeft));
+        // the vanishing piece is still on the board until the new one is placed
+        assert!(!game.legal_moves().contains(&Move::Place(0)));
+
+        game.play_move(&Move::Place(6));
+        assert_eq!(*game.board.space(BoardSpaceLocation::TopLeft), BoardSpace::Empty);
+        assert_eq!(*game.board.space(BoardSpaceLocation::BottomLeft), BoardSpace::X);
+        assert_eq!(game.pieces(&ActivePlayer::PlayerX).len(), MAX_PIECES);
+        assert!(game.legal_moves().contains(&Move::Place(0)));
+    }
+
+    #[test]
+    fn only_the_active_players_oldest_piece_is_marked()
+    {
+        let game = play(&[0, 3, 1, 7]);
+        assert!((0..9).all(|space|{style(&game, space) == CellStyle::Normal}));
+
+        // X is to move with three pieces; O's oldest piece isn't about to vanish yet
+        let game = play(&[0, 3, 1, 7, 5, 8]);
+        assert_eq!(style(&game, 0), CellStyle::Inactive);
+        assert_eq!(style(&game, 3), CellStyle::Normal);
+
+        let game = play(&[0, 3, 1, 7, 5, 8, 6]);
+        assert_eq!(style(&game, 0), CellStyle::Normal);
+        assert_eq!(style(&game, 3), CellStyle::Inactive);
+    }
+
+    #[test]
+    fn line_wins_even_with_a_vanishing_piece()
+    {
+        let game = play(&[0, 3, 1, 4, 2]);
+        assert_eq!(game.outcome(), Outcome::Win(ActivePlayer::PlayerX));
+        assert!(game.legal_moves().is_empty());
+    }
+
+    #[test]
+    fn third_repetition_is_a_draw()
+    {
+        // after the first six moves, each player keeps cycling their pieces through four spaces
+        let cycle = [0, 2, 1, 5, 3, 6, 4, 7];
+        let spaces: Vec<usize> = cycle.iter().cycle().take(22).copied().collect();
+
+        let game = play(&spaces[..21]);
+        assert_eq!(game.outcome(), Outcome::Incomplete);
+
+        // the position after the sixth move has now occurred three times
+        let game = play(&spaces);
+        assert_eq!(game.outcome(), Outcome::Draw);
+        assert!(game.legal_moves().is_empty());
+    }
+
+    #[test]
+    fn same_pieces_in_a_different_order_are_a_different_position()
+    {
+        let first = play(&[0, 3, 1, 7, 5, 8]);
+        let second = play(&[1, 3, 0, 7, 5, 8]);
+        assert_ne!(first.position_key(), second.position_key());
+    }
+}
@@ -0,0 +1,241 @@
+//! Ultimate tic-tac-toe
+//!
+//! The game is played on a 3×3 grid of [GameBoard]s. The space a player claims decides which
+//! board the opponent must play in next: claiming the top right space of any board sends the
+//! opponent to the top right board. If that board is already finished, the opponent may play
+//! in any unfinished board. Winning a board claims it on the large board, and the first player
+//! to claim three boards in a row wins the game.
+
+use crate::{
+    active_player::ActivePlayer,
+    gameboard::{GameBoard, BoardSpace, BoardSpaceLocation},
+    game_outcome::{GameOutcome, WinPosition}
+};
+
+use super::{Variant, Move, Outcome, BoardView, CellView, CellStyle, space_index, space_location};
+
+/// A game of Ultimate tic-tac-toe
+///
+/// Boards and the spaces within them are numbered from 0 to 8, row by row from the top left.
+/// The space `space` of board `board` is cell `board * 9 + space`.
+#[derive(Clone)]
+pub struct Ultimate {
+    boards: [GameBoard; 9],
+    active_player: ActivePlayer,
+    /// The board the active player must play in, if any
+    forced_board: Option<usize>
+}
+
+impl Ultimate {
+
+    /// Creates a new game, with `first_player` to move first
+    pub fn new(first_player: ActivePlayer) -> Self
+    {
+        Self{
+            boards: Default::default(),
+            active_player: first_player,
+            forced_board: None
+        }
+    }
+
+    /// Returns the boards the active player may play in
+    fn playable_boards(&self) -> Vec<usize>
+    {
+        if self.outcome().game_finished() {
+            return Vec::new();
+        }
+        match self.forced_board {
+            Some(board) => vec![board],
+            None => (0..9).filter(|board|{
+                !self.boards[*board].game_outcome().game_finished()
+            }).collect()
+        }
+    }
+
+    /// Returns the player that won the given board, if any
+    fn board_winner(&self, board: usize) -> Option<ActivePlayer>
+    {
+        match self.boards[board].game_outcome() {
+            GameOutcome::PlayerX(_) => Some(ActivePlayer::PlayerX),
+            GameOutcome::PlayerO(_) => Some(ActivePlayer::PlayerO),
+            GameOutcome::Draw | GameOutcome::Incomplete => None
+        }
+    }
+
+    /// Returns the row, column or diagonal of boards that won the game, if any
+    fn winning_line(&self) -> Option<(WinPosition, ActivePlayer)>
+    {
+        WinPosition::all().find_map(|win_position|{
+            let mut winners = win_position.as_board_spaces().iter().map(|location|{
+                self.board_winner(space_index(*location))
+            });
+            let first = winners.next()??;
+            winners.all(|winner|{winner.as_ref() == Some(&first)}).then_some((win_position, first))
+        })
+    }
+}
+
+impl Variant for Ultimate {
+
+    fn active_player(&self) -> ActivePlayer
+    {
+        self.active_player.clone()
+    }
+
+    fn legal_moves(&self) -> Vec<Move>
+    {
+        self.playable_boards().into_iter().flat_map(|board|{
+            self.boards[board].all_spaces()
+                .filter(|(_, space)|{**space == BoardSpace::Empty})
+                .map(move |(location, _)|{Move::Place(board * 9 + space_index(location))})
+                .collect::<Vec<_>>()
+        }).collect()
+    }
+
+    fn play_move(&mut self, game_move: &Move)
+    {
+        let cell = game_move.cell();
+        let (board, space) = (cell / 9, cell % 9);
+        *self.boards[board].space_mut(space_location(space)) = self.active_player.get_board_space();
+
+        // the opponent plays in the board matching the space just claimed, unless it is finished
+        self.forced_board = if self.boards[space].game_outcome().game_finished() {
+            None
+        } else {
+            Some(space)
+        };
+        self.active_player.switch();
+    }
+
+    fn outcome(&self) -> Outcome
+    {
+        if let Some((_, winner)) = self.winning_line() {
+            return Outcome::Win(winner);
+        }
+        if self.boards.iter().all(|board|{board.game_outcome().game_finished()}) {
+            Outcome::Draw
+        } else {
+            Outcome::Incomplete
+        }
+    }
+
+    fn heuristic(&self, player: &ActivePlayer) -> f64
+    {
+        // claimed boards in lines that can still be won are worth the most,
+        // followed by pieces in lines that can still be won within each board
+        let mut score = 0.0;
+
+        for win_position in WinPosition::all() {
+            let boards = win_position.as_board_spaces().map(space_index);
+            score += 3.0 * line_value(boards.iter().map(|board|{
+                match self.board_winner(*board) {
+                    Some(winner) if winner == *player => Some(1),
+                    Some(_) => Some(-1),
+                    // a drawn board can never be claimed
+                    None if self.boards[*board].game_outcome() == GameOutcome::Draw => None,
+                    None => Some(0)
+                }
+            }));
+        }
+
+        for board in &self.boards {
+            if board.game_outcome().game_finished() {
+                continue;
+            }
+            for win_position in WinPosition::all() {
+                score += 0.5 * line_value(win_position.as_board_spaces().iter().map(|location|{
+                    match board.space(*location) {
+                        BoardSpace::Empty => Some(0),
+                        space if *space == player.get_board_space() => Some(1),
+                        _ => Some(-1)
+                    }
+                }));
+            }
+        }
+
+        (score / 10.0).tanh()
+    }
+
+    fn boards(&self) -> Vec<BoardView>
+    {
+        let playable_boards = self.playable_boards();
+        let winning_line = self.winning_line();
+
+        (0..9).map(|board|{
+            let win_position = match self.boards[board].game_outcome() {
+                GameOutcome::PlayerX(win_position) | GameOutcome::PlayerO(win_position) => Some(win_position),
+                GameOutcome::Draw | GameOutcome::Incomplete => None
+            };
+            let inactive = !playable_boards.contains(&board) && !self.outcome().game_finished();
+
+            let cells = BoardSpaceLocation::all().map(|location|{
+                let winning = win_position.is_some_and(|win_position|{
+                    win_position.as_board_spaces().contains(&location)
+                });
+                CellView{
+                    cell: board * 9 + space_index(location),
+                    symbol: self.boards[board].space(location).get_char(),
+                    subscript: None,
+                    style: if winning {
+                        CellStyle::Winning
+                    } else if inactive {
+                        CellStyle::Inactive
+                    } else {
+                        CellStyle::Normal
+                    }
+                }
+            }).collect();
+
+            let in_winning_line = winning_line.as_ref().is_some_and(|(win_position, _)|{
+                win_position.as_board_spaces().contains(&space_location(board))
+            });
+
+            BoardView{
+                position: ((board % 3) as u16, (board / 3) as u16),
+                columns: 3,
+                cells,
+                style: if in_winning_line {
+                    CellStyle::Winning
+                } else if inactive {
+                    CellStyle::Inactive
+                } else {
+                    CellStyle::Normal
+                },
+                label: Some(match (self.board_winner(board), self.boards[board].game_outcome()) {
+                    (Some(winner), _) => format!("{} won", winner.get_char()),
+                    (None, GameOutcome::Draw) => "draw".to_owned(),
+                    _ if playable_boards.contains(&board) && playable_boards.len() == 1 => "play here".to_owned(),
+                    _ => String::new()
+                })
+            }
+        }).collect()
+    }
+
+    fn clone_variant(&self) -> Box<dyn Variant>
+    {
+        Box::new(self.clone())
+    }
+}
+
+/// Scores a line of three values, where `Some(1)` is ours, `Some(-1)` is the opponent's,
+/// `Some(0)` is open and `None` can never be claimed
+///
+/// Lines that either player can no longer complete are worth nothing
+fn line_value(values: impl Iterator<Item = Option<i8>>) -> f64
+{
+    let mut ours = 0;
+    let mut theirs = 0;
+    for value in values {
+        match value {
+            Some(1) => ours += 1,
+            Some(-1) => theirs += 1,
+            Some(_) => {},
+            None => return 0.0
+        }
+    }
+    match (ours, theirs) {
+        (ours, 0) => [0.0, 1.0, 4.0, 16.0][ours],
+        (0, theirs) => -[0.0, 1.0, 4.0, 16.0][theirs],
+        _ => 0.0
+    }
+}
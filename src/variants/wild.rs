@@ -0,0 +1,132 @@
+//! Wild tic-tac-toe, where either player may place either symbol
+//!
+//! On each turn, the active player places an X or an O in any empty space. Whoever completes a
+//! line of three matching symbols wins, no matter which symbol it is.
+
+use crate::{
+    active_player::ActivePlayer,
+    gameboard::{GameBoard, BoardSpace, BoardSpaceLocation},
+    game_outcome::{GameOutcome, WinPosition}
+};
+
+use super::{Variant, Move, Outcome, BoardView, CellView, CellStyle, space_index, space_location};
+
+/// A game of Wild tic-tac-toe
+///
+/// Spaces are numbered from 0 to 8, row by row from the top left.
+#[derive(Clone)]
+pub struct Wild {
+    board: GameBoard,
+    active_player: ActivePlayer
+}
+
+impl Wild {
+
+    /// Creates a new game, with `first_player` to move first
+    pub fn new(first_player: ActivePlayer) -> Self
+    {
+        Self{
+            board: GameBoard::new(),
+            active_player: first_player
+        }
+    }
+
+    /// Returns the completed line, if any
+    fn win_position(&self) -> Option<WinPosition>
+    {
+        match self.board.game_outcome() {
+            GameOutcome::PlayerX(win_position) | GameOutcome::PlayerO(win_position) => Some(win_position),
+            GameOutcome::Draw | GameOutcome::Incomplete => None
+        }
+    }
+}
+
+impl Variant for Wild {
+
+    fn active_player(&self) -> ActivePlayer
+    {
+        self.active_player.clone()
+    }
+
+    fn legal_moves(&self) -> Vec<Move>
+    {
+        if self.outcome().game_finished() {
+            return Vec::new();
+        }
+        self.board.all_spaces()
+            .filter(|(_, space)|{**space == BoardSpace::Empty})
+            .flat_map(|(location, _)|{
+                [BoardSpace::X, BoardSpace::O].map(|symbol|{Move::PlaceSymbol(space_index(location), symbol)})
+            })
+            .collect()
+    }
+
+    fn play_move(&mut self, game_move: &Move)
+    {
+        let Move::PlaceSymbol(cell, symbol) = game_move else {
+            return;
+        };
+        *self.board.space_mut(space_location(*cell)) = symbol.clone();
+        self.active_player.switch();
+    }
+
+    fn outcome(&self) -> Outcome
+    {
+        // the line was completed by the player who moved last
+        match self.board.game_outcome().credited_to(&self.active_player.opposite()) {
+            GameOutcome::PlayerX(_) => Outcome::Win(ActivePlayer::PlayerX),
+            GameOutcome::PlayerO(_) => Outcome::Win(ActivePlayer::PlayerO),
+            GameOutcome::Draw => Outcome::Draw,
+            GameOutcome::Incomplete => Outcome::Incomplete
+        }
+    }
+
+    fn heuristic(&self, player: &ActivePlayer) -> f64
+    {
+        // any line with two matching symbols and an empty space can be completed by the next
+        // player to move, whoever placed the symbols
+        let threat = WinPosition::all().any(|win_position|{
+            let spaces = win_position.as_board_spaces().map(|location|{self.board.space(location)});
+            let empty = spaces.iter().filter(|space|{***space == BoardSpace::Empty}).count();
+            let x = spaces.iter().filter(|space|{***space == BoardSpace::X}).count();
+            empty == 1 && (x == 2 || x == 0)
+        });
+
+        match (threat, *player == self.active_player) {
+            (false, _) => 0.0,
+            (true, true) => 1.0,
+            (true, false) => -1.0
+        }
+    }
+
+    fn boards(&self) -> Vec<BoardView>
+    {
+        let win_position = self.win_position();
+
+        let cells = BoardSpaceLocation::all().map(|location|{
+            CellView{
+                cell: space_index(location),
+                symbol: self.board.space(location).get_char(),
+                subscript: None,
+                style: if win_position.is_some_and(|win_position|{win_position.as_board_spaces().contains(&location)}) {
+                    CellStyle::Winning
+                } else {
+                    CellStyle::Normal
+                }
+            }
+        }).collect();
+
+        vec![BoardView{
+            position: (0, 0),
+            columns: 3,
+            cells,
+            style: CellStyle::Normal,
+            label: None
+        }]
+    }
+
+    fn clone_variant(&self) -> Box<dyn Variant>
+    {
+        Box::new(self.clone())
+    }
+}
@@ -0,0 +1,181 @@
+//! Plays games on a lobby server through local TCP clients, as described in the
+//! [protocol documentation](tactic::server)
+
+use std::{
+    io::{BufRead, BufReader, Write},
+    net::{SocketAddr, TcpListener, TcpStream},
+    thread,
+    time::{Duration, Instant}
+};
+
+use tactic::server::{self, records::Records};
+
+/// How long to wait for a reply before failing the test
+const REPLY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Starts a server with no saved records on a free port, returning its address
+fn start_server() -> SocketAddr
+{
+    let listener = TcpListener::bind("127.0.0.1:0").expect("should bind to a free port");
+    let address = listener.local_addr().expect("listener should have an address");
+    thread::spawn(move ||{server::run(listener, Records::new())});
+    address
+}
+
+/// A client connected to the server
+struct Client {
+    stream: TcpStream,
+    reader: BufReader<TcpStream>
+}
+
+impl Client {
+
+    /// Connects to the server and logs in with the given name
+    fn login(address: SocketAddr, name: &str) -> Self
+    {
+        let stream = TcpStream::connect(address).expect("should connect to the server");
+        stream.set_read_timeout(Some(REPLY_TIMEOUT)).expect("should set a read timeout");
+        let reader = BufReader::new(stream.try_clone().expect("should clone the stream"));
+        let mut client = Self{stream, reader};
+        client.send(&format!("LOGIN {}", name));
+        client.expect(&format!("WELCOME {}", name));
+        client
+    }
+
+    /// Sends a command to the server
+    fn send(&mut self, command: &str)
+    {
+        writeln!(self.stream, "{}", command).expect("should send a command");
+    }
+
+    /// Returns the next reply from the server, without its line ending
+    fn reply(&mut self) -> String
+    {
+        let mut line = String::new();
+        self.reader.read_line(&mut line).expect("should receive a reply");
+        line.trim_end().to_owned()
+    }
+
+    /// Checks that the next reply from the server is the given one
+    fn expect(&mut self, reply: &str)
+    {
+        assert_eq!(self.reply(), reply);
+    }
+
+    /// Sends a move, checking that both clients are sent the board with the given spaces
+    /// and the given player to move
+    fn play(&mut self, command: &str, opponent: &mut Client, board: &str)
+    {
+        self.send(command);
+        self.expect(board);
+        opponent.expect(board);
+    }
+}
+
+#[test]
+fn players_are_paired_and_their_records_kept()
+{
+    let address = start_server();
+    let mut alice = Client::login(address, "alice");
+    let mut bob = Client::login(address, "bob");
+
+    alice.send("PLAY");
+    alice.expect("QUEUED");
+    bob.send("LIST");
+    bob.expect("WAITING alice");
+
+    // the player who waited plays X
+    bob.send("play");
+    alice.expect("START bob X");
+    bob.expect("START alice O");
+    alice.expect("BOARD --------- X");
+    bob.expect("BOARD --------- X");
+
+    bob.send("MOVE 1 1");
+    bob.expect("ERROR it is not your turn");
+    alice.play("MOVE 0 0", &mut bob, "BOARD X-------- O");
+    bob.send("MOVE 0 0");
+    bob.expect("ERROR that space is taken");
+    bob.play("MOVE 0 1", &mut alice, "BOARD X--O----- X");
+    alice.play("MOVE 1 0", &mut bob, "BOARD XX-O----- O");
+    bob.play("MOVE 1 1", &mut alice, "BOARD XX-OO---- X");
+    alice.play("MOVE 2 0", &mut bob, "BOARD XXXOO---- O");
+    alice.expect("RESULT win");
+    bob.expect("RESULT loss");
+
+    alice.send("RECORD");
+    alice.expect("RECORD alice 1 0 0");
+    alice.send("RECORD bob");
+    alice.expect("RECORD bob 0 0 1");
+
+    bob.send("QUIT");
+    bob.expect("BYE");
+}
+
+#[test]
+fn disconnecting_resigns_the_game()
+{
+    let address = start_server();
+    let mut carol = Client::login(address, "carol");
+    let mut dave = Client::login(address, "dave");
+
+    carol.send("PLAY");
+    carol.expect("QUEUED");
+    dave.send("PLAY carol");
+    carol.expect("START dave X");
+    dave.expect("START carol O");
+    carol.expect("BOARD --------- X");
+    dave.expect("BOARD --------- X");
+    carol.play("MOVE 1 1", &mut dave, "BOARD ----X---- O");
+
+    drop(dave);
+    carol.expect("RESULT win");
+    carol.send("RECORD dave");
+    carol.expect("RECORD dave 0 0 1");
+}
+
+#[test]
+fn players_can_play_the_ai()
+{
+    let address = start_server();
+    let mut erin = Client::login(address, "erin");
+
+    erin.send("PLAY AI 50");
+    erin.expect("START AI X");
+    erin.expect("BOARD --------- X");
+    erin.send("MOVE 1 1");
+    erin.expect("BOARD ----X---- O");
+
+    // the AI replies with a move of its own
+    let reply = erin.reply();
+    let fields: Vec<&str> = reply.split_whitespace().collect();
+    assert!(matches!(fields[..], ["BOARD", spaces, "X"] if spaces.matches('O').count() == 1),
+        "the AI should have placed one piece, not replied '{}'", reply);
+
+    // leaving mid-game forfeits it, which is recorded once the server notices
+    drop(erin);
+    let mut frank = Client::login(address, "frank");
+    let deadline = Instant::now() + REPLY_TIMEOUT;
+    loop {
+        frank.send("RECORD erin");
+        let record = frank.reply();
+        if record == "RECORD erin 0 0 1" {
+            break;
+        }
+        assert!(Instant::now() < deadline, "erin's loss wasn't recorded: '{}'", record);
+        thread::sleep(Duration::from_millis(20));
+    }
+}
+
+#[test]
+fn long_lines_are_dropped()
+{
+    let address = start_server();
+    let mut grace = Client::login(address, "grace");
+
+    grace.send(&"A".repeat(10_000));
+    grace.expect("ERROR line too long");
+    // the connection still works afterwards
+    grace.send("LIST");
+    grace.expect("WAITING");
+}